@@ -0,0 +1,27 @@
+//! Helpers for parsing command-line arguments - there's just a handful of
+//! them, so they're picked out of `env::args()` by hand.
+
+use crate::START_SCENE;
+use sdf_playground_common::SCENES;
+
+/// Returns the value following given flag, e.g. `value(args, "--log")` for
+/// `--log debug`.
+pub fn value<'a>(
+    args: &'a [String],
+    flag: &str,
+) -> Option<&'a str> {
+    args.iter()
+        .skip_while(|arg| *arg != flag)
+        .nth(1)
+        .map(|arg| arg.as_str())
+}
+
+/// Returns the scene picked with `--scene <id>` (clamped to the existing
+/// ones), or the starting scene, if none has been picked.
+pub fn scene(args: &[String]) -> u32 {
+    value(args, "--scene")
+        .and_then(|scene| scene.parse().ok())
+        .map_or(START_SCENE, |scene: u32| {
+            scene.clamp(1, SCENES)
+        })
+}
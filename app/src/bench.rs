@@ -0,0 +1,215 @@
+//! Benchmark mode (`--bench`) - renders each scene for a fixed number of frames
+//! and reports how long a frame took on average.
+//!
+//! Each scene is rendered twice - with the adaptive, screen-space threshold of
+//! marching and with a fixed epsilon (see [`Params::epsilon`]), so that both
+//! approaches can be compared.
+//!
+//! Rendering happens into an off-screen texture of fixed size, so the results
+//! don't depend on the window's size nor on vsync.
+//!
+//! Frames are timed on the GPU, with timestamp queries (see [`GpuTimings`]);
+//! GPUs that don't support them get timed with the wall clock, which also
+//! includes the overhead of submitting the frame and waiting for it.
+
+use crate::gpu::Gpu;
+use crate::gpu_timings::GpuTimings;
+use crate::headless::Headless;
+use crate::renderer::Renderer;
+use log::warn;
+use sdf_playground_common::{Params, SCENES};
+use std::time::{Duration, Instant};
+use winit::event_loop::EventLoop;
+
+const WIDTH: u32 = 1280;
+const HEIGHT: u32 = 720;
+
+/// Frames rendered before we start measuring, so that shader compilation (by
+/// the driver), clock ramp-up etc. don't skew the results.
+const WARMUP_FRAMES: u32 = 30;
+
+/// Frames measured for each scene.
+const FRAMES: u32 = 300;
+
+/// Ways of marching compared for each scene, as values of [`Params::epsilon`].
+const MARCHES: [(&str, f32); 2] =
+    [("adaptive", 0.0), ("fixed", 0.001)];
+
+pub fn run(event_loop: &EventLoop<()>, json: bool) {
+    let Headless {
+        gpu,
+        mut renderer,
+        target,
+        ..
+    } = Headless::new(
+        event_loop,
+        "bench_target",
+        WIDTH,
+        HEIGHT,
+    );
+
+    let mut gpu_timings = GpuTimings::new(&gpu);
+
+    if !gpu_timings.supported() {
+        warn!(
+            "GPU doesn't support timestamp queries - falling back to \
+             wall-clock time, which overestimates frame times"
+        );
+    }
+
+    let results: Vec<_> = (1..=SCENES)
+        .flat_map(|scene| {
+            MARCHES.map(|march| (scene, march))
+        })
+        .map(|(scene, (march, epsilon))| {
+            let mut params = Params {
+                scene,
+                epsilon,
+                ..Params::new(WIDTH, HEIGHT)
+            };
+
+            let mut frames = Vec::new();
+
+            for frame in 0..(WARMUP_FRAMES + FRAMES) {
+                // Advancing time at a fixed rate makes the runs repeatable
+                params.time = frame as f32 / 60.0;
+                params.frame = frame;
+
+                let started_at = Instant::now();
+
+                render(
+                    &gpu,
+                    &mut renderer,
+                    &mut gpu_timings,
+                    &target,
+                    &params,
+                );
+
+                let elapsed =
+                    gpu_timings.total().unwrap_or_else(
+                        || started_at.elapsed(),
+                    );
+
+                if frame >= WARMUP_FRAMES {
+                    frames.push(elapsed);
+                }
+            }
+
+            Stats::new(scene, march, &mut frames)
+        })
+        .collect();
+
+    if json {
+        print_json(&results);
+    } else {
+        print_table(&results);
+    }
+}
+
+/// Renders a single frame and waits until the GPU is done with it, and with
+/// downloading its timings.
+fn render(
+    gpu: &Gpu,
+    renderer: &mut Renderer,
+    gpu_timings: &mut GpuTimings,
+    target: &wgpu::TextureView,
+    params: &Params,
+) {
+    renderer.update(gpu, params);
+
+    let mut encoder = gpu.device().create_command_encoder(
+        &wgpu::CommandEncoderDescriptor {
+            label: Some("bench_command_encoder"),
+        },
+    );
+
+    gpu_timings.begin(&mut encoder);
+    renderer.render(&mut encoder, target, 1.0, None);
+    gpu_timings.mark(&mut encoder, "scene");
+    gpu_timings.finish(&mut encoder);
+
+    gpu.queue().submit(Some(encoder.finish()));
+    gpu.device().poll(wgpu::Maintain::Wait);
+
+    // (first call starts the download, the second one finishes it)
+    gpu_timings.poll(gpu.device());
+    gpu.device().poll(wgpu::Maintain::Wait);
+    gpu_timings.poll(gpu.device());
+}
+
+/// Frame times of a single scene, in milliseconds.
+#[derive(Debug)]
+struct Stats {
+    scene: u32,
+    march: &'static str,
+    mean: f32,
+    median: f32,
+    min: f32,
+    max: f32,
+}
+
+impl Stats {
+    fn new(
+        scene: u32,
+        march: &'static str,
+        frames: &mut [Duration],
+    ) -> Self {
+        frames.sort();
+
+        let ms =
+            |frame: &Duration| frame.as_secs_f32() * 1000.0;
+
+        Self {
+            scene,
+            march,
+            mean: frames.iter().map(ms).sum::<f32>()
+                / frames.len() as f32,
+            median: ms(&frames[frames.len() / 2]),
+            min: ms(&frames[0]),
+            max: ms(&frames[frames.len() - 1]),
+        }
+    }
+}
+
+fn print_table(results: &[Stats]) {
+    println!(
+        "{:>5}  {:>8}  {:>9}  {:>9}  {:>9}  {:>9}",
+        "scene",
+        "march",
+        "mean ms",
+        "median ms",
+        "min ms",
+        "max ms"
+    );
+
+    for stats in results {
+        println!(
+            "{:>5}  {:>8}  {:>9.3}  {:>9.3}  {:>9.3}  {:>9.3}",
+            stats.scene,
+            stats.march,
+            stats.mean,
+            stats.median,
+            stats.min,
+            stats.max
+        );
+    }
+}
+
+fn print_json(results: &[Stats]) {
+    let results: Vec<_> = results
+        .iter()
+        .map(|stats| {
+            format!(
+                r#"{{"scene":{},"march":"{}","mean_ms":{},"median_ms":{},"min_ms":{},"max_ms":{}}}"#,
+                stats.scene,
+                stats.march,
+                stats.mean,
+                stats.median,
+                stats.min,
+                stats.max
+            )
+        })
+        .collect();
+
+    println!("[{}]", results.join(","));
+}
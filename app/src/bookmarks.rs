@@ -0,0 +1,267 @@
+//! Camera bookmarks (`bookmarks.toml`, or whatever `SDF_PLAYGROUND_BOOKMARKS`
+//! points at) - `Shift+F5`..`Shift+F8` store the current camera, `F5`..`F8`
+//! fly back to it, so that a change can be compared from the same couple of
+//! angles.
+//!
+//! A bookmark is the camera's [`Params::orbit`] and its framing (see
+//! [`Params::framing`] - the point it looks at and its distance from it);
+//! bookmarks can be given names by editing the file:
+//!
+//! ```toml
+//! [bookmark_1]
+//! name = "front"
+//! orbit = 0.0
+//! framing = [0.0, 0.0, 0.0, 5.0]
+//! ```
+//!
+//! Bookmarks without `framing` keep the current one.
+
+use crate::framing;
+use log::{error, info, warn};
+use sdf_playground_common::glam::Vec4;
+use sdf_playground_common::Params;
+use std::f32::consts::{PI, TAU};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use std::{env, fs};
+
+/// Number of bookmarks.
+pub const BOOKMARKS: u32 = 4;
+
+/// How long it takes to fly to a bookmark.
+const TRANSITION: Duration = Duration::from_millis(800);
+
+#[derive(Debug)]
+pub struct Bookmarks {
+    path: PathBuf,
+    transition: Option<Transition>,
+}
+
+#[derive(Clone, Copy, Debug)]
+struct Transition {
+    from: f32,
+    to: f32,
+    framing: Option<(Vec4, Vec4)>,
+    elapsed: Duration,
+}
+
+impl Bookmarks {
+    pub fn new() -> Self {
+        let path = env::var_os("SDF_PLAYGROUND_BOOKMARKS")
+            .map(PathBuf::from)
+            .unwrap_or_else(|| {
+                Path::new(env!("CARGO_MANIFEST_DIR"))
+                    .parent()
+                    .unwrap()
+                    .join("bookmarks.toml")
+            });
+
+        Self {
+            path,
+            transition: None,
+        }
+    }
+
+    /// Stores the current camera in given bookmark (`1..=BOOKMARKS`), keeping
+    /// its name (and the rest of the file) intact.
+    pub fn save(&self, bookmark: u32, params: &Params) {
+        let mut doc = match self.load() {
+            Ok(doc) => doc,
+
+            Err(err) => {
+                error!(
+                    "Couldn't load {}: {err}",
+                    self.path.display()
+                );
+                return;
+            }
+        };
+
+        let key = format!("bookmark_{bookmark}");
+
+        if !doc.contains_table(&key) {
+            doc[&key] = toml_edit::table();
+            doc[&key]["name"] = toml_edit::value(format!(
+                "bookmark {bookmark}"
+            ));
+        }
+
+        // (rounded, so that the file stays readable)
+        let orbit =
+            (wrap(params.orbit) as f64 * 1e4).round() / 1e4;
+
+        doc[&key]["orbit"] = toml_edit::value(orbit);
+
+        doc[&key]["framing"] = toml_edit::value(
+            framing::current(params)
+                .to_array()
+                .into_iter()
+                .map(|value| {
+                    (value as f64 * 1e4).round() / 1e4
+                })
+                .collect::<toml_edit::Array>(),
+        );
+
+        match fs::write(&self.path, doc.to_string()) {
+            Ok(()) => {
+                info!(
+                    "Camera stored as `{}`",
+                    name(&doc, &key)
+                );
+            }
+
+            Err(err) => {
+                error!(
+                    "Couldn't write {}: {err}",
+                    self.path.display()
+                );
+            }
+        }
+    }
+
+    /// Starts flying the camera to given bookmark (`1..=BOOKMARKS`).
+    pub fn jump(&mut self, bookmark: u32, params: &Params) {
+        let doc = match self.load() {
+            Ok(doc) => doc,
+
+            Err(err) => {
+                error!(
+                    "Couldn't load {}: {err}",
+                    self.path.display()
+                );
+                return;
+            }
+        };
+
+        let key = format!("bookmark_{bookmark}");
+
+        let orbit = doc
+            .get(&key)
+            .and_then(|bookmark| bookmark.get("orbit"))
+            .and_then(|orbit| {
+                orbit.as_float().or_else(|| {
+                    orbit.as_integer().map(|o| o as f64)
+                })
+            });
+
+        let framing = doc
+            .get(&key)
+            .and_then(|bookmark| bookmark.get("framing"))
+            .and_then(|framing| framing.as_array())
+            .and_then(|framing| {
+                let values: Vec<_> = framing
+                    .iter()
+                    .map(|value| {
+                        value.as_float().or_else(|| {
+                            value
+                                .as_integer()
+                                .map(|v| v as f64)
+                        })
+                    })
+                    .collect::<Option<_>>()?;
+
+                match values[..] {
+                    [x, y, z, w] if w > 0.0 => {
+                        Some(Vec4::new(
+                            x as f32, y as f32, z as f32,
+                            w as f32,
+                        ))
+                    }
+                    _ => None,
+                }
+            });
+
+        let Some(orbit) = orbit else {
+            warn!(
+                "Bookmark {bookmark} is empty - press Shift with its key to store the camera there"
+            );
+            return;
+        };
+
+        info!("Flying to `{}`", name(&doc, &key));
+
+        // Let's take the shorter way around
+        let from = wrap(params.orbit);
+        let mut to = orbit as f32;
+
+        if to - from > PI {
+            to -= TAU;
+        } else if from - to > PI {
+            to += TAU;
+        }
+
+        self.transition = Some(Transition {
+            from,
+            to,
+            framing: framing
+                .map(|to| (framing::current(params), to)),
+            elapsed: Duration::ZERO,
+        });
+    }
+
+    /// Stops the flight, if one is in progress - e.g. when the camera gets
+    /// moved by hand.
+    pub fn cancel(&mut self) {
+        self.transition = None;
+    }
+
+    /// Moves the camera along the flight, if one is in progress.
+    pub fn update(
+        &mut self,
+        params: &mut Params,
+        frame_time: Duration,
+    ) {
+        let Some(transition) = &mut self.transition else {
+            return;
+        };
+
+        transition.elapsed += frame_time;
+
+        let t = (transition.elapsed.as_secs_f32()
+            / TRANSITION.as_secs_f32())
+        .min(1.0);
+
+        // Smoothstep, so that the camera eases in and out
+        let t = t * t * (3.0 - 2.0 * t);
+
+        params.orbit = transition.from
+            + (transition.to - transition.from) * t;
+
+        if let Some((from, to)) = transition.framing {
+            params.framing = from.lerp(to, t);
+        }
+
+        if transition.elapsed >= TRANSITION {
+            self.transition = None;
+        }
+    }
+
+    fn load(&self) -> Result<toml_edit::Document, String> {
+        if !self.path.exists() {
+            return Ok(Default::default());
+        }
+
+        fs::read_to_string(&self.path)
+            .map_err(|err| err.to_string())?
+            .parse()
+            .map_err(|err: toml_edit::TomlError| {
+                err.to_string()
+            })
+    }
+}
+
+/// Returns name of given bookmark, falling back to its key.
+fn name<'a>(
+    doc: &'a toml_edit::Document,
+    key: &'a str,
+) -> &'a str {
+    doc.get(key)
+        .and_then(|bookmark| bookmark.get("name"))
+        .and_then(|name| name.as_str())
+        .unwrap_or(key)
+}
+
+/// Brings given angle into `-PI..=PI`.
+fn wrap(angle: f32) -> f32 {
+    (angle + PI).rem_euclid(TAU) - PI
+}
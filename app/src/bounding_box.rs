@@ -0,0 +1,162 @@
+//! Bounding box estimation - samples the scene's distance field (the host-
+//! compiled one, see [`sdf_playground_shader::scene()`]) over a coarse grid
+//! and returns the box its surface fits in, so that the application can tell
+//! where the scene is without relying on hand-written [`bounds()`].
+//!
+//! Since a distance field says how far the nearest surface is, a grid cell
+//! whose center is farther away from the surface than the cell's half-diagonal
+//! can't contain any part of it - so the remaining cells are a conservative
+//! estimate of where the surface is, refined by repeating the process within
+//! them a couple of times.
+//!
+//! Note that this relies on the distance field not overestimating distances -
+//! that's true for exact fields, but scenes that get creative with their
+//! distances (e.g. scene 4) might end up a bit outside of the estimate.
+//!
+//! [`bounds()`]: sdf_playground_shader::bounds()
+
+use sdf_playground_common::glam::{vec3, UVec3, Vec3};
+use sdf_playground_common::Params;
+use sdf_playground_shader::scene;
+
+/// Half of the size of the initial grid - scenes spanning beyond it are
+/// considered unbounded.
+const EXTENT: f32 = 32.0;
+
+/// Number of cells along each axis of the grid.
+const CELLS: u32 = 24;
+
+/// How many times the grid gets refined.
+const PASSES: u32 = 3;
+
+/// Moments at which the scene is sampled (offsets from the current time, in
+/// seconds) - so that the estimate covers the animations as well, at least the
+/// ones that repeat quicker than this.
+const MOMENTS: [f32; 8] =
+    [0.0, 0.5, 1.0, 1.5, 2.0, 2.5, 3.0, 3.5];
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BoundingBox {
+    pub min: Vec3,
+    pub max: Vec3,
+}
+
+impl BoundingBox {
+    pub fn center(&self) -> Vec3 {
+        (self.min + self.max) / 2.0
+    }
+
+    pub fn size(&self) -> Vec3 {
+        self.max - self.min
+    }
+
+    /// Returns all eight corners of the box - the `n`th bit of the index says
+    /// whether the `n`th coordinate is `min` or `max`.
+    pub fn corners(&self) -> [Vec3; 8] {
+        let mut corners = [Vec3::ZERO; 8];
+
+        for (idx, corner) in corners.iter_mut().enumerate()
+        {
+            *corner = Vec3::select(
+                UVec3::new(
+                    idx as u32 & 1,
+                    idx as u32 & 2,
+                    idx as u32 & 4,
+                )
+                .cmpne(UVec3::ZERO),
+                self.max,
+                self.min,
+            );
+        }
+
+        corners
+    }
+}
+
+/// Estimates the box enclosing current scene; returns `None` if the scene is
+/// empty or unbounded (e.g. spans past [`EXTENT`]).
+pub fn estimate(params: &Params) -> Option<BoundingBox> {
+    let mut bb = BoundingBox {
+        min: Vec3::splat(-EXTENT),
+        max: Vec3::splat(EXTENT),
+    };
+
+    for pass in 0..PASSES {
+        let found = sample(params, bb)?;
+
+        // If the surface touches the initial grid's walls, it most likely
+        // continues past them
+        if pass == 0
+            && (found.min.cmple(bb.min).any()
+                || found.max.cmpge(bb.max).any())
+        {
+            return None;
+        }
+
+        bb = found;
+    }
+
+    Some(bb)
+}
+
+/// Returns the box enclosing all cells (of the grid spanning given box) that
+/// might contain the surface.
+fn sample(
+    params: &Params,
+    bb: BoundingBox,
+) -> Option<BoundingBox> {
+    let cell = bb.size() / CELLS as f32;
+    let half_diagonal = cell.length() / 2.0;
+    let mut found: Option<BoundingBox> = None;
+
+    for idx in 0..CELLS.pow(3) {
+        let cell_min = bb.min
+            + cell
+                * vec3(
+                    (idx % CELLS) as f32,
+                    (idx / CELLS % CELLS) as f32,
+                    (idx / CELLS / CELLS) as f32,
+                );
+
+        let center = cell_min + cell / 2.0;
+
+        // (the cell's size doubles as the level of detail - there's no point
+        // in computing anything smaller)
+        let distance = MOMENTS
+            .iter()
+            .map(|moment| {
+                let params = Params {
+                    time: params.time + moment,
+                    ..*params
+                };
+
+                scene(
+                    params.scene,
+                    params.scene_time(),
+                    &params.hot,
+                    center,
+                    cell.min_element(),
+                )
+            })
+            .fold(f32::MAX, f32::min);
+
+        if distance > half_diagonal {
+            continue;
+        }
+
+        let cell_max = cell_min + cell;
+
+        found = Some(match found {
+            Some(found) => BoundingBox {
+                min: found.min.min(cell_min),
+                max: found.max.max(cell_max),
+            },
+            None => BoundingBox {
+                min: cell_min,
+                max: cell_max,
+            },
+        });
+    }
+
+    found
+}
@@ -0,0 +1,404 @@
+//! Sketch bundles (`.sdfpkg`) - lets you share a complete, working sketch
+//! instead of pasting snippets of code:
+//!
+//! - `--export <path>` packs the shader crate, the script (which is where
+//!   scene and parameter presets live) and a thumbnail into a single file,
+//! - `--import <path>` unpacks such file over the current sketch, backing the
+//!   current one up first (as `import-backup.sdfpkg` in the cache directory).
+//!
+//! Bundles are plain zip archives, so they can be inspected (or assembled by
+//! hand) with any zip tool:
+//!
+//! ```text
+//! sdfpkg.toml         version and the scene the thumbnail shows
+//! shader/Cargo.toml   (ignored when importing)
+//! shader/src/**       sources of the shader crate
+//! script.rhai         (optional)
+//! thumbnail.png       (optional, ignored when importing)
+//! ```
+//!
+//! Note that only the shader crate is bundled - `common` and `sdflib` are
+//! assumed to match on both ends.
+//!
+//! Importing extracts only the shader's sources (and the script) - the
+//! manifest could pull in dependencies or a build script, i.e. code that runs
+//! on your machine during the next compilation, so the current one is kept.
+
+use crate::compiler::{cache_dir, files, shader_dir};
+use crate::image::encode_png;
+use crate::script::Script;
+use crate::{verify, Error};
+use flate2::read::DeflateDecoder;
+use flate2::write::DeflateEncoder;
+use flate2::Compression;
+use std::fs;
+use std::io::{self, Read, Write};
+use std::path::{Component, Path, PathBuf};
+
+/// Version of the bundle format, stored in `sdfpkg.toml` - bumped whenever
+/// bundles stop being compatible with older versions of the playground.
+const VERSION: i64 = 1;
+
+const MANIFEST: &str = "sdfpkg.toml";
+const SCRIPT: &str = "script.rhai";
+const THUMBNAIL: &str = "thumbnail.png";
+const SHADER_DIR: &str = "shader/";
+const SHADER_MANIFEST: &str = "shader/Cargo.toml";
+const SHADER_SRC_DIR: &str = "shader/src/";
+
+/// Packs the current sketch into given file, rendering given scene as its
+/// thumbnail.
+pub fn export(
+    path: &Path,
+    scene: u32,
+) -> Result<(), Error> {
+    let mut entries = vec![(
+        MANIFEST.to_owned(),
+        format!("version = {VERSION}\nscene = {scene}\n")
+            .into_bytes(),
+    )];
+
+    let crate_dir = shader_dir();
+
+    for file in files(&crate_dir) {
+        // (`files()` returns paths inside of `crate_dir`, so the prefix is
+        // there)
+        let name = file
+            .strip_prefix(&crate_dir)
+            .unwrap()
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/");
+
+        let bytes =
+            fs::read(&file).map_err(Error::io(&file))?;
+
+        entries
+            .push((format!("{SHADER_DIR}{name}"), bytes));
+    }
+
+    let script = Script::path();
+
+    if script.exists() {
+        let bytes = fs::read(&script)
+            .map_err(Error::io(&script))?;
+
+        entries.push((SCRIPT.to_owned(), bytes));
+    }
+
+    entries.push((THUMBNAIL.to_owned(), thumbnail(scene)));
+
+    fs::write(path, zip(&entries)).map_err(Error::io(path))
+}
+
+/// Unpacks given bundle over the current sketch, returning path of the backup
+/// made of the current sketch beforehand.
+pub fn import(path: &Path) -> Result<PathBuf, Error> {
+    let invalid = |reason: String| Error::Bundle {
+        path: path.to_owned(),
+        reason,
+    };
+
+    let bytes = fs::read(path).map_err(Error::io(path))?;
+    let files = unzip(&bytes).map_err(invalid)?;
+
+    // Let's validate everything before touching any file, so that a broken
+    // bundle doesn't leave a half-imported sketch behind
+    let manifest = files
+        .iter()
+        .find(|(name, _)| name == MANIFEST)
+        .ok_or_else(|| invalid(format!("no {MANIFEST}")))?;
+
+    let manifest = String::from_utf8_lossy(&manifest.1)
+        .parse::<toml_edit::Document>()
+        .map_err(|err| invalid(err.to_string()))?;
+
+    let version = manifest
+        .get("version")
+        .and_then(|version| version.as_integer());
+
+    if version != Some(VERSION) {
+        return Err(invalid(format!(
+            "unsupported version (expected {VERSION}) - \
+             was it exported by a newer playground?"
+        )));
+    }
+
+    for (name, _) in &files {
+        let known = name == MANIFEST
+            || name == SCRIPT
+            || name == THUMBNAIL
+            || name == SHADER_MANIFEST
+            || name.starts_with(SHADER_SRC_DIR);
+
+        // Paths come from whoever made the bundle, so they must not be able to
+        // point outside of the shader's directory
+        let safe = Path::new(name)
+            .components()
+            .all(|c| matches!(c, Component::Normal(_)));
+
+        if !known || !safe {
+            return Err(invalid(format!(
+                "unexpected file: {name}"
+            )));
+        }
+    }
+
+    let has_sources = files
+        .iter()
+        .any(|(name, _)| name.starts_with(SHADER_SRC_DIR));
+
+    if !has_sources {
+        return Err(invalid("no shader sources".into()));
+    }
+
+    let backup = cache_dir().join("import-backup.sdfpkg");
+
+    if let Some(dir) = backup.parent() {
+        fs::create_dir_all(dir).map_err(Error::io(dir))?;
+    }
+
+    export(&backup, crate::START_SCENE)?;
+
+    let crate_dir = shader_dir();
+    let src_dir = crate_dir.join("src");
+
+    // Files missing from the bundle would otherwise linger around (and
+    // possibly break the compilation)
+    match fs::remove_dir_all(&src_dir) {
+        Err(err)
+            if err.kind() != io::ErrorKind::NotFound =>
+        {
+            return Err(Error::io(&src_dir)(err));
+        }
+        _ => (),
+    }
+
+    for (name, bytes) in &files {
+        let dst = if name == SCRIPT {
+            Script::path()
+        } else if name.starts_with(SHADER_SRC_DIR) {
+            // (safe to unwrap, since `SHADER_SRC_DIR` starts with it)
+            crate_dir.join(
+                name.strip_prefix(SHADER_DIR).unwrap(),
+            )
+        } else {
+            continue;
+        };
+
+        if let Some(dir) = dst.parent() {
+            fs::create_dir_all(dir)
+                .map_err(Error::io(dir))?;
+        }
+
+        fs::write(&dst, bytes).map_err(Error::io(dst))?;
+    }
+
+    Ok(backup)
+}
+
+/// Renders given scene on the CPU (see `verify`) and encodes it as PNG.
+fn thumbnail(scene: u32) -> Vec<u8> {
+    let pixels = verify::render(
+        scene,
+        sdf_playground_shader::reference,
+    );
+
+    encode_png(
+        verify::WIDTH,
+        verify::HEIGHT,
+        png::ColorType::Rgba,
+        &pixels,
+    )
+}
+
+/// Creates a zip archive with given files, deflating each one.
+///
+/// Modification times are always set to 1980-01-01 (the earliest one zip can
+/// represent), so that exporting the same sketch twice yields the same bytes.
+fn zip(files: &[(String, Vec<u8>)]) -> Vec<u8> {
+    const DATE: u16 = (1 << 5) | 1;
+
+    let mut zip = Vec::new();
+    let mut central = Vec::new();
+
+    for (name, bytes) in files {
+        let mut encoder = DeflateEncoder::new(
+            Vec::new(),
+            Compression::default(),
+        );
+
+        // (writing into a `Vec` can't fail)
+        encoder.write_all(bytes).unwrap();
+
+        let data = encoder.finish().unwrap();
+        let offset = zip.len() as u32;
+
+        // Fields shared by the local and the central header: version needed,
+        // flags, method (deflate), time, date, crc, sizes and name's length
+        let mut common = Vec::new();
+
+        common.extend(20u16.to_le_bytes());
+        common.extend(0u16.to_le_bytes());
+        common.extend(8u16.to_le_bytes());
+        common.extend(0u16.to_le_bytes());
+        common.extend(DATE.to_le_bytes());
+        common.extend(crc32fast::hash(bytes).to_le_bytes());
+        common.extend((data.len() as u32).to_le_bytes());
+        common.extend((bytes.len() as u32).to_le_bytes());
+        common.extend((name.len() as u16).to_le_bytes());
+        common.extend(0u16.to_le_bytes());
+
+        zip.extend(0x04034b50u32.to_le_bytes());
+        zip.extend(&common);
+        zip.extend(name.as_bytes());
+        zip.extend(data);
+
+        central.extend(0x02014b50u32.to_le_bytes());
+        central.extend(20u16.to_le_bytes());
+        central.extend(&common);
+
+        // (comment's length, disk number and attributes)
+        central.extend([0; 10]);
+        central.extend(offset.to_le_bytes());
+        central.extend(name.as_bytes());
+    }
+
+    let central_offset = zip.len() as u32;
+
+    zip.extend(&central);
+    zip.extend(0x06054b50u32.to_le_bytes());
+    zip.extend([0; 4]);
+    zip.extend((files.len() as u16).to_le_bytes());
+    zip.extend((files.len() as u16).to_le_bytes());
+    zip.extend((central.len() as u32).to_le_bytes());
+    zip.extend(central_offset.to_le_bytes());
+    zip.extend(0u16.to_le_bytes());
+    zip
+}
+
+/// Extracts all files from given zip archive - supports just what zip tools
+/// produce by default (stored or deflated files, no encryption, no zip64),
+/// which is enough for bundles.
+fn unzip(
+    zip: &[u8],
+) -> Result<Vec<(String, Vec<u8>)>, String> {
+    let u16_at = |offset: usize| {
+        zip.get(offset..offset + 2)
+            .map(|b| {
+                u16::from_le_bytes([b[0], b[1]]) as usize
+            })
+            .ok_or_else(|| {
+                "unexpected end of file".to_owned()
+            })
+    };
+
+    let u32_at = |offset: usize| {
+        zip.get(offset..offset + 4)
+            .map(|b| {
+                u32::from_le_bytes([b[0], b[1], b[2], b[3]])
+            })
+            .ok_or_else(|| {
+                "unexpected end of file".to_owned()
+            })
+    };
+
+    // End of central directory is at the very end, followed only by an
+    // optional comment
+    let eocd = (0..zip.len().saturating_sub(21))
+        .rev()
+        .find(|&offset| u32_at(offset) == Ok(0x06054b50))
+        .ok_or("not a zip archive")?;
+
+    let count = u16_at(eocd + 10)?;
+    let mut offset = u32_at(eocd + 16)? as usize;
+    let mut files = Vec::with_capacity(count);
+
+    for _ in 0..count {
+        if u32_at(offset)? != 0x02014b50 {
+            return Err(
+                "corrupted central directory".into()
+            );
+        }
+
+        let flags = u16_at(offset + 8)?;
+        let method = u16_at(offset + 10)?;
+        let crc = u32_at(offset + 16)?;
+        let compressed_size = u32_at(offset + 20)? as usize;
+        let size = u32_at(offset + 24)? as usize;
+        let name_len = u16_at(offset + 28)?;
+        let extra_len = u16_at(offset + 30)?;
+        let comment_len = u16_at(offset + 32)?;
+        let local = u32_at(offset + 42)? as usize;
+
+        let name = zip
+            .get(offset + 46..offset + 46 + name_len)
+            .ok_or("unexpected end of file")?;
+
+        let name = String::from_utf8(name.to_vec())
+            .map_err(|_| "file name is not UTF-8")?;
+
+        offset += 46 + name_len + extra_len + comment_len;
+
+        if name.ends_with('/') {
+            continue;
+        }
+
+        if flags & 1 != 0 {
+            return Err(format!("{name} is encrypted"));
+        }
+
+        let start = local
+            + 30
+            + u16_at(local + 26)?
+            + u16_at(local + 28)?;
+
+        let data = zip
+            .get(start..start + compressed_size)
+            .ok_or("unexpected end of file")?;
+
+        let bytes = match method {
+            0 => data.to_vec(),
+
+            8 => {
+                // (the size comes from the archive, so it can't be trusted
+                // with preallocating memory either - but it can't be larger
+                // than what deflate is able to squeeze into the data)
+                let mut bytes = Vec::with_capacity(
+                    size.min(
+                        compressed_size
+                            .saturating_mul(1032),
+                    ),
+                );
+
+                // (`take()`, so that a malicious archive can't make us
+                // inflate gigabytes)
+                DeflateDecoder::new(data)
+                    .take(size as u64 + 1)
+                    .read_to_end(&mut bytes)
+                    .map_err(|err| {
+                        format!("{name}: {err}")
+                    })?;
+
+                bytes
+            }
+
+            method => {
+                return Err(format!(
+                    "{name} uses unsupported compression method {method}"
+                ));
+            }
+        };
+
+        if bytes.len() != size
+            || crc32fast::hash(&bytes) != crc
+        {
+            return Err(format!("{name} is corrupted"));
+        }
+
+        files.push((name, bytes));
+    }
+
+    Ok(files)
+}
@@ -1,75 +1,651 @@
-use log::{error, info};
+use crate::error::Error;
+use crate::history::History;
+use crate::verify::Fnv1a;
+use log::{error, info, warn};
 use spirv_builder::{MetadataPrintout, SpirvBuilder};
+use std::fmt;
+use std::hash::Hasher;
 use std::path::{Path, PathBuf};
+use std::process::Command;
 use std::sync::mpsc;
-use std::thread;
-use std::time::{Duration, SystemTime};
+use std::time::{Duration, Instant, SystemTime};
+use std::{env, fs, thread};
+
+/// How often the compiler checks the sources for changes - checking walks the
+/// shader, `common` and `sdflib` crates, so it's not free.
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
 
 #[derive(Debug)]
 pub struct Compiler {
-    rx: mpsc::Receiver<PathBuf>,
+    rx: mpsc::Receiver<CompilerEvent>,
+    cache: Cache,
+    history: Option<History>,
 }
 
 impl Compiler {
     pub fn spawn() -> Self {
         let (tx, rx) = mpsc::channel();
+        let cache = Cache::new();
 
-        thread::spawn(move || {
-            let mut previous_modified_at: Option<
-                SystemTime,
-            > = None;
+        thread::spawn({
+            let cache = cache.clone();
 
-            loop {
-                let crate_dir =
+            move || {
+                let root_dir =
                     Path::new(env!("CARGO_MANIFEST_DIR"))
                         .parent()
-                        .unwrap()
-                        .join("shader");
-
-                let modified_at = crate_dir
-                    .join("src")
-                    .join("lib.rs")
-                    .metadata()
-                    .unwrap()
-                    .modified()
-                    .unwrap();
-
-                if previous_modified_at
-                    .map_or(true, |p| p != modified_at)
-                {
+                        .unwrap();
+
+                let crate_dir = shader_dir();
+
+                // Shader depends on `common` (e.g. for `Params`) and `sdflib`,
+                // so changing them has to trigger a recompilation as well
+                let crate_dirs = [
+                    crate_dir.as_path(),
+                    &root_dir.join("common"),
+                    &root_dir.join("sdflib"),
+                ];
+
+                // Unoptimized modules of heavier scenes run noticeably slower, but
+                // it can be useful to turn optimizations off when debugging the
+                // compiler itself
+                let optimize =
+                    env::var("SDF_PLAYGROUND_OPTIMIZE")
+                        .map_or(true, |val| val != "0");
+
+                let mut previous_modified_at: Option<
+                    SystemTime,
+                > = None;
+
+                // Hash of the sources we've already handled - if the shader has
+                // been compiled during one of the previous runs, we can pick it up
+                // straight from the cache
+                let mut previous_hash = cache.hash();
+
+                // Complexity of the most recently compiled shader, to compare
+                // the next one against
+                let mut previous_complexity = None;
+
+                loop {
+                    let modified_at =
+                        modified_at(&crate_dirs);
+
+                    if previous_modified_at
+                        == Some(modified_at)
+                    {
+                        thread::sleep(POLL_INTERVAL);
+                        continue;
+                    }
+
+                    // Editors like to touch files even when nothing has changed
+                    // (e.g. when saving an unmodified buffer), so instead of
+                    // relying solely on mtimes, let's check the contents
+                    let hash = hash(&crate_dirs, optimize);
+
+                    if previous_modified_at.is_none()
+                        && previous_hash == Some(hash)
+                    {
+                        info!("Using cached shader");
+
+                        previous_complexity =
+                            Complexity::of(&cache.module());
+
+                        _ = tx.send(
+                            CompilerEvent::Succeeded {
+                                path: cache.module(),
+                                duration: Duration::ZERO,
+                                complexity:
+                                    previous_complexity,
+                                grown_from: None,
+                            },
+                        );
+                    }
+
+                    previous_modified_at =
+                        Some(modified_at);
+
+                    if previous_hash == Some(hash) {
+                        continue;
+                    }
+
                     info!("Compiling shader");
 
-                    let shader_path = SpirvBuilder::new(
-                        crate_dir,
+                    _ = tx.send(CompilerEvent::Compiling);
+
+                    let started_at = Instant::now();
+
+                    let result = SpirvBuilder::new(
+                        &crate_dir,
                         "spirv-unknown-vulkan1.1",
                     )
                     .print_metadata(MetadataPrintout::None)
-                    .build()
-                    .map(|result| {
-                        result
-                            .module
-                            .unwrap_single()
-                            .to_owned()
-                    });
-
-                    if let Ok(shader_path) = shader_path {
-                        _ = tx.send(shader_path);
-                    } else {
-                        error!("Compilation failed");
-                    }
+                    .release(optimize)
+                    .build();
 
-                    previous_modified_at =
-                        Some(modified_at);
-                } else {
-                    thread::sleep(Duration::from_millis(5));
+                    let event = match result {
+                        Ok(result) => {
+                            let duration =
+                                started_at.elapsed();
+
+                            info!(
+                            "Compilation succeeded (took {:.1}s)",
+                            duration.as_secs_f32()
+                        );
+
+                            let module = result
+                                .module
+                                .unwrap_single();
+
+                            // Not being able to cache the module is a
+                            // shame, but we can still use it
+                            let path = cache
+                                .store(hash, module, &crate_dir)
+                                .unwrap_or_else(|err| {
+                                    warn!("Couldn't cache shader: {err}");
+                                    module.to_owned()
+                                });
+
+                            if optimize {
+                                self::optimize(&path);
+                            }
+
+                            let complexity =
+                                Complexity::of(&path);
+
+                            let grown_from = previous_complexity
+                                .zip(complexity)
+                                .filter(|(prev, curr)| {
+                                    curr.has_grown_since(prev)
+                                })
+                                .map(|(prev, _)| prev);
+
+                            if let Some(complexity) =
+                                complexity
+                            {
+                                match grown_from {
+                                    Some(prev) => warn!(
+                                        "Shader has grown from {prev} to {complexity} - expect a lower frame rate"
+                                    ),
+                                    None => info!(
+                                        "Shader complexity: {complexity}"
+                                    ),
+                                }
+
+                                previous_complexity =
+                                    Some(complexity);
+                            }
+
+                            CompilerEvent::Succeeded {
+                                path,
+                                duration,
+                                complexity,
+                                grown_from,
+                            }
+                        }
+
+                        Err(err) => {
+                            error!(
+                                "Compilation failed: {err}"
+                            );
+
+                            CompilerEvent::Failed {
+                                error: err.to_string(),
+                            }
+                        }
+                    };
+
+                    _ = tx.send(event);
+
+                    previous_hash = Some(hash);
                 }
             }
         });
 
-        Self { rx }
+        Self {
+            rx,
+            cache,
+            history: History::from_env(),
+        }
     }
 
-    pub fn poll(&self) -> Option<PathBuf> {
+    pub fn poll(&self) -> Option<CompilerEvent> {
         self.rx.try_recv().ok()
     }
+
+    /// Marks the most recently compiled shader as working (i.e. accepted by
+    /// the GPU), storing it together with its sources in
+    /// `target/sdf-playground/last-good`.
+    ///
+    /// This way, when a later version turns out to be broken, you can always
+    /// see what the last working version looked like.
+    ///
+    /// If the history is enabled (see `history`), the sources get recorded
+    /// there as well.
+    pub fn promote(&self) {
+        if let Err(err) = self.cache.promote() {
+            warn!("Couldn't store last good shader: {err}");
+        }
+
+        if let Some(history) = &self.history {
+            let source = self.cache.dir.join("source");
+
+            if let Err(err) = history.record(&source) {
+                warn!("Couldn't record shader in history: {err}");
+            }
+        }
+    }
+
+    /// Returns the module most recently marked as working (see
+    /// [`Self::promote()`]), if there's one - used when the current one gets
+    /// rejected by the GPU and there's no older renderer to fall back to (e.g.
+    /// right after starting the application).
+    pub fn last_good(&self) -> Option<PathBuf> {
+        Some(self.cache.last_good())
+            .filter(|path| path.exists())
+    }
+
+    pub fn history(&self) -> Option<&History> {
+        self.history.as_ref()
+    }
+}
+
+#[derive(Debug)]
+pub enum CompilerEvent {
+    /// Shader's source has changed and we've started compiling it.
+    Compiling,
+
+    /// Shader has been compiled into given SPIR-V module.
+    ///
+    /// If the module is dramatically more complex than the previous one,
+    /// `grown_from` says how complex the previous one was.
+    Succeeded {
+        path: PathBuf,
+        duration: Duration,
+        complexity: Option<Complexity>,
+        grown_from: Option<Complexity>,
+    },
+
+    /// Shader couldn't be compiled - details (i.e. rustc's diagnostics) have
+    /// been printed to the terminal.
+    Failed { error: String },
+}
+
+impl CompilerEvent {
+    /// Returns a short, human-readable status - used for the window's title.
+    pub fn status(&self) -> String {
+        match self {
+            CompilerEvent::Compiling => "compiling…".into(),
+
+            CompilerEvent::Succeeded {
+                duration,
+                complexity: Some(complexity),
+                grown_from: Some(grown_from),
+                ..
+            } => {
+                format!(
+                    "shader OK ({:.1}s), but it has grown from {grown_from} to {complexity}",
+                    duration.as_secs_f32()
+                )
+            }
+
+            CompilerEvent::Succeeded {
+                duration, ..
+            } => {
+                format!(
+                    "shader OK ({:.1}s)",
+                    duration.as_secs_f32()
+                )
+            }
+
+            CompilerEvent::Failed { error } => {
+                format!("compile error: {error}")
+            }
+        }
+    }
+}
+
+/// Rough estimate of how heavy a shader is, based on its SPIR-V - so that we
+/// can warn when a hot-reloaded shader grows dramatically, before it shows up
+/// in the frame rate.
+///
+/// It doesn't know how many times the loops iterate or how much particular
+/// instructions cost, so it's meant for comparing versions of the same shader
+/// rather than for judging it on its own.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Complexity {
+    /// Number of instructions inside functions.
+    pub instructions: usize,
+
+    /// Number of loops.
+    pub loops: usize,
+}
+
+impl Complexity {
+    /// How many times larger a shader has to get (between two compilations)
+    /// for us to warn about it.
+    const GROWTH: f32 = 1.5;
+
+    /// Analyzes given SPIR-V module; returns `None` if it's not a valid one.
+    fn of(module: &Path) -> Option<Self> {
+        const MAGIC: u32 = 0x0723_0203;
+        const OP_FUNCTION: u32 = 54;
+        const OP_FUNCTION_END: u32 = 56;
+        const OP_LOOP_MERGE: u32 = 246;
+
+        let module = fs::read(module).ok()?;
+
+        let words: Vec<_> = module
+            .chunks_exact(4)
+            .map(|word| {
+                u32::from_le_bytes(word.try_into().unwrap())
+            })
+            .collect();
+
+        if words.first() != Some(&MAGIC) {
+            return None;
+        }
+
+        let mut this = Self {
+            instructions: 0,
+            loops: 0,
+        };
+
+        let mut in_function = false;
+
+        // (first five words are the header)
+        let mut idx = 5;
+
+        while let Some(&word) = words.get(idx) {
+            let (len, opcode) = (word >> 16, word & 0xffff);
+
+            if len == 0 {
+                return None;
+            }
+
+            match opcode {
+                OP_FUNCTION => in_function = true,
+                OP_FUNCTION_END => in_function = false,
+                OP_LOOP_MERGE => this.loops += 1,
+                _ => (),
+            }
+
+            if in_function {
+                this.instructions += 1;
+            }
+
+            idx += len as usize;
+        }
+
+        Some(this)
+    }
+
+    /// Returns whether the shader is dramatically more complex than `prev`.
+    fn has_grown_since(&self, prev: &Self) -> bool {
+        let grown = |curr: usize, prev: usize| {
+            curr as f32 > prev.max(1) as f32 * Self::GROWTH
+        };
+
+        grown(self.instructions, prev.instructions)
+            || grown(self.loops, prev.loops)
+    }
+}
+
+impl fmt::Display for Complexity {
+    fn fmt(
+        &self,
+        f: &mut fmt::Formatter<'_>,
+    ) -> fmt::Result {
+        write!(
+            f,
+            "{} instructions and {} loops",
+            self.instructions, self.loops
+        )
+    }
+}
+
+/// Directory keeping the most recently compiled shader, so that restarting the
+/// application doesn't require recompiling it.
+///
+/// By default it's located inside `target/`, but it can be changed through the
+/// `SDF_PLAYGROUND_CACHE_DIR` environment variable.
+#[derive(Clone, Debug)]
+struct Cache {
+    dir: PathBuf,
+}
+
+impl Cache {
+    fn new() -> Self {
+        let dir = cache_dir();
+
+        // (if this fails, so will storing modules - which gets reported then)
+        _ = fs::create_dir_all(&dir);
+
+        Self { dir }
+    }
+
+    /// Returns hash of sources from which the cached module has been compiled.
+    fn hash(&self) -> Option<u64> {
+        if !self.module().exists() {
+            return None;
+        }
+
+        fs::read_to_string(self.dir.join("shader.hash"))
+            .ok()?
+            .trim()
+            .parse()
+            .ok()
+    }
+
+    fn module(&self) -> PathBuf {
+        self.dir.join("shader.spv")
+    }
+
+    /// Stores given module together with a snapshot of the sources it's been
+    /// compiled from.
+    fn store(
+        &self,
+        hash: u64,
+        module: &Path,
+        crate_dir: &Path,
+    ) -> Result<PathBuf, Error> {
+        fs::copy(module, self.module())
+            .map_err(Error::io(self.module()))?;
+
+        let hash_path = self.dir.join("shader.hash");
+
+        fs::write(&hash_path, hash.to_string())
+            .map_err(Error::io(hash_path))?;
+
+        copy_files(crate_dir, &self.dir.join("source"))?;
+
+        Ok(self.module())
+    }
+
+    fn last_good(&self) -> PathBuf {
+        self.dir.join("last-good").join("shader.spv")
+    }
+
+    fn promote(&self) -> Result<(), Error> {
+        let dst = self.dir.join("last-good");
+
+        copy_files(
+            &self.dir.join("source"),
+            &dst.join("source"),
+        )?;
+
+        let module = self.last_good();
+
+        fs::copy(self.module(), &module)
+            .map_err(Error::io(module))?;
+
+        Ok(())
+    }
+}
+
+/// Returns path of the shader crate.
+pub fn shader_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .parent()
+        .unwrap()
+        .join("shader")
+}
+
+/// Returns path of the directory where compiled modules (and other things
+/// worth keeping between runs, such as backups made by `--import`) are stored.
+pub fn cache_dir() -> PathBuf {
+    env::var_os("SDF_PLAYGROUND_CACHE_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| {
+            Path::new(env!("CARGO_MANIFEST_DIR"))
+                .parent()
+                .unwrap()
+                .join("target")
+                .join("sdf-playground")
+        })
+}
+
+/// Points the cargo spawned by spirv-builder into the cache (unless the user
+/// has picked a target directory on their own).
+///
+/// spirv-builder doesn't let us pick the target directory, but the cargo it
+/// spawns inherits our environment - pointing it into the cache keeps rustc's
+/// incremental artifacts around between runs (and lets
+/// `SDF_PLAYGROUND_CACHE_DIR` move them together with the module).
+///
+/// Modifies the environment, so it has to be called before any threads get
+/// started.
+pub fn set_target_dir() {
+    if env::var_os("CARGO_TARGET_DIR").is_none() {
+        env::set_var(
+            "CARGO_TARGET_DIR",
+            cache_dir().join("target"),
+        );
+    }
+}
+
+/// Replaces `dst` with a copy of all files from `src`.
+pub fn copy_files(
+    src: &Path,
+    dst: &Path,
+) -> Result<(), Error> {
+    _ = fs::remove_dir_all(dst);
+
+    for file in files(src) {
+        // (`files()` returns paths inside of `src`, so the prefix is there)
+        let file_dst =
+            dst.join(file.strip_prefix(src).unwrap());
+
+        if let Some(dir) = file_dst.parent() {
+            fs::create_dir_all(dir)
+                .map_err(Error::io(dir))?;
+        }
+
+        fs::copy(&file, &file_dst)
+            .map_err(Error::io(file_dst))?;
+    }
+
+    Ok(())
+}
+
+/// Returns paths of all files comprising given crate, in a stable order.
+pub fn files(crate_dir: &Path) -> Vec<PathBuf> {
+    fn visit(dir: &Path, files: &mut Vec<PathBuf>) {
+        let Ok(entries) = fs::read_dir(dir) else {
+            return;
+        };
+
+        for entry in entries.flatten() {
+            let path = entry.path();
+
+            if path.is_dir() {
+                visit(&path, files);
+            } else {
+                files.push(path);
+            }
+        }
+    }
+
+    let mut files = vec![crate_dir.join("Cargo.toml")];
+
+    visit(&crate_dir.join("src"), &mut files);
+    files.sort();
+    files
+}
+
+/// Returns when any of given crates has been modified most recently.
+fn modified_at(crate_dirs: &[&Path]) -> SystemTime {
+    crate_dirs
+        .iter()
+        .flat_map(|crate_dir| files(crate_dir))
+        .filter_map(|file| {
+            file.metadata().ok()?.modified().ok()
+        })
+        .max()
+        .unwrap_or(SystemTime::UNIX_EPOCH)
+}
+
+/// Returns hash of given crates' contents (and of the settings the shader is
+/// compiled with).
+///
+/// The hash gets stored next to the cached module, so it has to stay the same
+/// between runs - hence FNV-1a instead of `DefaultHasher`, which can change
+/// with every version of Rust.
+fn hash(crate_dirs: &[&Path], optimize: bool) -> u64 {
+    let mut hasher = Fnv1a::default();
+
+    hasher.write(&[optimize as u8]);
+
+    for file in crate_dirs.iter().flat_map(|dir| files(dir))
+    {
+        let contents = fs::read(&file).unwrap_or_default();
+
+        // (lengths delimit the paths and contents, so that moving bytes from
+        // one to another can't produce the same hash)
+        for bytes in
+            [file.to_string_lossy().as_bytes(), &contents]
+        {
+            hasher
+                .write(&(bytes.len() as u64).to_le_bytes());
+            hasher.write(bytes);
+        }
+    }
+
+    hasher.finish()
+}
+
+/// Runs given SPIR-V module through `spirv-opt`, if it's available.
+///
+/// rustc already optimizes the code on its own, but `spirv-opt` knows a couple
+/// of SPIR-V-specific tricks on top of that (e.g. better loop unrolling and
+/// dead branch elimination).
+fn optimize(module: &Path) {
+    let optimized = module.with_extension("opt.spv");
+
+    let output = Command::new("spirv-opt")
+        .arg("-O")
+        .arg(module)
+        .arg("-o")
+        .arg(&optimized)
+        .output();
+
+    match output {
+        Ok(output) if output.status.success() => {
+            if let Err(err) = fs::rename(&optimized, module)
+            {
+                warn!("Couldn't replace module with the optimized one: {err}");
+            }
+        }
+
+        Ok(output) => {
+            error!(
+                "spirv-opt failed ({}), using unoptimized module:\n{}",
+                output.status,
+                String::from_utf8_lossy(&output.stderr).trim_end(),
+            );
+        }
+
+        Err(_) => {
+            info!("spirv-opt not found, skipping additional optimizations");
+        }
+    }
 }
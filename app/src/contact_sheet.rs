@@ -0,0 +1,224 @@
+//! Contact sheets (`E`) - the current frame rendered with a sweep of exposures
+//! (columns) and tonemappers (rows), saved as a single PNG, so that look-dev
+//! decisions can be compared side by side (and later, away from the
+//! playground).
+//!
+//! The shader itself doesn't tonemap anything (its output is just clamped when
+//! it lands on the screen), so the sweep is done on the CPU, on a downloaded
+//! copy of the frame.
+
+use crate::error::Error;
+use crate::history::timestamp;
+use crate::image::encode_png;
+use crate::text::glyph;
+use sdf_playground_common::glam::{UVec2, Vec3, Vec4};
+use std::fs;
+use std::path::PathBuf;
+use std::time::SystemTime;
+
+/// Exposures, in stops.
+const EXPOSURES: &[f32] = &[-2.0, -1.0, 0.0, 1.0, 2.0];
+
+/// Tonemappers, along with their names.
+const TONEMAPPERS: &[(&str, Tonemapper)] = &[
+    ("clip", clip),
+    ("reinhard", reinhard),
+    ("aces", aces),
+];
+
+/// Maps a linear color into `0.0..=1.0`.
+type Tonemapper = fn(Vec3) -> Vec3;
+
+/// Maximum width of a single tile, in pixels - the frame gets downscaled until
+/// it fits, so that the sheet stays reasonably sized.
+const TILE_WIDTH: u32 = 320;
+
+/// Space between the tiles, in pixels.
+const GAP: u32 = 2;
+
+/// Builds a contact sheet out of given frame (as returned from
+/// [`crate::renderer::Renderer::read_output()`]) and saves it into the current
+/// directory, returning its path.
+pub fn save(
+    size: UVec2,
+    pixels: &[Vec4],
+) -> Result<PathBuf, Error> {
+    let path = PathBuf::from(format!(
+        "contact-sheet-{}.png",
+        timestamp(SystemTime::now())
+    ));
+
+    let (width, height, sheet) = render(size, pixels);
+
+    let png = encode_png(
+        width,
+        height,
+        png::ColorType::Rgb,
+        &sheet,
+    );
+
+    fs::write(&path, png).map_err(Error::io(&path))?;
+
+    Ok(path)
+}
+
+/// Lays out the tiles, returning the sheet's width, height and pixels (8-bit
+/// sRGB).
+fn render(
+    size: UVec2,
+    pixels: &[Vec4],
+) -> (u32, u32, Vec<u8>) {
+    let frame = downscale(size, pixels);
+    let tile = frame.0;
+
+    let width =
+        EXPOSURES.len() as u32 * (tile.x + GAP) + GAP;
+    let height =
+        TONEMAPPERS.len() as u32 * (tile.y + GAP) + GAP;
+    let mut sheet = vec![0; (3 * width * height) as usize];
+
+    for (row, (name, tonemap)) in
+        TONEMAPPERS.iter().enumerate()
+    {
+        for (col, exposure) in EXPOSURES.iter().enumerate()
+        {
+            let x0 = GAP + col as u32 * (tile.x + GAP);
+            let y0 = GAP + row as u32 * (tile.y + GAP);
+            let scale = exposure.exp2();
+
+            for y in 0..tile.y {
+                for x in 0..tile.x {
+                    let color =
+                        frame.1[(y * tile.x + x) as usize];
+                    let color = tonemap(color * scale);
+
+                    let offset = (3
+                        * ((y0 + y) * width + x0 + x))
+                        as usize;
+
+                    sheet[offset..offset + 3]
+                        .copy_from_slice(&[
+                            srgb(color.x),
+                            srgb(color.y),
+                            srgb(color.z),
+                        ]);
+                }
+            }
+
+            let label = format!("{name} {exposure:+}ev");
+
+            stamp(
+                &mut sheet,
+                width,
+                x0 + 4,
+                y0 + 4,
+                &label,
+            );
+        }
+    }
+
+    (width, height, sheet)
+}
+
+/// Shrinks given frame (by an integer factor, averaging the pixels) until it
+/// fits [`TILE_WIDTH`].
+fn downscale(
+    size: UVec2,
+    pixels: &[Vec4],
+) -> (UVec2, Vec<Vec3>) {
+    let factor = (size.x + TILE_WIDTH - 1) / TILE_WIDTH;
+    let factor = factor.max(1);
+    let tile = (size / factor).max(UVec2::ONE);
+
+    let pixels = (0..tile.y)
+        .flat_map(|y| (0..tile.x).map(move |x| (x, y)))
+        .map(|(x, y)| {
+            let mut sum = Vec3::ZERO;
+
+            for dy in 0..factor {
+                for dx in 0..factor {
+                    let x =
+                        (x * factor + dx).min(size.x - 1);
+                    let y =
+                        (y * factor + dy).min(size.y - 1);
+
+                    sum += pixels
+                        [(y * size.x + x) as usize]
+                        .truncate();
+                }
+            }
+
+            // (NaNs and negative colors end up black)
+            (sum / (factor * factor) as f32).max(Vec3::ZERO)
+        })
+        .collect();
+
+    (tile, pixels)
+}
+
+/// Draws given text with its top-left corner at given position - white, with
+/// a dark shadow, so that it's readable over any tile.
+fn stamp(
+    sheet: &mut [u8],
+    width: u32,
+    x: u32,
+    y: u32,
+    text: &str,
+) {
+    for (shadow, color) in [(1, 0), (0, 255)] {
+        for (idx, ch) in text.chars().enumerate() {
+            for (dy, row) in
+                glyph(ch).into_iter().enumerate()
+            {
+                for dx in 0..5 {
+                    if row & (0b10000 >> dx) == 0 {
+                        continue;
+                    }
+
+                    let px =
+                        x + shadow + 6 * idx as u32 + dx;
+                    let py = y + shadow + dy as u32;
+                    let offset =
+                        (3 * (py * width + px)) as usize;
+
+                    if let Some(pixel) =
+                        sheet.get_mut(offset..offset + 3)
+                    {
+                        pixel.fill(color);
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn clip(color: Vec3) -> Vec3 {
+    color.min(Vec3::ONE)
+}
+
+fn reinhard(color: Vec3) -> Vec3 {
+    color / (color + Vec3::ONE)
+}
+
+/// Krzysztof Narkowicz's fit of the ACES filmic curve, see:
+/// https://knarkowicz.wordpress.com/2016/01/06/aces-filmic-tone-mapping-curve/
+fn aces(color: Vec3) -> Vec3 {
+    let (a, b, c, d, e) = (2.51, 0.03, 2.43, 0.59, 0.14);
+
+    ((color * (a * color + b))
+        / (color * (c * color + d) + e))
+        .clamp(Vec3::ZERO, Vec3::ONE)
+}
+
+/// Encodes given linear value (`0.0..=1.0`) as 8-bit sRGB.
+pub fn srgb(value: f32) -> u8 {
+    let value = value.clamp(0.0, 1.0);
+
+    let value = if value <= 0.0031308 {
+        value * 12.92
+    } else {
+        1.055 * value.powf(1.0 / 2.4) - 0.055
+    };
+
+    (value * 255.0).round() as u8
+}
@@ -0,0 +1,73 @@
+//! Demo mode (`D` or `--demo`) - cycles through all the scenes, orbiting the
+//! camera around them; handy for leaving the playground running unattended.
+
+use sdf_playground_common::{Params, SCENES};
+use std::env;
+use std::time::Duration;
+
+/// How fast the camera orbits around the scene, in radians per second.
+const ORBIT_SPEED: f32 = 0.2;
+
+/// How long it takes to blend between two scenes.
+const CROSSFADE: Duration = Duration::from_secs(2);
+
+#[derive(Debug)]
+pub struct Demo {
+    /// How long each scene is shown for, including the crossfade.
+    interval: Duration,
+
+    /// For how long the current scene has been shown.
+    elapsed: Duration,
+}
+
+impl Demo {
+    pub fn new() -> Self {
+        let interval =
+            env::var("SDF_PLAYGROUND_DEMO_INTERVAL")
+                .ok()
+                .and_then(|val| val.parse().ok())
+                .map_or(
+                    Duration::from_secs(10),
+                    Duration::from_secs_f32,
+                );
+
+        Self {
+            interval: interval.max(CROSSFADE),
+            elapsed: Duration::ZERO,
+        }
+    }
+
+    /// Moves the demo forward by given amount of time.
+    pub fn update(
+        &mut self,
+        params: &mut Params,
+        frame_time: Duration,
+    ) {
+        self.elapsed += frame_time;
+        params.orbit +=
+            frame_time.as_secs_f32() * ORBIT_SPEED;
+
+        if self.elapsed >= self.interval {
+            self.elapsed = Duration::ZERO;
+
+            params.prev_scene = params.scene;
+            params.scene = params.scene % SCENES + 1;
+        }
+
+        params.crossfade = if params.prev_scene == 0 {
+            0.0
+        } else {
+            (1.0 - self.elapsed.as_secs_f32()
+                / CROSSFADE.as_secs_f32())
+            .max(0.0)
+        };
+    }
+
+    /// Brings the camera back to where it's normally located and cancels the
+    /// crossfade, if one is in progress.
+    pub fn stop(params: &mut Params) {
+        params.orbit = 0.0;
+        params.prev_scene = 0;
+        params.crossfade = 0.0;
+    }
+}
@@ -0,0 +1,56 @@
+use std::io;
+use std::path::PathBuf;
+use thiserror::Error;
+
+/// Things that can go wrong in the application.
+///
+/// Errors related to the window and the GPU are fatal, since there's nothing
+/// to render into without them; the rest (shaders, files) only get reported
+/// in the window's title, keeping the playground running with whatever worked
+/// before.
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("couldn't create window: {0}")]
+    Window(#[from] winit::error::OsError),
+
+    #[error("couldn't find any suitable GPU")]
+    NoAdapter,
+
+    #[error("couldn't create surface: {0}")]
+    Surface(#[from] wgpu::CreateSurfaceError),
+
+    #[error("couldn't open GPU: {0}")]
+    Device(#[from] wgpu::RequestDeviceError),
+
+    #[error("shader rejected by GPU: {0}")]
+    Shader(#[from] wgpu::Error),
+
+    #[error(
+        "shader's Params take {shader} bytes, but application's take {app} - \
+         restart the application to pick up changes in `common`"
+    )]
+    ParamsLayout { shader: u32, app: u32 },
+
+    #[error("{} is not a valid bundle: {reason}", path.display())]
+    Bundle { path: PathBuf, reason: String },
+
+    #[error("couldn't access {}: {source}", path.display())]
+    Io {
+        path: PathBuf,
+
+        #[source]
+        source: io::Error,
+    },
+}
+
+impl Error {
+    /// Returns a function which wraps [`io::Error`] into [`Error::Io`] for
+    /// given path - for use with [`Result::map_err()`].
+    pub fn io(
+        path: impl Into<PathBuf>,
+    ) -> impl FnOnce(io::Error) -> Self {
+        let path = path.into();
+
+        move |source| Self::Io { path, source }
+    }
+}
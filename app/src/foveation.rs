@@ -0,0 +1,270 @@
+use crate::gpu::Gpu;
+use crate::renderer::{self, Renderer};
+use sdf_playground_common::glam::vec4;
+use sdf_playground_common::Params;
+use std::fmt;
+
+/// Post-process pass of foveated rendering (see `Params::fovea`) - fills in
+/// tiles the shader has skipped by upscaling the lower-resolution pixels
+/// rendered for them (see [`Renderer::fovea()`]).
+///
+/// The result goes both into the target and into the renderer's output, so
+/// that everything afterwards (post effects, the next frame's history etc.)
+/// sees the complete frame.
+#[derive(Debug)]
+pub struct Foveation {
+    pipeline: wgpu::RenderPipeline,
+    bind_group_layout: wgpu::BindGroupLayout,
+    buffer: wgpu::Buffer,
+
+    /// How the skipped pixels get interpolated.
+    pub filter: Filter,
+}
+
+/// Filter used to upscale the lower-resolution parts of the frame.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Filter {
+    /// Cheapest, but soft.
+    #[default]
+    Bilinear,
+
+    /// Catmull-Rom - sharper, looks at 4x4 pixels instead of 2x2.
+    Bicubic,
+
+    /// Lanczos (with `a = 2`) - sharpest, at a similar cost as bicubic.
+    Lanczos,
+}
+
+impl Filter {
+    pub fn next(self) -> Self {
+        match self {
+            Filter::Bilinear => Filter::Bicubic,
+            Filter::Bicubic => Filter::Lanczos,
+            Filter::Lanczos => Filter::Bilinear,
+        }
+    }
+}
+
+impl fmt::Display for Filter {
+    fn fmt(
+        &self,
+        f: &mut fmt::Formatter<'_>,
+    ) -> fmt::Result {
+        match self {
+            Filter::Bilinear => write!(f, "bilinear"),
+            Filter::Bicubic => write!(f, "bicubic"),
+            Filter::Lanczos => write!(f, "lanczos"),
+        }
+    }
+}
+
+impl Foveation {
+    pub fn new(gpu: &Gpu) -> Self {
+        let device = gpu.device();
+
+        let module = device.create_shader_module(
+            wgpu::ShaderModuleDescriptor {
+                label: Some("foveation_shader"),
+                source: wgpu::ShaderSource::Wgsl(
+                    include_str!("foveation.wgsl").into(),
+                ),
+            },
+        );
+
+        let buffer =
+            device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some("foveation_buffer"),
+                size: 3 * 4 * 4,
+                usage: wgpu::BufferUsages::UNIFORM
+                    | wgpu::BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            });
+
+        let bind_group_layout = device
+            .create_bind_group_layout(
+                &wgpu::BindGroupLayoutDescriptor {
+                    label: Some("foveation_bind_group_layout"),
+                    entries: &[
+                        wgpu::BindGroupLayoutEntry {
+                            binding: 0,
+                            visibility:
+                                wgpu::ShaderStages::FRAGMENT,
+                            ty: wgpu::BindingType::Texture {
+                                sample_type:
+                                    wgpu::TextureSampleType::Float {
+                                        filterable: false,
+                                    },
+                                view_dimension:
+                                    wgpu::TextureViewDimension::D2,
+                                multisampled: false,
+                            },
+                            count: None,
+                        },
+                        wgpu::BindGroupLayoutEntry {
+                            binding: 1,
+                            visibility:
+                                wgpu::ShaderStages::FRAGMENT,
+                            ty: wgpu::BindingType::Buffer {
+                                ty: wgpu::BufferBindingType::Uniform,
+                                has_dynamic_offset: false,
+                                min_binding_size: None,
+                            },
+                            count: None,
+                        },
+                    ],
+                },
+            );
+
+        let pipeline_layout = device
+            .create_pipeline_layout(
+                &wgpu::PipelineLayoutDescriptor {
+                    label: Some(
+                        "foveation_pipeline_layout",
+                    ),
+                    bind_group_layouts: &[
+                        &bind_group_layout,
+                    ],
+                    push_constant_ranges: &[],
+                },
+            );
+
+        let pipeline = device.create_render_pipeline(
+            &wgpu::RenderPipelineDescriptor {
+                label: Some("foveation_pipeline"),
+                layout: Some(&pipeline_layout),
+                vertex: wgpu::VertexState {
+                    module: &module,
+                    entry_point: "main_vs",
+                    buffers: &[],
+                },
+                primitive: wgpu::PrimitiveState::default(),
+                depth_stencil: None,
+                multisample:
+                    wgpu::MultisampleState::default(),
+                fragment: Some(wgpu::FragmentState {
+                    module: &module,
+                    entry_point: "main_fs",
+                    targets: &[
+                        Some(wgpu::ColorTargetState {
+                            format: gpu.format(),
+                            blend: None,
+                            write_mask:
+                                wgpu::ColorWrites::ALL,
+                        }),
+                        Some(wgpu::ColorTargetState {
+                            format:
+                                renderer::HISTORY_FORMAT,
+                            blend: None,
+                            write_mask:
+                                wgpu::ColorWrites::ALL,
+                        }),
+                    ],
+                }),
+                multiview: None,
+            },
+        );
+
+        Self {
+            pipeline,
+            bind_group_layout,
+            buffer,
+            filter: Filter::default(),
+        }
+    }
+
+    /// Recreates the pipeline on given GPU - used after the previous GPU has
+    /// been lost.
+    pub fn recreate(&mut self, gpu: &Gpu) {
+        *self = Self {
+            filter: self.filter,
+            ..Self::new(gpu)
+        };
+    }
+
+    /// Fills in pixels of the renderer's most recent frame skipped by foveated
+    /// rendering, drawing them into the target and into the frame itself (see
+    /// [`Renderer::output()`]); does nothing if the frame's been rendered
+    /// without foveated rendering.
+    pub fn render(
+        &self,
+        gpu: &Gpu,
+        encoder: &mut wgpu::CommandEncoder,
+        target: &wgpu::TextureView,
+        renderer: &Renderer,
+        params: &Params,
+    ) {
+        let Some(atlas) = renderer.fovea() else {
+            return;
+        };
+
+        let region4 = params.fovea_region(4).as_vec2();
+        let region8 = params.fovea_region(8).as_vec2();
+
+        gpu.queue().write_buffer(
+            &self.buffer,
+            0,
+            bytemuck::bytes_of(&[
+                params.fovea,
+                vec4(
+                    self.filter as u32 as f32,
+                    params.width as f32,
+                    params.height as f32,
+                    0.0,
+                ),
+                vec4(
+                    region4.x, region4.y, region8.x,
+                    region8.y,
+                ),
+            ]),
+        );
+
+        let bind_group = gpu.device().create_bind_group(
+            &wgpu::BindGroupDescriptor {
+                label: Some("foveation_bind_group"),
+                layout: &self.bind_group_layout,
+                entries: &[
+                    wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource:
+                            wgpu::BindingResource::TextureView(
+                                atlas,
+                            ),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 1,
+                        resource: self.buffer.as_entire_binding(),
+                    },
+                ],
+            },
+        );
+
+        let mut pass = encoder.begin_render_pass(
+            &wgpu::RenderPassDescriptor {
+                label: Some("foveation_render_pass"),
+                color_attachments: &[
+                    Some(wgpu::RenderPassColorAttachment {
+                        view: target,
+                        resolve_target: None,
+                        ops: wgpu::Operations {
+                            load: wgpu::LoadOp::Load,
+                            store: true,
+                        },
+                    }),
+                    Some(wgpu::RenderPassColorAttachment {
+                        view: renderer.output(),
+                        resolve_target: None,
+                        ops: wgpu::Operations {
+                            load: wgpu::LoadOp::Load,
+                            store: true,
+                        },
+                    }),
+                ],
+                depth_stencil_attachment: None,
+            },
+        );
+
+        pass.set_pipeline(&self.pipeline);
+        pass.set_bind_group(0, &bind_group, &[]);
+        pass.draw(0..3, 0..1);
+    }
+}
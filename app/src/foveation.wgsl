@@ -0,0 +1,183 @@
+// Fills in pixels skipped by foveated rendering, see `foveation.rs`.
+
+struct Settings {
+    // Same as `Params::fovea`
+    fovea: vec4<f32>,
+
+    // Upscaling filter, see below
+    upscaling: f32,
+
+    // Size of the screen, in pixels
+    size: vec2<f32>,
+
+    // Same as `Params::fovea_region()` for blocks four (`xy`) and eight (`zw`)
+    regions: vec4<f32>,
+}
+
+struct Output {
+    @location(0) color: vec4<f32>,
+    @location(1) history: vec4<f32>,
+}
+
+// Same as `FOVEA_TILE`
+const TILE: u32 = 8u;
+
+// Same as `Filter`
+const BILINEAR: u32 = 0u;
+const BICUBIC: u32 = 1u;
+const LANCZOS: u32 = 2u;
+
+const PI: f32 = 3.14159265;
+
+// Lower-resolution parts of the frame, see `Params::fovea_atlas_size()`
+@group(0) @binding(0)
+var atlas: texture_2d<f32>;
+
+@group(0) @binding(1)
+var<uniform> settings: Settings;
+
+// Full-screen triangle, same as in the main shader
+@vertex
+fn main_vs(@builtin(vertex_index) id: u32) -> @builtin(position) vec4<f32> {
+    let uv = vec2<f32>(f32((id << 1u) & 2u), f32(id & 2u));
+
+    return vec4<f32>(uv.x * 2.0 - 1.0, 1.0 - uv.y * 2.0, 0.0, 1.0);
+}
+
+// Same as `Params::fovea_block()`
+fn block(pixel: vec2<u32>) -> u32 {
+    let tile = vec2<f32>(pixel / TILE * TILE) + f32(TILE) * 0.5;
+    let level = u32(distance(tile, settings.fovea.xy) / settings.fovea.z);
+
+    return 1u << min(level, 3u);
+}
+
+// Same as `Params::fovea_renders()`, with `grid` being its block (named so, to
+// avoid shadowing `block()`)
+fn rendered(pixel: vec2<i32>, grid: i32) -> bool {
+    let curr = vec2<u32>(pixel);
+    let prev = vec2<u32>(max(pixel, vec2<i32>(1)) - 1);
+
+    return i32(block(curr)) == grid
+        || i32(block(vec2<u32>(prev.x, curr.y))) == grid
+        || i32(block(vec2<u32>(curr.x, prev.y))) == grid
+        || i32(block(prev)) == grid;
+}
+
+// Returns color of given pixel, rendered with given block
+fn fetch(pixel: vec2<i32>, block: i32) -> vec4<f32> {
+    var region = vec2<i32>(0);
+
+    if block == 4 {
+        region = vec2<i32>(settings.regions.xy);
+    } else if block == 8 {
+        region = vec2<i32>(settings.regions.zw);
+    }
+
+    return textureLoad(atlas, region + pixel / block, 0);
+}
+
+// Returns weights of the four grid's pixels around given point (at `-1`, `0`,
+// `1` and `2` cells away from the previous one, with `t` being how far it is
+// from there) for the bicubic (Catmull-Rom) or the Lanczos (`a = 2`) filter
+fn weights(t: f32) -> vec4<f32> {
+    if u32(settings.upscaling) == BICUBIC {
+        return vec4<f32>(
+            t * (-0.5 + t * (1.0 - 0.5 * t)),
+            1.0 + t * t * (-2.5 + 1.5 * t),
+            t * (0.5 + t * (2.0 - 1.5 * t)),
+            t * t * (-0.5 + 0.5 * t),
+        );
+    }
+
+    let x = vec4<f32>(t + 1.0, t, 1.0 - t, 2.0 - t) * PI;
+
+    // (the `max()` avoids dividing zero by zero right at the pixel)
+    return select(
+        2.0 * sin(x) * sin(x * 0.5) / max(x * x, vec4<f32>(1e-6)),
+        vec4<f32>(1.0),
+        x < vec4<f32>(1e-3),
+    );
+}
+
+// Interpolates the pixel from the 4x4 rendered pixels around it, with the
+// filter returned by `weights()`; sharper than the bilinear interpolation, at
+// the cost of a couple more fetches.
+//
+// The outer ring of those pixels isn't always there - it might be off-screen
+// or (where the resolution changes) skipped - in which case we stick to the
+// closest inner one instead.
+fn cubic(pixel: vec2<i32>, p0: vec2<i32>, p1: vec2<i32>, block: i32) -> vec4<f32> {
+    let size = vec2<i32>(settings.size);
+    let t = vec2<f32>(pixel - p0) / f32(block);
+    let wx = weights(t.x);
+    let wy = weights(t.y);
+
+    var color = vec4<f32>(0.0);
+
+    for (var y = 0; y < 4; y++) {
+        for (var x = 0; x < 4; x++) {
+            let inner = select(p0, p1, vec2<i32>(x, y) >= vec2<i32>(2));
+            var tap = p0 + (vec2<i32>(x, y) - 1) * block;
+
+            tap = select(tap, inner, (tap < vec2<i32>(0)) | (tap > size - 1));
+
+            if !rendered(tap, block) {
+                tap = clamp(tap, p0, p1);
+            }
+
+            color += fetch(tap, block) * wx[x] * wy[y];
+        }
+    }
+
+    return color / (dot(wx, vec4<f32>(1.0)) * dot(wy, vec4<f32>(1.0)));
+}
+
+@fragment
+fn main_fs(@builtin(position) pos: vec4<f32>) -> Output {
+    let size = vec2<i32>(settings.size);
+    let pixel = vec2<i32>(pos.xy);
+    let block = i32(block(vec2<u32>(pixel)));
+
+    if block == 1 {
+        // We've been rendered at full resolution, nothing to fill in
+        discard;
+    }
+
+    // Corners of the grid's cell we're in - all of them have been rendered
+    // (at the screen's edges, where the next corner is missing, we stick to
+    // the previous one)
+    let p0 = pixel / block * block;
+    let p1 = select(p0 + block, p0, p0 + block > size - 1);
+
+    let color = upscale(pixel, p0, p1, block);
+
+    return Output(color, color);
+}
+
+fn upscale(pixel: vec2<i32>, p0: vec2<i32>, p1: vec2<i32>, block: i32) -> vec4<f32> {
+    // Corners' colors
+    let a = fetch(p0, block);
+    let b = fetch(vec2<i32>(p1.x, p0.y), block);
+    let c = fetch(vec2<i32>(p0.x, p1.y), block);
+    let d = fetch(p1, block);
+
+    if all(pixel == p0) {
+        return a;
+    }
+
+    if u32(settings.upscaling) == BILINEAR {
+        let t = vec2<f32>(pixel - p0) / f32(block);
+
+        return mix(mix(a, b, t.x), mix(c, d, t.x), t.y);
+    }
+
+    // Both remaining filters overshoot around sharp edges, so let's keep the
+    // result within the corners' range - otherwise e.g. the sun would get
+    // rings of negative colors around it
+    return clamp(
+        cubic(pixel, p0, p1, block),
+        min(min(a, b), min(c, d)),
+        max(max(a, b), max(c, d)),
+    );
+}
@@ -0,0 +1,188 @@
+use crate::gpu::Gpu;
+use std::collections::VecDeque;
+use std::mem;
+use std::time::Duration;
+
+/// How many of the most recent frames are shown.
+const FRAMES: usize = 240;
+
+/// Size of the graph, in pixels.
+const WIDTH: u32 = FRAMES as u32;
+const HEIGHT: u32 = 80;
+
+/// Scrolling graph of frame times, drawn in the bottom-left corner of the
+/// window - makes it easy to spot hitches (e.g. during hot-reloading) and
+/// scenes that are too heavy for given GPU.
+#[derive(Debug)]
+pub struct FrameGraph {
+    frames: VecDeque<f32>,
+    pipeline: wgpu::RenderPipeline,
+    buffer: wgpu::Buffer,
+    bind_group: wgpu::BindGroup,
+}
+
+impl FrameGraph {
+    pub fn new(gpu: &Gpu) -> Self {
+        let device = gpu.device();
+
+        let module = device.create_shader_module(
+            wgpu::ShaderModuleDescriptor {
+                label: Some("frame_graph_shader"),
+                source: wgpu::ShaderSource::Wgsl(
+                    include_str!("frame_graph.wgsl").into(),
+                ),
+            },
+        );
+
+        let buffer =
+            device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some("frame_graph_buffer"),
+                size: (FRAMES * mem::size_of::<f32>())
+                    as wgpu::BufferAddress,
+                usage: wgpu::BufferUsages::UNIFORM
+                    | wgpu::BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            });
+
+        let bind_group_layout = device
+            .create_bind_group_layout(
+                &wgpu::BindGroupLayoutDescriptor {
+                    label: Some("frame_graph_bind_group_layout"),
+                    entries: &[wgpu::BindGroupLayoutEntry {
+                        binding: 0,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Buffer {
+                            ty: wgpu::BufferBindingType::Uniform,
+                            has_dynamic_offset: false,
+                            min_binding_size: None,
+                        },
+                        count: None,
+                    }],
+                },
+            );
+
+        let bind_group = device.create_bind_group(
+            &wgpu::BindGroupDescriptor {
+                label: Some("frame_graph_bind_group"),
+                layout: &bind_group_layout,
+                entries: &[wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: buffer.as_entire_binding(),
+                }],
+            },
+        );
+
+        let pipeline_layout = device
+            .create_pipeline_layout(
+                &wgpu::PipelineLayoutDescriptor {
+                    label: Some(
+                        "frame_graph_pipeline_layout",
+                    ),
+                    bind_group_layouts: &[
+                        &bind_group_layout,
+                    ],
+                    push_constant_ranges: &[],
+                },
+            );
+
+        let pipeline = device.create_render_pipeline(
+            &wgpu::RenderPipelineDescriptor {
+                label: Some("frame_graph_pipeline"),
+                layout: Some(&pipeline_layout),
+                vertex: wgpu::VertexState {
+                    module: &module,
+                    entry_point: "main_vs",
+                    buffers: &[],
+                },
+                primitive: wgpu::PrimitiveState::default(),
+                depth_stencil: None,
+                multisample: wgpu::MultisampleState::default(),
+                fragment: Some(wgpu::FragmentState {
+                    module: &module,
+                    entry_point: "main_fs",
+                    targets: &[Some(wgpu::ColorTargetState {
+                        format: gpu.format(),
+                        blend: Some(
+                            wgpu::BlendState::ALPHA_BLENDING,
+                        ),
+                        write_mask: wgpu::ColorWrites::ALL,
+                    })],
+                }),
+                multiview: None,
+            },
+        );
+
+        Self {
+            frames: [0.0; FRAMES].into(),
+            pipeline,
+            buffer,
+            bind_group,
+        }
+    }
+
+    /// Recreates the graph on given GPU, preserving the frames collected so
+    /// far - used after the previous GPU has been lost.
+    pub fn recreate(&mut self, gpu: &Gpu) {
+        let frames = mem::take(&mut self.frames);
+
+        *self = Self::new(gpu);
+        self.frames = frames;
+    }
+
+    /// Records how long the most recent frame took.
+    pub fn push(&mut self, frame_time: Duration) {
+        self.frames.pop_front();
+        self.frames
+            .push_back(frame_time.as_secs_f32() * 1000.0);
+    }
+
+    /// Draws the graph over whatever the target already contains.
+    pub fn render(
+        &self,
+        queue: &wgpu::Queue,
+        encoder: &mut wgpu::CommandEncoder,
+        target: &wgpu::TextureView,
+        width: u32,
+        height: u32,
+    ) {
+        let frames: Vec<_> =
+            self.frames.iter().copied().collect();
+
+        queue.write_buffer(
+            &self.buffer,
+            0,
+            bytemuck::cast_slice(&frames),
+        );
+
+        let mut pass = encoder.begin_render_pass(
+            &wgpu::RenderPassDescriptor {
+                label: Some("frame_graph_render_pass"),
+                color_attachments: &[Some(
+                    wgpu::RenderPassColorAttachment {
+                        view: target,
+                        resolve_target: None,
+                        ops: wgpu::Operations {
+                            load: wgpu::LoadOp::Load,
+                            store: true,
+                        },
+                    },
+                )],
+                depth_stencil_attachment: None,
+            },
+        );
+
+        pass.set_pipeline(&self.pipeline);
+        pass.set_bind_group(0, &self.bind_group, &[]);
+
+        pass.set_viewport(
+            0.0,
+            height.saturating_sub(HEIGHT) as f32,
+            WIDTH.min(width) as f32,
+            HEIGHT.min(height) as f32,
+            0.0,
+            1.0,
+        );
+
+        pass.draw(0..3, 0..1);
+    }
+}
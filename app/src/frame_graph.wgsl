@@ -0,0 +1,54 @@
+// Draws the frame-time graph, see `frame_graph.rs`.
+
+struct Frames {
+    // Frame times in milliseconds, from the oldest to the newest one (packed
+    // into vec4s, since arrays in uniform buffers have to be 16-byte aligned)
+    times: array<vec4<f32>, 60>,
+}
+
+struct VertexOutput {
+    @builtin(position) pos: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+}
+
+@group(0) @binding(0)
+var<uniform> frames: Frames;
+
+// Full-screen triangle, same as in the main shader - the viewport takes care of
+// putting it in the corner
+@vertex
+fn main_vs(@builtin(vertex_index) id: u32) -> VertexOutput {
+    let uv = vec2<f32>(f32((id << 1u) & 2u), f32(id & 2u));
+
+    var out: VertexOutput;
+    out.pos = vec4<f32>(uv.x * 2.0 - 1.0, 1.0 - uv.y * 2.0, 0.0, 1.0);
+    out.uv = uv;
+    return out;
+}
+
+@fragment
+fn main_fs(in: VertexOutput) -> @location(0) vec4<f32> {
+    // Top of the graph corresponds to 50 ms
+    let ms = (1.0 - in.uv.y) * 50.0;
+    let line = fwidth(ms);
+
+    // Guide lines at 60 FPS and 30 FPS
+    if abs(ms - 16.6) < line || abs(ms - 33.3) < line {
+        return vec4<f32>(1.0, 1.0, 1.0, 0.5);
+    }
+
+    let idx = min(u32(in.uv.x * 240.0), 239u);
+    let frame = frames.times[idx / 4u][idx % 4u];
+
+    if ms < frame {
+        if frame <= 16.7 {
+            return vec4<f32>(0.2, 0.8, 0.2, 0.9);
+        } else if frame <= 33.4 {
+            return vec4<f32>(0.9, 0.7, 0.1, 0.9);
+        } else {
+            return vec4<f32>(0.9, 0.1, 0.1, 0.9);
+        }
+    }
+
+    return vec4<f32>(0.0, 0.0, 0.0, 0.5);
+}
@@ -0,0 +1,140 @@
+//! Framing the scene (`F`) - like "frame selected" in DCC apps, points the
+//! camera at the scene's center and moves it closer or farther away, so that
+//! the whole scene (as estimated by [`bounding_box`]) fits on the screen.
+//!
+//! The camera keeps looking from the same direction (see [`Params::orbit`]),
+//! so framing plays along with orbiting and bookmarks.
+
+use crate::bounding_box::{self, BoundingBox};
+use log::{info, warn};
+use sdf_playground_common::glam::{Mat3, Vec3, Vec4};
+use sdf_playground_common::{look_at, Params};
+use sdf_playground_shader::CAMERA;
+use std::time::Duration;
+
+/// How long it takes to frame the scene.
+const TRANSITION: Duration = Duration::from_millis(800);
+
+/// Space left around the scene, as a fraction of the screen.
+const MARGIN: f32 = 0.1;
+
+#[derive(Debug, Default)]
+pub struct Framing {
+    transition: Option<Transition>,
+}
+
+#[derive(Clone, Copy, Debug)]
+struct Transition {
+    from: Vec4,
+    to: Vec4,
+    elapsed: Duration,
+}
+
+impl Framing {
+    /// Starts moving the camera so that it frames current scene; pushes the
+    /// far plane away if the scene wouldn't fit otherwise.
+    pub fn frame(&mut self, params: &mut Params) {
+        let Some(bb) = bounding_box::estimate(params)
+        else {
+            warn!(
+                "Couldn't frame scene {} - it seems to be empty or unbounded",
+                params.scene
+            );
+            return;
+        };
+
+        let to = fit(params, bb);
+
+        params.far =
+            params.far.max(to.w + bb.size().length());
+
+        info!(
+            "Framing scene {} ({:.2} x {:.2} x {:.2})",
+            params.scene,
+            bb.size().x,
+            bb.size().y,
+            bb.size().z
+        );
+
+        let from = current(params);
+
+        self.transition = Some(Transition {
+            from,
+            to,
+            elapsed: Duration::ZERO,
+        });
+    }
+
+    /// Moves the camera towards the framing, if one is in progress.
+    pub fn update(
+        &mut self,
+        params: &mut Params,
+        frame_time: Duration,
+    ) {
+        let Some(transition) = &mut self.transition else {
+            return;
+        };
+
+        transition.elapsed += frame_time;
+
+        let t = (transition.elapsed.as_secs_f32()
+            / TRANSITION.as_secs_f32())
+        .min(1.0);
+
+        // Smoothstep, so that the camera eases in and out
+        let t = t * t * (3.0 - 2.0 * t);
+
+        params.framing =
+            transition.from.lerp(transition.to, t);
+
+        if transition.elapsed >= TRANSITION {
+            self.transition = None;
+        }
+    }
+}
+
+/// Returns current framing (see [`Params::framing`]) - with the default one
+/// spelled out, so that the camera can fly away from it.
+pub fn current(params: &Params) -> Vec4 {
+    if params.framing.w > 0.0 {
+        params.framing
+    } else {
+        Vec3::ZERO.extend(CAMERA.length())
+    }
+}
+
+/// Returns framing (see [`Params::framing`]) that fits given box on the
+/// screen, when looking from the current direction.
+fn fit(params: &Params, bb: BoundingBox) -> Vec4 {
+    let target = bb.center();
+
+    let direction = Mat3::from_rotation_y(params.orbit)
+        * CAMERA.normalize();
+
+    let orientation =
+        look_at(target + direction, target, Vec3::Y);
+
+    // Half of the screen's size at a unit distance from the camera (see
+    // `ray_direction()`), less the margin
+    let screen =
+        params.frustum.abs().min_element() * (1.0 - MARGIN);
+
+    // Each corner has to be on the screen - the closer it is to the camera
+    // (along `direction`), the farther the camera has to go
+    let distance = bb
+        .corners()
+        .into_iter()
+        .map(|corner| {
+            let offset = corner - target;
+
+            let sideways = offset
+                .dot(orientation.x_axis)
+                .abs()
+                .max(offset.dot(orientation.y_axis).abs());
+
+            sideways / screen + offset.dot(direction)
+        })
+        .fold(0.0, f32::max);
+
+    target.extend(distance)
+}
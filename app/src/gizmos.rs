@@ -0,0 +1,539 @@
+use crate::bounding_box::{self, BoundingBox};
+use crate::gpu::Gpu;
+use sdf_playground_common::glam::{
+    vec2, vec3, Mat3, Vec2, Vec3,
+};
+use sdf_playground_common::{look_at, Hot, Params};
+use sdf_playground_shader::{Bounds, SUN};
+use std::f32::consts::TAU;
+use std::mem;
+
+/// Most vertices drawn at once - lines past that are skipped.
+const MAX_VERTICES: usize = 4096;
+
+/// Each vertex is a position (`xy` in clip space, `z` = distance from the
+/// camera) followed by a color.
+type Vertex = [f32; 8];
+
+/// How far from the center of the world the sun's marker is drawn - the sun
+/// itself is way too far to be seen.
+const SUN_DISTANCE: f32 = 9.0;
+
+/// Line between two points, in given color.
+type Line = (Vec3, Vec3, [f32; 4]);
+
+/// How far (in world units, along both `x` and `z`) the grid extends from the
+/// center of the world; grid lines are one unit apart, with every fifth one
+/// brighter.
+const GRID_SIZE: i32 = 15;
+
+/// Length of the `y` axis drawn together with the grid (the `x` and `z` ones
+/// span the entire grid).
+const AXIS_LENGTH: f32 = 3.0;
+
+const SUN_COLOR: [f32; 4] = [1.0, 0.85, 0.2, 1.0];
+const BOUNDS_COLOR: [f32; 4] = [0.2, 0.9, 1.0, 0.8];
+const ESTIMATE_COLOR: [f32; 4] = [1.0, 0.4, 0.9, 0.6];
+const GRID_COLOR: [f32; 4] = [1.0, 1.0, 1.0, 0.15];
+const GRID_MAJOR_COLOR: [f32; 4] = [1.0, 1.0, 1.0, 0.35];
+const X_COLOR: [f32; 4] = [1.0, 0.25, 0.25, 1.0];
+const Y_COLOR: [f32; 4] = [0.25, 1.0, 0.25, 1.0];
+const Z_COLOR: [f32; 4] = [0.3, 0.5, 1.0, 1.0];
+
+/// Wireframe gizmos, drawn over the scene - a marker showing where the sun is
+/// and the scene's bounding volume (see [`sdf_playground_shader::bounds()`]),
+/// so that it's easier to tell how things are laid out in space - along with
+/// the box estimated out of the distance field (see [`bounding_box`]), which
+/// shows whether the hand-written bounds are still up to date.
+///
+/// Separately, there's a ground grid with the axes, as a reference for scale
+/// and orientation.
+///
+/// Gizmos are projected on the CPU, using the same camera as the shader; the
+/// parts hidden behind the scene are drawn fainter.
+#[derive(Debug)]
+pub struct Gizmos {
+    pub enabled: bool,
+    pub grid: bool,
+
+    pipeline: wgpu::RenderPipeline,
+    bind_group_layout: wgpu::BindGroupLayout,
+    buffer: wgpu::Buffer,
+
+    /// Estimated bounding box, along with the scene and hot variables it was
+    /// estimated for - estimating takes a while, so it's not redone each
+    /// frame.
+    estimate: Option<(u32, Hot, Option<BoundingBox>)>,
+}
+
+impl Gizmos {
+    pub fn new(gpu: &Gpu) -> Self {
+        let device = gpu.device();
+
+        let module = device.create_shader_module(
+            wgpu::ShaderModuleDescriptor {
+                label: Some("gizmos_shader"),
+                source: wgpu::ShaderSource::Wgsl(
+                    include_str!("gizmos.wgsl").into(),
+                ),
+            },
+        );
+
+        let buffer =
+            device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some("gizmos_buffer"),
+                size: (MAX_VERTICES
+                    * mem::size_of::<Vertex>())
+                    as wgpu::BufferAddress,
+                usage: wgpu::BufferUsages::VERTEX
+                    | wgpu::BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            });
+
+        let bind_group_layout = device
+            .create_bind_group_layout(
+            &wgpu::BindGroupLayoutDescriptor {
+                label: Some("gizmos_bind_group_layout"),
+                entries: &[wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility:
+                        wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type:
+                            wgpu::TextureSampleType::Float {
+                                filterable: false,
+                            },
+                        view_dimension:
+                            wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                }],
+            },
+        );
+
+        let pipeline_layout = device
+            .create_pipeline_layout(
+                &wgpu::PipelineLayoutDescriptor {
+                    label: Some("gizmos_pipeline_layout"),
+                    bind_group_layouts: &[
+                        &bind_group_layout,
+                    ],
+                    push_constant_ranges: &[],
+                },
+            );
+
+        let pipeline = device.create_render_pipeline(
+            &wgpu::RenderPipelineDescriptor {
+                label: Some("gizmos_pipeline"),
+                layout: Some(&pipeline_layout),
+                vertex: wgpu::VertexState {
+                    module: &module,
+                    entry_point: "main_vs",
+                    buffers: &[wgpu::VertexBufferLayout {
+                        array_stride: mem::size_of::<Vertex>()
+                            as wgpu::BufferAddress,
+                        step_mode: wgpu::VertexStepMode::Vertex,
+                        attributes: &wgpu::vertex_attr_array![
+                            0 => Float32x4,
+                            1 => Float32x4,
+                        ],
+                    }],
+                },
+                primitive: wgpu::PrimitiveState {
+                    topology: wgpu::PrimitiveTopology::LineList,
+                    ..Default::default()
+                },
+                depth_stencil: None,
+                multisample: wgpu::MultisampleState::default(),
+                fragment: Some(wgpu::FragmentState {
+                    module: &module,
+                    entry_point: "main_fs",
+                    targets: &[Some(wgpu::ColorTargetState {
+                        format: gpu.format(),
+                        blend: Some(
+                            wgpu::BlendState::ALPHA_BLENDING,
+                        ),
+                        write_mask: wgpu::ColorWrites::ALL,
+                    })],
+                }),
+                multiview: None,
+            },
+        );
+
+        Self {
+            enabled: false,
+            grid: false,
+            pipeline,
+            bind_group_layout,
+            buffer,
+            estimate: None,
+        }
+    }
+
+    /// Recreates the pipeline on given GPU, preserving the settings - used
+    /// after the previous GPU has been lost.
+    pub fn recreate(&mut self, gpu: &Gpu) {
+        *self = Self {
+            enabled: self.enabled,
+            grid: self.grid,
+            ..Self::new(gpu)
+        };
+    }
+
+    /// Returns the estimated bounding box of current scene, estimating it if
+    /// the scene (or its hot variables) changed since the last time.
+    fn estimate(
+        &mut self,
+        params: &Params,
+    ) -> Option<BoundingBox> {
+        let is_stale = |(scene, hot, _): &(u32, Hot, _)| {
+            *scene != params.scene
+                || bytemuck::bytes_of(hot)
+                    != bytemuck::bytes_of(&params.hot)
+        };
+
+        if self.estimate.as_ref().map_or(true, is_stale) {
+            self.estimate = Some((
+                params.scene,
+                params.hot,
+                bounding_box::estimate(params),
+            ));
+        }
+
+        self.estimate.as_ref().and_then(|(_, _, bb)| *bb)
+    }
+
+    /// Draws gizmos over whatever the target already contains; `aov` is used
+    /// to tell which parts are hidden behind the scene.
+    pub fn render(
+        &mut self,
+        gpu: &Gpu,
+        encoder: &mut wgpu::CommandEncoder,
+        target: &wgpu::TextureView,
+        aov: &wgpu::TextureView,
+        params: &Params,
+    ) {
+        let estimate =
+            self.enabled.then(|| self.estimate(params));
+
+        let vertices = vertices(
+            params,
+            self.enabled,
+            self.grid,
+            estimate.flatten(),
+        );
+
+        if vertices.is_empty() {
+            return;
+        }
+
+        gpu.queue().write_buffer(
+            &self.buffer,
+            0,
+            bytemuck::cast_slice(&vertices),
+        );
+
+        let bind_group = gpu.device().create_bind_group(
+            &wgpu::BindGroupDescriptor {
+                label: Some("gizmos_bind_group"),
+                layout: &self.bind_group_layout,
+                entries: &[wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource:
+                        wgpu::BindingResource::TextureView(
+                            aov,
+                        ),
+                }],
+            },
+        );
+
+        let mut pass = encoder.begin_render_pass(
+            &wgpu::RenderPassDescriptor {
+                label: Some("gizmos_render_pass"),
+                color_attachments: &[Some(
+                    wgpu::RenderPassColorAttachment {
+                        view: target,
+                        resolve_target: None,
+                        ops: wgpu::Operations {
+                            load: wgpu::LoadOp::Load,
+                            store: true,
+                        },
+                    },
+                )],
+                depth_stencil_attachment: None,
+            },
+        );
+
+        pass.set_pipeline(&self.pipeline);
+        pass.set_bind_group(0, &bind_group, &[]);
+        pass.set_vertex_buffer(0, self.buffer.slice(..));
+        pass.draw(0..vertices.len() as u32, 0..1);
+    }
+}
+
+/// Returns vertices of all the enabled gizmos, as a list of lines.
+fn vertices(
+    params: &Params,
+    gizmos: bool,
+    grid: bool,
+    estimate: Option<BoundingBox>,
+) -> Vec<Vertex> {
+    let camera = Camera::new(params);
+    let mut lines = Vec::new();
+
+    if grid {
+        grid_lines(&mut lines);
+    }
+
+    if gizmos {
+        gizmo_lines(params, estimate, &mut lines);
+    }
+
+    lines
+        .into_iter()
+        .filter_map(|(a, b, color)| {
+            camera.line(a, b, color)
+        })
+        .flatten()
+        .take(MAX_VERTICES)
+        .collect()
+}
+
+/// Ground grid on the `y = 0` plane, with lines along the `x` and `z` axes
+/// highlighted, plus a short `y` axis - colored red, green and blue
+/// respectively, as usual.
+fn grid_lines(lines: &mut Vec<Line>) {
+    let extent = GRID_SIZE as f32;
+
+    for idx in -GRID_SIZE..=GRID_SIZE {
+        let offset = idx as f32;
+
+        let color = if idx % 5 == 0 {
+            GRID_MAJOR_COLOR
+        } else {
+            GRID_COLOR
+        };
+
+        // (the axes get drawn separately)
+        if idx != 0 {
+            lines.push((
+                vec3(-extent, 0.0, offset),
+                vec3(extent, 0.0, offset),
+                color,
+            ));
+
+            lines.push((
+                vec3(offset, 0.0, -extent),
+                vec3(offset, 0.0, extent),
+                color,
+            ));
+        }
+    }
+
+    lines.push((
+        vec3(-extent, 0.0, 0.0),
+        vec3(extent, 0.0, 0.0),
+        X_COLOR,
+    ));
+
+    lines.push((
+        Vec3::ZERO,
+        vec3(0.0, AXIS_LENGTH, 0.0),
+        Y_COLOR,
+    ));
+
+    lines.push((
+        vec3(0.0, 0.0, -extent),
+        vec3(0.0, 0.0, extent),
+        Z_COLOR,
+    ));
+}
+
+/// Sun's marker, the scene's bounding volume and its estimated bounding box.
+fn gizmo_lines(
+    params: &Params,
+    estimate: Option<BoundingBox>,
+    lines: &mut Vec<Line>,
+) {
+    // Sun - a line pointing towards it, ending with a small diamond
+    let sun = SUN.normalize() * SUN_DISTANCE;
+
+    lines.push((Vec3::ZERO, sun, SUN_COLOR));
+
+    for (a, b) in [
+        (Vec3::X, Vec3::Y),
+        (Vec3::Y, Vec3::Z),
+        (Vec3::Z, Vec3::X),
+    ] {
+        for (a_sign, b_sign) in [
+            (-1.0, -1.0),
+            (-1.0, 1.0),
+            (1.0, -1.0),
+            (1.0, 1.0),
+        ] {
+            lines.push((
+                sun + a * a_sign * 0.4,
+                sun + b * b_sign * 0.4,
+                SUN_COLOR,
+            ));
+        }
+    }
+
+    match sdf_playground_shader::bounds(params.scene) {
+        Some(Bounds::Sphere { radius }) => {
+            // Three great circles, one around each axis
+            const SEGMENTS: usize = 64;
+
+            let point = |axis: usize, idx: usize| {
+                let angle =
+                    idx as f32 / SEGMENTS as f32 * TAU;
+                let (sin, cos) = angle.sin_cos();
+
+                let point = match axis {
+                    0 => vec3(0.0, cos, sin),
+                    1 => vec3(cos, 0.0, sin),
+                    _ => vec3(cos, sin, 0.0),
+                };
+
+                point * radius
+            };
+
+            for axis in 0..3 {
+                for idx in 0..SEGMENTS {
+                    lines.push((
+                        point(axis, idx),
+                        point(axis, idx + 1),
+                        BOUNDS_COLOR,
+                    ));
+                }
+            }
+        }
+
+        Some(Bounds::Cuboid { half_size }) => {
+            cuboid_lines(
+                BoundingBox {
+                    min: -half_size,
+                    max: half_size,
+                },
+                BOUNDS_COLOR,
+                lines,
+            );
+        }
+
+        None => (),
+    }
+
+    if let Some(estimate) = estimate {
+        cuboid_lines(estimate, ESTIMATE_COLOR, lines);
+    }
+}
+
+/// Box's edges.
+fn cuboid_lines(
+    bb: BoundingBox,
+    color: [f32; 4],
+    lines: &mut Vec<Line>,
+) {
+    let corners = bb.corners();
+
+    // Each edge connects two corners differing in one coordinate
+    for corner in 0..8 {
+        for axis in 0..3 {
+            if corner & (1 << axis) != 0 {
+                continue;
+            }
+
+            lines.push((
+                corners[corner],
+                corners[corner | (1 << axis)],
+                color,
+            ));
+        }
+    }
+}
+
+/// Camera used by the shader (see `camera()` there), for projecting points
+/// onto the screen.
+struct Camera {
+    origin: Vec3,
+    orientation: Mat3,
+
+    /// See [`Params::frustum`].
+    min: Vec2,
+    max: Vec2,
+}
+
+impl Camera {
+    /// Points closer to the camera than this get clipped.
+    const NEAR: f32 = 0.01;
+
+    fn new(params: &Params) -> Self {
+        let origin = sdf_playground_shader::camera(params);
+
+        Self {
+            origin,
+            orientation: look_at(
+                origin,
+                params.target(),
+                Vec3::Y,
+            ),
+            min: vec2(params.frustum.x, params.frustum.y),
+            max: vec2(params.frustum.z, params.frustum.w),
+        }
+    }
+
+    /// Returns vertices of a line between given points, clipped to the part
+    /// in front of the camera - `None` if it's entirely behind.
+    fn line(
+        &self,
+        a: Vec3,
+        b: Vec3,
+        color: [f32; 4],
+    ) -> Option<[Vertex; 2]> {
+        // (`orientation` is orthonormal, so its transpose is its inverse)
+        let to_local = |point: Vec3| {
+            self.orientation.transpose()
+                * (point - self.origin)
+        };
+
+        let (mut a, mut b) = (to_local(a), to_local(b));
+
+        if a.z < Self::NEAR && b.z < Self::NEAR {
+            return None;
+        }
+
+        if a.z < Self::NEAR {
+            a = a.lerp(b, (Self::NEAR - a.z) / (b.z - a.z));
+        } else if b.z < Self::NEAR {
+            b = b.lerp(a, (Self::NEAR - b.z) / (a.z - b.z));
+        }
+
+        Some([self.vertex(a, color), self.vertex(b, color)])
+    }
+
+    /// Projects given point (in camera's space) - the inverse of
+    /// `ray_direction()` from `common`.
+    fn vertex(
+        &self,
+        point: Vec3,
+        color: [f32; 4],
+    ) -> Vertex {
+        let screen =
+            vec2(point.x / point.z, -point.y / point.z);
+
+        let uv =
+            (screen - self.min) / (self.max - self.min);
+
+        [
+            uv.x * 2.0 - 1.0,
+            1.0 - uv.y * 2.0,
+            point.length(),
+            0.0,
+            color[0],
+            color[1],
+            color[2],
+            color[3],
+        ]
+    }
+}
@@ -0,0 +1,39 @@
+// Draws gizmos over the scene, see `gizmos.rs`.
+
+struct VertexOutput {
+    @builtin(position) pos: vec4<f32>,
+    @location(0) distance: f32,
+    @location(1) color: vec4<f32>,
+}
+
+@group(0) @binding(0)
+var aov: texture_2d<f32>;
+
+// Vertices come already projected: `xy` is the position in clip space, `z` the
+// distance from the camera
+@vertex
+fn main_vs(
+    @location(0) pos: vec4<f32>,
+    @location(1) color: vec4<f32>,
+) -> VertexOutput {
+    var out: VertexOutput;
+    out.pos = vec4<f32>(pos.xy, 0.0, 1.0);
+    out.distance = pos.z;
+    out.color = color;
+    return out;
+}
+
+@fragment
+fn main_fs(in: VertexOutput) -> @location(0) vec4<f32> {
+    let size = vec2<i32>(textureDimensions(aov));
+    let scene = textureLoad(aov, clamp(vec2<i32>(in.pos.xy), vec2<i32>(0), size - 1), 0);
+
+    // Parts hidden behind the scene are drawn fainter, so that it's clear
+    // what's in front of what (pixels that haven't been rendered during this
+    // frame have a negative distance - let's treat them as empty)
+    if scene.w >= 0.0 && scene.w < in.distance {
+        return vec4<f32>(in.color.rgb, in.color.a * 0.3);
+    }
+
+    return in.color;
+}
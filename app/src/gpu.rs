@@ -0,0 +1,291 @@
+use crate::error::Error;
+use log::{error, info, warn};
+use std::sync::{Arc, Mutex};
+use winit::window::Window;
+
+/// Owns the connection with GPU and the window's surface.
+#[derive(Debug)]
+pub struct Gpu {
+    instance: wgpu::Instance,
+    surface: wgpu::Surface,
+    device: wgpu::Device,
+    queue: wgpu::Queue,
+    config: wgpu::SurfaceConfiguration,
+
+    /// First validation error that nobody has caught (through an error
+    /// scope) since the last [`Self::take_error()`].
+    error: Arc<Mutex<Option<String>>>,
+}
+
+impl Gpu {
+    /// Creates GPU rendering into given window; when `transparent` is true,
+    /// the window's surface gets composited using the alpha channel rendered
+    /// by the shader.
+    pub fn new(
+        window: &Window,
+        transparent: bool,
+    ) -> Result<Self, Error> {
+        let instance = wgpu::Instance::default();
+
+        // Safety: the surface doesn't outlive the window, since both live until
+        // the end of `main()`
+        let surface =
+            unsafe { instance.create_surface(window) }?;
+
+        let adapter = pollster::block_on(
+            instance.request_adapter(&wgpu::RequestAdapterOptions {
+                power_preference:
+                    wgpu::util::power_preference_from_env()
+                        .unwrap_or_default(),
+                force_fallback_adapter: false,
+                compatible_surface: Some(&surface),
+            }),
+        )
+        .ok_or(Error::NoAdapter)?;
+
+        info!("Using adapter: {:?}", adapter.get_info());
+
+        let mut features = wgpu::Features::empty();
+
+        // Timestamps let us tell how long each pass takes (see `GpuTimings`),
+        // but they're optional
+        if adapter
+            .features()
+            .contains(wgpu::Features::TIMESTAMP_QUERY)
+        {
+            features |= wgpu::Features::TIMESTAMP_QUERY;
+        }
+
+        let (device, queue) =
+            pollster::block_on(adapter.request_device(
+                &wgpu::DeviceDescriptor {
+                    label: Some("gpu_device"),
+                    features,
+                    limits: adapter.limits(),
+                },
+                None,
+            ))?;
+
+        // By default, wgpu panics on errors that haven't been caught by any
+        // error scope - e.g. when a hot-reloaded shader's interface doesn't
+        // match what we bind to it; instead, let's store the error so that
+        // the application can report it and roll back to something working
+        let error = Arc::new(Mutex::new(None));
+
+        device.on_uncaptured_error(Box::new({
+            let error = Arc::clone(&error);
+
+            move |err| {
+                let mut error = error.lock().unwrap();
+
+                // (the same error usually repeats every frame, so there's no
+                // point in logging it over and over)
+                if error.is_none() {
+                    error!("GPU error: {err}");
+                    *error = Some(err.to_string());
+                }
+            }
+        }));
+
+        let capabilities =
+            surface.get_capabilities(&adapter);
+
+        // Shader returns linear colors, so we need an sRGB surface to have them
+        // converted into what the monitor expects
+        let format = capabilities
+            .formats
+            .iter()
+            .copied()
+            .find(|format| format.is_srgb())
+            .unwrap_or(capabilities.formats[0]);
+
+        // Shader returns premultiplied colors (see
+        // `Params::background_color()`), which is what the compositor should
+        // expect; some platforms (e.g. macOS) support only the straight alpha,
+        // though, in which case partially transparent pixels come out a bit
+        // darker than they should
+        let alpha_mode = if transparent {
+            let caps = &capabilities.alpha_modes;
+
+            if caps.contains(
+                &wgpu::CompositeAlphaMode::PreMultiplied,
+            ) {
+                wgpu::CompositeAlphaMode::PreMultiplied
+            } else if caps.contains(
+                &wgpu::CompositeAlphaMode::PostMultiplied,
+            ) {
+                warn!("Premultiplied alpha is not supported here - semi-transparent pixels will come out darker");
+                wgpu::CompositeAlphaMode::PostMultiplied
+            } else {
+                warn!("Transparent windows are not supported here");
+                caps[0]
+            }
+        } else {
+            capabilities.alpha_modes[0]
+        };
+
+        let size = window.inner_size();
+
+        let config = wgpu::SurfaceConfiguration {
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+            format,
+            width: size.width.max(1),
+            height: size.height.max(1),
+            present_mode: wgpu::PresentMode::AutoVsync,
+            alpha_mode,
+            view_formats: vec![],
+        };
+
+        surface.configure(&device, &config);
+
+        Ok(Self {
+            instance,
+            surface,
+            device,
+            queue,
+            config,
+            error,
+        })
+    }
+
+    pub fn device(&self) -> &wgpu::Device {
+        &self.device
+    }
+
+    pub fn queue(&self) -> &wgpu::Queue {
+        &self.queue
+    }
+
+    /// Returns the first GPU error that has happened since the previous call,
+    /// if any (see `on_uncaptured_error` in [`Self::new()`]).
+    pub fn take_error(&self) -> Option<String> {
+        self.error.lock().unwrap().take()
+    }
+
+    pub fn format(&self) -> wgpu::TextureFormat {
+        self.config.format
+    }
+
+    pub fn resize(&mut self, width: u32, height: u32) {
+        // Minimized windows report a size of zero, which is not something we
+        // can configure the surface with
+        if width == 0 || height == 0 {
+            return;
+        }
+
+        self.config.width = width;
+        self.config.height = height;
+        self.surface.configure(&self.device, &self.config);
+    }
+
+    pub fn vsync(&self) -> bool {
+        self.config.present_mode
+            == wgpu::PresentMode::AutoVsync
+    }
+
+    pub fn set_vsync(&mut self, vsync: bool) {
+        self.config.present_mode = if vsync {
+            wgpu::PresentMode::AutoVsync
+        } else {
+            wgpu::PresentMode::AutoNoVsync
+        };
+
+        self.surface.configure(&self.device, &self.config);
+    }
+
+    /// Creates surface for another window, configured the same way as the
+    /// main one (so that the same pipelines can render into it).
+    ///
+    /// # Safety
+    ///
+    /// The surface mustn't outlive the window.
+    pub unsafe fn create_surface(
+        &self,
+        window: &Window,
+    ) -> Result<
+        (wgpu::Surface, wgpu::SurfaceConfiguration),
+        Error,
+    > {
+        let surface =
+            self.instance.create_surface(window)?;
+
+        let size = window.inner_size();
+
+        let config = wgpu::SurfaceConfiguration {
+            width: size.width.max(1),
+            height: size.height.max(1),
+            ..self.config.clone()
+        };
+
+        surface.configure(&self.device, &config);
+
+        Ok((surface, config))
+    }
+
+    /// Acquires the next frame and renders it using given function.
+    pub fn render(
+        &self,
+        f: impl FnOnce(
+            &mut wgpu::CommandEncoder,
+            &wgpu::TextureView,
+        ),
+    ) -> Result<(), wgpu::SurfaceError> {
+        self.render_to(&self.surface, f)
+    }
+
+    /// Same as [`Self::render()`], but renders into given surface (see
+    /// [`Self::create_surface()`]).
+    pub fn render_to(
+        &self,
+        surface: &wgpu::Surface,
+        f: impl FnOnce(
+            &mut wgpu::CommandEncoder,
+            &wgpu::TextureView,
+        ),
+    ) -> Result<(), wgpu::SurfaceError> {
+        let frame = surface.get_current_texture()?;
+
+        let view =
+            frame.texture.create_view(&Default::default());
+
+        let mut encoder =
+            self.device.create_command_encoder(
+                &wgpu::CommandEncoderDescriptor {
+                    label: Some("gpu_command_encoder"),
+                },
+            );
+
+        f(&mut encoder, &view);
+
+        self.queue.submit(Some(encoder.finish()));
+        frame.present();
+
+        Ok(())
+    }
+
+    /// Fills given target with black - used when there's nothing to render
+    /// yet (e.g. while the shader is still being compiled).
+    pub fn clear(
+        encoder: &mut wgpu::CommandEncoder,
+        target: &wgpu::TextureView,
+    ) {
+        encoder.begin_render_pass(
+            &wgpu::RenderPassDescriptor {
+                label: Some("gpu_clear_pass"),
+                color_attachments: &[Some(
+                    wgpu::RenderPassColorAttachment {
+                        view: target,
+                        resolve_target: None,
+                        ops: wgpu::Operations {
+                            load: wgpu::LoadOp::Clear(
+                                wgpu::Color::BLACK,
+                            ),
+                            store: true,
+                        },
+                    },
+                )],
+                depth_stencil_attachment: None,
+            },
+        );
+    }
+}
@@ -0,0 +1,348 @@
+use crate::gpu::Gpu;
+use crate::renderer::ReadbackState;
+use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// How many passes can be timed in a single frame.
+const PASSES: u32 = 16;
+
+/// Size of a single timestamp, in bytes.
+const TIMESTAMP_SIZE: u64 = 8;
+
+/// Per-pass GPU timings - a timestamp is written after each pass of the frame
+/// (see [`Self::mark()`]) and the differences between them tell how long each
+/// pass took, so that the cost of each rendering feature can be told apart.
+///
+/// Like the renderer's stats, timings get downloaded without stalling the GPU,
+/// so they arrive with a couple of frames of delay - and only every couple of
+/// frames.
+///
+/// Requires [`wgpu::Features::TIMESTAMP_QUERY`]; on GPUs that don't support
+/// it, nothing gets measured.
+#[derive(Debug)]
+pub struct GpuTimings {
+    queries: Option<Queries>,
+
+    /// Labels of the passes marked so far during the current frame; `None` if
+    /// the current frame isn't being timed (e.g. because the previous one is
+    /// still being downloaded).
+    labels: Option<Vec<&'static str>>,
+
+    /// Labels of the frame being downloaded.
+    pending: Vec<&'static str>,
+
+    /// Most recently downloaded timings - labels and durations (in
+    /// milliseconds), in the order the passes were rendered.
+    timings: Vec<(&'static str, f32)>,
+}
+
+struct Queries {
+    query_set: wgpu::QuerySet,
+
+    /// Buffer the timestamps get resolved into.
+    buffer: wgpu::Buffer,
+
+    /// Buffer we copy `buffer` into, so that it can be mapped while the GPU
+    /// keeps on rendering further frames.
+    readback: wgpu::Buffer,
+
+    state: ReadbackState,
+
+    /// Set by `map_async()`'s callback, once `readback` is ready to be read.
+    mapped: Arc<AtomicBool>,
+
+    /// How many nanoseconds a single tick of the timestamp is worth.
+    period: f32,
+}
+
+// (`wgpu::QuerySet` doesn't implement `Debug`)
+impl fmt::Debug for Queries {
+    fn fmt(
+        &self,
+        f: &mut fmt::Formatter<'_>,
+    ) -> fmt::Result {
+        f.debug_struct("Queries")
+            .field("state", &self.state)
+            .field("period", &self.period)
+            .finish_non_exhaustive()
+    }
+}
+
+impl GpuTimings {
+    pub fn new(gpu: &Gpu) -> Self {
+        let device = gpu.device();
+
+        let queries = device
+            .features()
+            .contains(wgpu::Features::TIMESTAMP_QUERY)
+            .then(|| {
+                let size =
+                    (PASSES + 1) as u64 * TIMESTAMP_SIZE;
+
+                let query_set = device.create_query_set(
+                    &wgpu::QuerySetDescriptor {
+                        label: Some("gpu_timings_query_set"),
+                        ty: wgpu::QueryType::Timestamp,
+                        count: PASSES + 1,
+                    },
+                );
+
+                let buffer = device.create_buffer(
+                    &wgpu::BufferDescriptor {
+                        label: Some("gpu_timings_buffer"),
+                        size,
+                        usage: wgpu::BufferUsages::QUERY_RESOLVE
+                            | wgpu::BufferUsages::COPY_SRC,
+                        mapped_at_creation: false,
+                    },
+                );
+
+                let readback = device.create_buffer(
+                    &wgpu::BufferDescriptor {
+                        label: Some("gpu_timings_readback"),
+                        size,
+                        usage: wgpu::BufferUsages::MAP_READ
+                            | wgpu::BufferUsages::COPY_DST,
+                        mapped_at_creation: false,
+                    },
+                );
+
+                Queries {
+                    query_set,
+                    buffer,
+                    readback,
+                    state: ReadbackState::Idle,
+                    mapped: Default::default(),
+                    period: gpu.queue().get_timestamp_period(),
+                }
+            });
+
+        Self {
+            queries,
+            labels: None,
+            pending: Vec::new(),
+            timings: Vec::new(),
+        }
+    }
+
+    /// Recreates the queries on given GPU - used after the previous GPU has
+    /// been lost.
+    pub fn recreate(&mut self, gpu: &Gpu) {
+        *self = Self::new(gpu);
+    }
+
+    /// Starts timing a frame, if the previous one has been downloaded already;
+    /// called before the frame's first pass.
+    pub fn begin(
+        &mut self,
+        encoder: &mut wgpu::CommandEncoder,
+    ) {
+        let Some(queries) = &self.queries else {
+            return;
+        };
+
+        if queries.state != ReadbackState::Idle {
+            return;
+        }
+
+        encoder.write_timestamp(&queries.query_set, 0);
+        self.labels = Some(Vec::new());
+    }
+
+    /// Records that given pass has just been finished - the time since the
+    /// previous mark (or since [`Self::begin()`]) gets attributed to it.
+    ///
+    /// Passes marked more than once (e.g. rendering the scene with two
+    /// shaders, in split-screen mode) get their timings summed up.
+    pub fn mark(
+        &mut self,
+        encoder: &mut wgpu::CommandEncoder,
+        label: &'static str,
+    ) {
+        let (Some(queries), Some(labels)) =
+            (&self.queries, &mut self.labels)
+        else {
+            return;
+        };
+
+        if labels.len() as u32 >= PASSES {
+            return;
+        }
+
+        labels.push(label);
+
+        encoder.write_timestamp(
+            &queries.query_set,
+            labels.len() as u32,
+        );
+    }
+
+    /// Finishes timing the frame, recording the download of its timestamps;
+    /// called after the frame's last pass.
+    pub fn finish(
+        &mut self,
+        encoder: &mut wgpu::CommandEncoder,
+    ) {
+        let (Some(queries), Some(labels)) =
+            (&mut self.queries, self.labels.take())
+        else {
+            return;
+        };
+
+        let count = labels.len() as u32 + 1;
+
+        encoder.resolve_query_set(
+            &queries.query_set,
+            0..count,
+            &queries.buffer,
+            0,
+        );
+
+        encoder.copy_buffer_to_buffer(
+            &queries.buffer,
+            0,
+            &queries.readback,
+            0,
+            count as u64 * TIMESTAMP_SIZE,
+        );
+
+        queries.state = ReadbackState::Copied;
+        self.pending = labels;
+    }
+
+    /// Moves the download forward; called once per frame, before rendering
+    /// (i.e. after the previous frame has been submitted).
+    pub fn poll(&mut self, device: &wgpu::Device) {
+        let Some(queries) = &mut self.queries else {
+            return;
+        };
+
+        match queries.state {
+            ReadbackState::Idle => {
+                //
+            }
+
+            ReadbackState::Copied => {
+                let mapped = queries.mapped.clone();
+
+                queries.readback.slice(..).map_async(
+                    wgpu::MapMode::Read,
+                    move |result| {
+                        if result.is_ok() {
+                            mapped.store(
+                                true,
+                                Ordering::Release,
+                            );
+                        }
+                    },
+                );
+
+                queries.state = ReadbackState::Mapping;
+            }
+
+            ReadbackState::Mapping => {
+                device.poll(wgpu::Maintain::Poll);
+
+                if !queries
+                    .mapped
+                    .swap(false, Ordering::Acquire)
+                {
+                    return;
+                }
+
+                let timestamps: Vec<u64> = queries
+                    .readback
+                    .slice(..)
+                    .get_mapped_range()
+                    .chunks_exact(TIMESTAMP_SIZE as usize)
+                    .map(|bytes| {
+                        u64::from_le_bytes(
+                            bytes.try_into().unwrap(),
+                        )
+                    })
+                    .collect();
+
+                queries.readback.unmap();
+                queries.state = ReadbackState::Idle;
+
+                let mut timings: Vec<(&'static str, f32)> =
+                    Vec::new();
+
+                for (label, ticks) in self
+                    .pending
+                    .iter()
+                    .zip(timestamps.windows(2))
+                {
+                    // (timestamps aren't guaranteed to be monotonic, e.g.
+                    // when the GPU changes its clock mid-frame)
+                    let ms = ticks[1]
+                        .saturating_sub(ticks[0])
+                        as f32
+                        * queries.period
+                        / 1_000_000.0;
+
+                    match timings
+                        .iter_mut()
+                        .find(|(name, _)| name == label)
+                    {
+                        Some((_, total)) => *total += ms,
+                        None => timings.push((label, ms)),
+                    }
+                }
+
+                self.timings = timings;
+            }
+        }
+    }
+
+    /// Returns whether the GPU supports timing passes - if not, nothing ever
+    /// gets measured.
+    pub fn supported(&self) -> bool {
+        self.queries.is_some()
+    }
+
+    /// Returns how long all passes of the most recently downloaded frame took,
+    /// or `None` if nothing's been downloaded yet.
+    pub fn total(&self) -> Option<Duration> {
+        if self.timings.is_empty() {
+            return None;
+        }
+
+        let ms: f32 =
+            self.timings.iter().map(|(_, ms)| ms).sum();
+
+        Some(Duration::from_secs_f32(ms / 1000.0))
+    }
+
+    /// Returns the breakdown's text - time taken by each pass and in total.
+    pub fn text(&self) -> Vec<String> {
+        let mut text =
+            vec!["gpu timings".to_owned(), String::new()];
+
+        if self.queries.is_none() {
+            text.push(
+                "not supported by this gpu (no timestamp queries)"
+                    .to_owned(),
+            );
+
+            return text;
+        }
+
+        text.extend(self.timings.iter().map(
+            |(label, ms)| {
+                format!("{label:<14} {ms:>7.2} ms")
+            },
+        ));
+
+        let total: f32 =
+            self.timings.iter().map(|(_, ms)| ms).sum();
+
+        text.push(format!(
+            "{:<14} {total:>7.2} ms",
+            "total"
+        ));
+        text
+    }
+}
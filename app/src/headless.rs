@@ -0,0 +1,107 @@
+//! Off-screen rendering, shared by the modes that render without showing a
+//! window - benchmarking (`--bench`) and perfect loops (`--loop`).
+
+use crate::compiler::{Compiler, CompilerEvent};
+use crate::error::Error;
+use crate::gpu::Gpu;
+use crate::renderer::Renderer;
+use log::error;
+use std::fmt::Display;
+use std::path::PathBuf;
+use std::time::Duration;
+use std::{process, thread};
+use winit::event_loop::EventLoop;
+use winit::window::{Window, WindowBuilder};
+
+/// GPU, renderer (with the shader already compiled) and a texture of fixed
+/// size to render into.
+#[derive(Debug)]
+pub struct Headless {
+    pub gpu: Gpu,
+    pub renderer: Renderer,
+    pub target: wgpu::TextureView,
+
+    /// We don't render anything into the window, but we need it to get a GPU
+    /// that's able to present to it (i.e. the same GPU as in the regular
+    /// mode).
+    _window: Window,
+}
+
+impl Headless {
+    /// Sets everything up, compiling the shader along the way - exits if
+    /// anything fails, since there's no window to show the error in.
+    pub fn new(
+        event_loop: &EventLoop<()>,
+        label: &str,
+        width: u32,
+        height: u32,
+    ) -> Self {
+        let window = WindowBuilder::new()
+            .with_visible(false)
+            .build(event_loop)
+            .unwrap_or_else(|err| fail(Error::from(err)));
+
+        let gpu = Gpu::new(&window, false)
+            .unwrap_or_else(|err| fail(err));
+
+        let renderer = Renderer::new(&gpu, compile())
+            .unwrap_or_else(|err| fail(err));
+
+        let target = gpu
+            .device()
+            .create_texture(&wgpu::TextureDescriptor {
+                label: Some(label),
+                size: wgpu::Extent3d {
+                    width,
+                    height,
+                    depth_or_array_layers: 1,
+                },
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: wgpu::TextureDimension::D2,
+                format: gpu.format(),
+                usage:
+                    wgpu::TextureUsages::RENDER_ATTACHMENT,
+                view_formats: &[],
+            })
+            .create_view(&Default::default());
+
+        Self {
+            gpu,
+            renderer,
+            target,
+            _window: window,
+        }
+    }
+}
+
+/// Waits for the compiler to build the shader and returns path to it.
+fn compile() -> PathBuf {
+    let compiler = Compiler::spawn();
+
+    loop {
+        match compiler.poll() {
+            Some(CompilerEvent::Succeeded {
+                path, ..
+            }) => {
+                return path;
+            }
+
+            Some(CompilerEvent::Failed { error }) => {
+                fail(format!(
+                    "Couldn't compile shader: {error}"
+                ));
+            }
+
+            _ => {
+                thread::sleep(Duration::from_millis(10));
+            }
+        }
+    }
+}
+
+/// Logs given error and exits - there's no window to show it in.
+pub fn fail(err: impl Display) -> ! {
+    error!("{err}");
+    process::exit(1);
+}
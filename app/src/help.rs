@@ -0,0 +1,167 @@
+//! Help overlay (`/`) - lists all the key bindings (as currently configured in
+//! the keymap), values of the most interesting parameters and statistics of
+//! the rendered frames.
+
+use crate::keymap::{key_name, Keymap};
+use sdf_playground_common::{
+    debug_view, shading, stereo, Params, Stats,
+};
+
+/// Returns the help's text - key bindings on the left, parameters and stats
+/// on the right.
+pub fn text(
+    keymap: &Keymap,
+    params: &Params,
+    stats: Option<Stats>,
+) -> Vec<String> {
+    let bindings: Vec<_> = keymap
+        .bindings()
+        .map(|(name, _, key)| {
+            format!(
+                "{:>6}  {}",
+                key_name(key),
+                name.replace('_', " ")
+            )
+        })
+        .collect();
+
+    let on_off = |val: bool| if val { "on" } else { "off" };
+
+    let debug_view = match params.debug_view {
+        debug_view::NONE => "none",
+        debug_view::SLICE => "slice",
+        debug_view::LIPSCHITZ => "lipschitz",
+        debug_view::STEPS => "steps",
+        debug_view::EXPOSURE => "exposure",
+        _ => "?",
+    };
+
+    let shading = match params.shading {
+        shading::REGULAR => "regular",
+        shading::TOON => "toon",
+        shading::MATCAP => "matcap",
+        _ => "?",
+    };
+
+    let stereo = match params.stereo {
+        stereo::OFF => "off",
+        stereo::ANAGLYPH => "anaglyph",
+        stereo::SIDE_BY_SIDE => "side-by-side",
+        _ => "?",
+    };
+
+    let coarse = match params.coarse {
+        0 => "off".to_owned(),
+        coarse => format!("1/{coarse}"),
+    };
+
+    let epsilon = if params.epsilon > 0.0 {
+        format!("{}", params.epsilon)
+    } else {
+        "adaptive".to_owned()
+    };
+
+    let pixels =
+        (params.width * params.height).max(1) as f32;
+
+    let mut params = vec![
+        ("scene", params.scene.to_string()),
+        ("time", format!("{:.1}", params.time)),
+        ("debug view", debug_view.to_owned()),
+        ("shading", shading.to_owned()),
+        ("steps", params.steps.to_string()),
+        ("shadow steps", params.shadow_steps.to_string()),
+        ("samples", params.samples.to_string()),
+        ("epsilon", epsilon),
+        ("coarse", coarse),
+        (
+            "foveated",
+            on_off(params.fovea.z > 0.0).to_owned(),
+        ),
+        (
+            "checkerboard",
+            on_off(params.checkerboard != 0).to_owned(),
+        ),
+        (
+            "motion blur",
+            on_off(params.shutter > 0.0).to_owned(),
+        ),
+        (
+            "relief",
+            on_off(params.relief.x != 0.0).to_owned(),
+        ),
+        (
+            "shadow catcher",
+            on_off(params.shadow_catcher != 0).to_owned(),
+        ),
+        ("subsurface", format!("{:.1}", params.subsurface)),
+        ("stereo", stereo.to_owned()),
+        (
+            "eye separation",
+            format!("{:.2}", params.eye_separation),
+        ),
+        ("orbit", format!("{:.2}", params.orbit)),
+    ];
+
+    if let Some(stats) = stats {
+        let rays = stats.rays.max(1) as f32;
+
+        params.extend([
+            ("", String::new()),
+            ("rays", stats.rays.to_string()),
+            (
+                "hits",
+                format!(
+                    "{:.1}%",
+                    100.0 * stats.hits as f32 / rays
+                ),
+            ),
+            (
+                "steps per ray",
+                format!("{:.1}", stats.steps as f32 / rays),
+            ),
+            (
+                "exhausted rays",
+                format!(
+                    "{:.1}%",
+                    100.0 * stats.exhausted_rays as f32
+                        / rays
+                ),
+            ),
+            (
+                "exhausted px",
+                format!(
+                    "{:.1}%",
+                    100.0 * stats.exhausted as f32 / pixels
+                ),
+            ),
+        ]);
+    }
+
+    let params: Vec<_> = params
+        .iter()
+        .map(|(name, value)| format!("{name:<14}  {value}"))
+        .collect();
+
+    let bindings_width = bindings
+        .iter()
+        .map(|line| line.chars().count())
+        .max()
+        .unwrap_or(0);
+
+    (0..bindings.len().max(params.len()))
+        .map(|idx| {
+            let binding = bindings
+                .get(idx)
+                .map_or("", |line| line.as_str());
+
+            let param = params
+                .get(idx)
+                .map_or("", |line| line.as_str());
+
+            format!("{binding:<bindings_width$}    {param}")
+                .trim_end()
+                .to_owned()
+        })
+        .collect()
+}
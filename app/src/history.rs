@@ -0,0 +1,309 @@
+//! Shader history (`SDF_PLAYGROUND_HISTORY`) - keeps a snapshot of each shader
+//! that got compiled and accepted by the GPU, so that live-coding experiments
+//! can be rolled back past the editor's undo limits.
+//!
+//! Snapshots are plain copies of the shader crate (`Cargo.toml` and `src/`),
+//! stored in directories named after the moment they were taken (in UTC); they
+//! can be browsed and restored with `R`.
+
+use crate::compiler::{cache_dir, copy_files, files};
+use crate::error::Error;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use std::{env, fs};
+use winit::event::VirtualKeyCode;
+use winit_input_helper::WinitInputHelper;
+
+/// How many snapshots are shown at once.
+const RESULTS: usize = 12;
+
+#[derive(Clone, Debug)]
+pub struct History {
+    dir: PathBuf,
+}
+
+impl History {
+    /// Returns the history pointed at by `SDF_PLAYGROUND_HISTORY` - either a
+    /// directory, or `1` for `history` inside the cache directory; `None` if
+    /// the history is disabled (which is the default).
+    pub fn from_env() -> Option<Self> {
+        let dir = env::var_os("SDF_PLAYGROUND_HISTORY")?;
+
+        let dir = if dir == "0" {
+            return None;
+        } else if dir == "1" {
+            cache_dir().join("history")
+        } else {
+            PathBuf::from(dir)
+        };
+
+        Some(Self { dir })
+    }
+
+    /// Stores a snapshot of given crate, unless it's the same as the most
+    /// recent one.
+    pub fn record(
+        &self,
+        crate_dir: &Path,
+    ) -> Result<(), Error> {
+        if let Some(latest) = self.snapshots().first() {
+            if same_files(&self.dir.join(latest), crate_dir)
+            {
+                return Ok(());
+            }
+        }
+
+        let name = timestamp(SystemTime::now());
+
+        // (two snapshots within the same second are rare, but possible)
+        let dir = (1..)
+            .map(|idx| match idx {
+                1 => self.dir.join(&name),
+                idx => {
+                    self.dir.join(format!("{name}-{idx}"))
+                }
+            })
+            .find(|dir| !dir.exists())
+            .unwrap();
+
+        copy_files(crate_dir, &dir)
+    }
+
+    /// Returns names of all snapshots, the most recent first.
+    pub fn snapshots(&self) -> Vec<String> {
+        let Ok(entries) = fs::read_dir(&self.dir) else {
+            return Vec::new();
+        };
+
+        let mut snapshots: Vec<_> = entries
+            .flatten()
+            .filter(|entry| entry.path().is_dir())
+            .filter_map(|entry| {
+                entry.file_name().into_string().ok()
+            })
+            .collect();
+
+        // (timestamps are zero-padded, so they sort chronologically)
+        snapshots.sort_by(|a, b| b.cmp(a));
+        snapshots
+    }
+
+    /// Replaces sources of given crate with the ones from given snapshot.
+    ///
+    /// Current sources get recorded first (they might've never compiled, so
+    /// they wouldn't be in the history yet), so restoring can be undone by
+    /// restoring the most recent snapshot.
+    pub fn restore(
+        &self,
+        snapshot: &str,
+        crate_dir: &Path,
+    ) -> Result<(), Error> {
+        self.record(crate_dir)?;
+
+        let snapshot = self.dir.join(snapshot);
+        let src_dir = crate_dir.join("src");
+
+        // Files created after the snapshot would otherwise linger around (and
+        // possibly break the compilation)
+        fs::remove_dir_all(&src_dir)
+            .map_err(Error::io(&src_dir))?;
+
+        for file in files(&snapshot) {
+            // (`files()` returns paths inside of `snapshot`, so the prefix is
+            // there)
+            let dst = crate_dir.join(
+                file.strip_prefix(&snapshot).unwrap(),
+            );
+
+            if let Some(dir) = dst.parent() {
+                fs::create_dir_all(dir)
+                    .map_err(Error::io(dir))?;
+            }
+
+            fs::copy(&file, &dst)
+                .map_err(Error::io(dst))?;
+        }
+
+        Ok(())
+    }
+
+    /// Returns whether given snapshot matches given crate.
+    pub fn is_current(
+        &self,
+        snapshot: &str,
+        crate_dir: &Path,
+    ) -> bool {
+        same_files(&self.dir.join(snapshot), crate_dir)
+    }
+}
+
+/// Overlay listing the snapshots, one of which can be picked with arrows and
+/// `Enter` to restore it.
+#[derive(Debug)]
+pub struct HistoryBrowser {
+    snapshots: Vec<String>,
+
+    /// Snapshot matching the current sources, if any.
+    current: Option<usize>,
+
+    /// Index of the highlighted snapshot.
+    selected: usize,
+}
+
+/// What happened to the browser after processing the input.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum HistoryEvent {
+    /// Browser is still open.
+    Idle,
+
+    /// Browser has been closed without picking anything.
+    Closed,
+
+    /// Given snapshot has been picked - the browser should be closed and the
+    /// snapshot restored.
+    Picked(String),
+}
+
+impl HistoryBrowser {
+    pub fn new(
+        history: &History,
+        crate_dir: &Path,
+    ) -> Self {
+        let snapshots = history.snapshots();
+
+        let current =
+            snapshots.iter().position(|snapshot| {
+                history.is_current(snapshot, crate_dir)
+            });
+
+        Self {
+            snapshots,
+            current,
+            selected: 0,
+        }
+    }
+
+    /// Handles moving the selection with arrows and confirming it with
+    /// `Enter` (or closing the browser with `Esc`).
+    pub fn update(
+        &mut self,
+        input: &WinitInputHelper,
+    ) -> HistoryEvent {
+        if input.key_pressed(VirtualKeyCode::Escape) {
+            return HistoryEvent::Closed;
+        }
+
+        if input.key_pressed(VirtualKeyCode::Up) {
+            self.selected = self.selected.saturating_sub(1);
+        }
+
+        if input.key_pressed(VirtualKeyCode::Down) {
+            self.selected = (self.selected + 1).min(
+                self.snapshots.len().saturating_sub(1),
+            );
+        }
+
+        if input.key_pressed(VirtualKeyCode::Return) {
+            return match self.snapshots.get(self.selected) {
+                Some(snapshot) => {
+                    HistoryEvent::Picked(snapshot.clone())
+                }
+                None => HistoryEvent::Closed,
+            };
+        }
+
+        HistoryEvent::Idle
+    }
+
+    /// Returns the browser's text - the snapshots, the most recent first.
+    pub fn text(&self) -> Vec<String> {
+        // Scroll the list so that the selection is always visible
+        let skip =
+            self.selected.saturating_sub(RESULTS - 1);
+
+        let mut text = vec![
+            "shader history (enter restores)".to_owned(),
+            String::new(),
+        ];
+
+        text.extend(
+            self.snapshots
+                .iter()
+                .enumerate()
+                .skip(skip)
+                .take(RESULTS)
+                .map(|(idx, snapshot)| {
+                    let cursor = if idx == self.selected {
+                        '>'
+                    } else {
+                        ' '
+                    };
+
+                    let current =
+                        if Some(idx) == self.current {
+                            "  (current)"
+                        } else {
+                            ""
+                        };
+
+                    format!("{cursor} {snapshot}{current}")
+                }),
+        );
+
+        if self.snapshots.is_empty() {
+            text.push("  no snapshots yet".into());
+        }
+
+        text
+    }
+}
+
+/// Returns whether both crates consist of the same files with the same
+/// contents.
+fn same_files(a: &Path, b: &Path) -> bool {
+    let (a_files, b_files) = (files(a), files(b));
+
+    a_files.len() == b_files.len()
+        && a_files.iter().zip(&b_files).all(
+            |(a_file, b_file)| {
+                a_file.strip_prefix(a)
+                    == b_file.strip_prefix(b)
+                    && fs::read(a_file).ok().is_some_and(
+                        |a_file| {
+                            fs::read(b_file).ok()
+                                == Some(a_file)
+                        },
+                    )
+            },
+        )
+}
+
+/// Formats given moment as `YYYY-MM-DD_HH-MM-SS` (in UTC).
+pub fn timestamp(time: SystemTime) -> String {
+    let secs = time
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |time| time.as_secs());
+
+    let (days, secs) = (secs / 86400, secs % 86400);
+
+    // Converts days since 1970-01-01 into a date, see:
+    // http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+    let z = days + 719_468;
+    let era = z / 146_097;
+    let doe = z % 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36524
+        - doe / 146_096)
+        / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + (month <= 2) as u64;
+
+    format!(
+        "{year:04}-{month:02}-{day:02}_{:02}-{:02}-{:02}",
+        secs / 3600,
+        secs / 60 % 60,
+        secs % 60
+    )
+}
@@ -0,0 +1,290 @@
+//! Hot variables (`W`) - literals in the shader's scene code wrapped in
+//! `hot!("name", default)` get found in the sources and listed as sliders, so
+//! that they can be tweaked live, without waiting for the compiler:
+//!
+//! ```rust,ignore
+//! sdf::sphere(point, hot!("radius", 5.0))
+//! ```
+//!
+//! Tweaks survive recompiling the shader (as long as the variable stays
+//! there) and get passed to it through [`Params::hot`] - see [`Hot`] for the
+//! shader's side of things.
+
+use crate::compiler::{files, shader_dir};
+use log::warn;
+use sdf_playground_common::{Hot, Params};
+use std::fs;
+use winit::event::VirtualKeyCode;
+use winit_input_helper::WinitInputHelper;
+
+/// How many variables are shown at once.
+const RESULTS: usize = 12;
+
+/// Width of the slider, in characters.
+const SLIDER_WIDTH: usize = 21;
+
+#[derive(Debug, Default)]
+pub struct HotVariables {
+    variables: Vec<Variable>,
+
+    /// Index of the highlighted variable.
+    selected: usize,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct Variable {
+    name: String,
+    default: f32,
+
+    /// Value set in the application, if it's been tweaked.
+    value: Option<f32>,
+}
+
+impl Variable {
+    fn value(&self) -> f32 {
+        self.value.unwrap_or(self.default)
+    }
+
+    /// How much the value changes with a single press of an arrow - and
+    /// (times `SLIDER_WIDTH / 2`) how far the slider reaches.
+    fn step(&self) -> f32 {
+        self.default.abs().max(0.1) * 0.05
+    }
+}
+
+impl HotVariables {
+    /// Finds hot variables in the shader's sources, keeping the tweaks of the
+    /// ones that are still there.
+    pub fn scan(&mut self) {
+        let crate_dir = shader_dir();
+
+        let mut variables: Vec<Variable> = Vec::new();
+
+        for file in files(&crate_dir) {
+            if file
+                .extension()
+                .is_some_and(|ext| ext != "rs")
+            {
+                continue;
+            }
+
+            let Ok(code) = fs::read_to_string(&file) else {
+                continue;
+            };
+
+            for (name, default) in find(&code) {
+                // (`scene_dual()` mirrors `scene()`, so most variables are
+                // there twice)
+                if variables
+                    .iter()
+                    .any(|var| var.name == name)
+                {
+                    continue;
+                }
+
+                let value = self
+                    .variables
+                    .iter()
+                    .find(|var| var.name == name)
+                    .and_then(|var| var.value);
+
+                variables.push(Variable {
+                    name,
+                    default,
+                    value,
+                });
+            }
+        }
+
+        self.variables = variables;
+        self.selected = self
+            .selected
+            .min(self.variables.len().saturating_sub(1));
+    }
+
+    /// Passes tweaked values to the shader.
+    pub fn apply(&self, params: &mut Params) {
+        params.hot = Hot::default();
+
+        for var in &self.variables {
+            if let Some(value) = var.value {
+                params.hot.set(Hot::key(&var.name), value);
+            }
+        }
+    }
+
+    /// Handles moving the selection with `Up` / `Down`, tweaking the value
+    /// with `Left` / `Right` (`Shift` for finer steps) and resetting it with
+    /// `Backspace`; returns `false` once the list gets closed with `Esc`.
+    pub fn update(
+        &mut self,
+        input: &WinitInputHelper,
+    ) -> bool {
+        if input.key_pressed(VirtualKeyCode::Escape) {
+            return false;
+        }
+
+        if input.key_pressed(VirtualKeyCode::Up) {
+            self.selected = self.selected.saturating_sub(1);
+        }
+
+        if input.key_pressed(VirtualKeyCode::Down) {
+            self.selected = (self.selected + 1).min(
+                self.variables.len().saturating_sub(1),
+            );
+        }
+
+        let tweaked = self
+            .variables
+            .iter()
+            .filter(|var| var.value.is_some())
+            .count();
+
+        let Some(var) =
+            self.variables.get_mut(self.selected)
+        else {
+            return true;
+        };
+
+        if input.key_pressed(VirtualKeyCode::Back) {
+            var.value = None;
+        }
+
+        let direction = match (
+            input.key_pressed(VirtualKeyCode::Left),
+            input.key_pressed(VirtualKeyCode::Right),
+        ) {
+            (true, false) => -1.0,
+            (false, true) => 1.0,
+            _ => return true,
+        };
+
+        if var.value.is_none() && tweaked >= Hot::CAPACITY {
+            warn!(
+                "At most {} hot variables can be tweaked at once - reset \
+                 some of them with Backspace first",
+                Hot::CAPACITY
+            );
+
+            return true;
+        }
+
+        let step = if input.held_shift() {
+            var.step() / 10.0
+        } else {
+            var.step()
+        };
+
+        var.value = Some(var.value() + direction * step);
+
+        true
+    }
+
+    /// Returns the list's text - the variables, with their sliders.
+    pub fn text(&self) -> Vec<String> {
+        // Scroll the list so that the selection is always visible
+        let skip =
+            self.selected.saturating_sub(RESULTS - 1);
+
+        let name_width = self
+            .variables
+            .iter()
+            .map(|var| var.name.len())
+            .max()
+            .unwrap_or(0);
+
+        let mut text = vec![
+            "hot variables (arrows tweak, backspace resets)"
+                .to_owned(),
+            String::new(),
+        ];
+
+        text.extend(
+            self.variables
+                .iter()
+                .enumerate()
+                .skip(skip)
+                .take(RESULTS)
+                .map(|(idx, var)| {
+                    let cursor = if idx == self.selected {
+                        '>'
+                    } else {
+                        ' '
+                    };
+
+                    let tweaked = if var.value.is_some() {
+                        '*'
+                    } else {
+                        ' '
+                    };
+
+                    format!(
+                        "{cursor} {:name_width$}  {} {:>9.3}{tweaked}",
+                        var.name,
+                        slider(var),
+                        var.value(),
+                    )
+                }),
+        );
+
+        if self.variables.is_empty() {
+            text.push(
+                "  none yet - wrap a literal in hot!(\"name\", 1.0)"
+                    .into(),
+            );
+        }
+
+        text
+    }
+}
+
+/// Draws given variable's slider, centered at its default value.
+fn slider(var: &Variable) -> String {
+    let half = (SLIDER_WIDTH / 2) as f32;
+    let offset = (var.value() - var.default) / var.step();
+    let pos = (half + offset).round().clamp(0.0, 2.0 * half)
+        as usize;
+
+    (0..SLIDER_WIDTH)
+        .map(|idx| {
+            if idx == pos {
+                '|'
+            } else if idx == SLIDER_WIDTH / 2 {
+                '+'
+            } else {
+                '-'
+            }
+        })
+        .collect()
+}
+
+/// Returns names and defaults of all `hot!()`s in given code, skipping the
+/// ones whose default isn't a plain number.
+fn find(code: &str) -> Vec<(String, f32)> {
+    // (calls can span multiple lines, once they get formatted)
+    let code: String = code
+        .lines()
+        .filter(|line| !line.trim_start().starts_with("//"))
+        .collect();
+
+    code.split("hot!(")
+        .skip(1)
+        .filter_map(|call| {
+            let call =
+                call.trim_start().strip_prefix('"')?;
+            let (name, rest) = call.split_once('"')?;
+            let rest =
+                rest.trim_start().strip_prefix(',')?;
+            let (default, _) = rest.split_once(')')?;
+
+            let default = default
+                .trim()
+                .trim_end_matches("f32")
+                .replace('_', "")
+                .parse()
+                .ok()?;
+
+            Some((name.to_owned(), default))
+        })
+        .collect()
+}
@@ -0,0 +1,26 @@
+//! Encoding of the images we save - frames, contact sheets and thumbnails.
+
+/// Encodes given 8-bit pixels as PNG.
+pub fn encode_png(
+    width: u32,
+    height: u32,
+    color: png::ColorType,
+    pixels: &[u8],
+) -> Vec<u8> {
+    let mut png = Vec::new();
+    let mut encoder =
+        png::Encoder::new(&mut png, width, height);
+
+    encoder.set_color(color);
+    encoder.set_depth(png::BitDepth::Eight);
+
+    // (writing into a `Vec` can't fail)
+    encoder
+        .write_header()
+        .and_then(|mut writer| {
+            writer.write_image_data(pixels)
+        })
+        .unwrap();
+
+    png
+}
@@ -0,0 +1,419 @@
+//! Keyboard bindings (`keymap.toml`, or whatever `SDF_PLAYGROUND_KEYMAP`
+//! points at) - maps actions to keys, so that the controls can be adjusted
+//! without recompiling the application.
+//!
+//! Just like the script, the keymap gets reloaded as soon as it's modified;
+//! actions missing from the file keep their default keys.
+
+use log::{error, info, warn};
+use sdf_playground_common::SCENES;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+use std::{env, fs};
+use winit::event::VirtualKeyCode;
+use winit_input_helper::WinitInputHelper;
+
+/// Something that can be bound to a key.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Action {
+    Quit,
+    Help,
+    Probe,
+    History,
+    HotVariables,
+    ContactSheet,
+    Vsync,
+    Demo,
+    View2d,
+    FrameGraph,
+    Outline,
+    Gizmos,
+    Grid,
+    Foveated,
+    Scopes,
+    SplitScreen,
+    PreviousShader,
+    Gallery,
+    DebugView,
+    Scene(u32),
+    MotionBlur,
+    Coarse,
+    ShadowCatcher,
+    Subsurface,
+    Shading,
+    Quality(u32),
+    Fxaa,
+    Taa,
+    UpscaleFilter,
+    ViewTarget,
+    OrbitLeft,
+    OrbitRight,
+    FrameScene,
+    Bookmark(u32),
+    Checkerboard,
+    Stereo,
+    Relief,
+    SliceX,
+    SliceY,
+    SliceZ,
+    SliceBackward,
+    SliceForward,
+    EyesCloser,
+    EyesApart,
+}
+
+/// All actions, along with their names (as used in `keymap.toml`) and
+/// default keys.
+const ACTIONS: &[(&str, Action, VirtualKeyCode)] = {
+    use sdf_playground_common::quality;
+    use Action::*;
+    use VirtualKeyCode as Key;
+
+    &[
+        ("quit", Quit, Key::Escape),
+        ("help", Help, Key::Slash),
+        ("probe", Probe, Key::I),
+        ("history", History, Key::R),
+        ("hot_variables", HotVariables, Key::W),
+        ("contact_sheet", ContactSheet, Key::E),
+        ("vsync", Vsync, Key::V),
+        ("demo", Demo, Key::D),
+        ("view_2d", View2d, Key::M),
+        ("frame_graph", FrameGraph, Key::G),
+        ("outline", Outline, Key::L),
+        ("gizmos", Gizmos, Key::N),
+        ("grid", Grid, Key::J),
+        ("foveated", Foveated, Key::Q),
+        ("scopes", Scopes, Key::H),
+        ("split_screen", SplitScreen, Key::C),
+        ("previous_shader", PreviousShader, Key::A),
+        ("gallery", Gallery, Key::Space),
+        ("debug_view", DebugView, Key::Tab),
+        ("scene_1", Scene(1), Key::Key1),
+        ("scene_2", Scene(2), Key::Key2),
+        ("scene_3", Scene(3), Key::Key3),
+        ("scene_4", Scene(4), Key::Key4),
+        ("scene_5", Scene(5), Key::Key5),
+        ("scene_6", Scene(6), Key::Key6),
+        ("scene_7", Scene(7), Key::Key7),
+        ("scene_8", Scene(8), Key::Key8),
+        ("scene_9", Scene(9), Key::Key9),
+        ("motion_blur", MotionBlur, Key::B),
+        ("coarse", Coarse, Key::P),
+        ("shadow_catcher", ShadowCatcher, Key::O),
+        ("subsurface", Subsurface, Key::U),
+        ("shading", Shading, Key::T),
+        ("quality_low", Quality(quality::LOW), Key::F1),
+        (
+            "quality_medium",
+            Quality(quality::MEDIUM),
+            Key::F2,
+        ),
+        ("quality_high", Quality(quality::HIGH), Key::F3),
+        ("quality_ultra", Quality(quality::ULTRA), Key::F4),
+        ("fxaa", Fxaa, Key::F9),
+        ("taa", Taa, Key::F10),
+        ("upscale_filter", UpscaleFilter, Key::F11),
+        ("view_target", ViewTarget, Key::F12),
+        ("orbit_left", OrbitLeft, Key::Left),
+        ("orbit_right", OrbitRight, Key::Right),
+        ("frame_scene", FrameScene, Key::F),
+        ("bookmark_1", Bookmark(1), Key::F5),
+        ("bookmark_2", Bookmark(2), Key::F6),
+        ("bookmark_3", Bookmark(3), Key::F7),
+        ("bookmark_4", Bookmark(4), Key::F8),
+        ("checkerboard", Checkerboard, Key::K),
+        ("stereo", Stereo, Key::S),
+        ("relief", Relief, Key::Backslash),
+        ("slice_x", SliceX, Key::X),
+        ("slice_y", SliceY, Key::Y),
+        ("slice_z", SliceZ, Key::Z),
+        ("slice_backward", SliceBackward, Key::LBracket),
+        ("slice_forward", SliceForward, Key::RBracket),
+        ("eyes_closer", EyesCloser, Key::Minus),
+        ("eyes_apart", EyesApart, Key::Equals),
+    ]
+};
+
+/// Keys that can be used in `keymap.toml`, named the same way as in winit's
+/// [`VirtualKeyCode`] (e.g. `"A"`, `"Key1"`, `"F1"`, `"LBracket"`).
+const KEYS: &[VirtualKeyCode] = {
+    use VirtualKeyCode::*;
+
+    &[
+        A,
+        B,
+        C,
+        D,
+        E,
+        F,
+        G,
+        H,
+        I,
+        J,
+        K,
+        L,
+        M,
+        N,
+        O,
+        P,
+        Q,
+        R,
+        S,
+        T,
+        U,
+        V,
+        W,
+        X,
+        Y,
+        Z,
+        Key0,
+        Key1,
+        Key2,
+        Key3,
+        Key4,
+        Key5,
+        Key6,
+        Key7,
+        Key8,
+        Key9,
+        F1,
+        F2,
+        F3,
+        F4,
+        F5,
+        F6,
+        F7,
+        F8,
+        F9,
+        F10,
+        F11,
+        F12,
+        Escape,
+        Tab,
+        Space,
+        Return,
+        Back,
+        Insert,
+        Delete,
+        Home,
+        End,
+        PageUp,
+        PageDown,
+        Left,
+        Up,
+        Right,
+        Down,
+        Minus,
+        Equals,
+        LBracket,
+        RBracket,
+        Comma,
+        Period,
+        Slash,
+        Backslash,
+        Semicolon,
+        Apostrophe,
+        Grave,
+        Numpad0,
+        Numpad1,
+        Numpad2,
+        Numpad3,
+        Numpad4,
+        Numpad5,
+        Numpad6,
+        Numpad7,
+        Numpad8,
+        Numpad9,
+        NumpadAdd,
+        NumpadSubtract,
+        NumpadMultiply,
+        NumpadDivide,
+        NumpadDecimal,
+        NumpadEnter,
+    ]
+};
+
+#[derive(Debug)]
+pub struct Keymap {
+    path: PathBuf,
+    keys: HashMap<Action, VirtualKeyCode>,
+
+    /// When the keymap has been modified when we've last loaded it.
+    modified_at: Option<SystemTime>,
+}
+
+impl Keymap {
+    pub fn new() -> Self {
+        let path = env::var_os("SDF_PLAYGROUND_KEYMAP")
+            .map(PathBuf::from)
+            .unwrap_or_else(|| {
+                Path::new(env!("CARGO_MANIFEST_DIR"))
+                    .parent()
+                    .unwrap()
+                    .join("keymap.toml")
+            });
+
+        let mut this = Self {
+            path,
+            keys: defaults(),
+            modified_at: None,
+        };
+
+        this.reload();
+        this
+    }
+
+    /// Returns whether the key bound to given action has just been pressed.
+    pub fn pressed(
+        &self,
+        input: &WinitInputHelper,
+        action: Action,
+    ) -> bool {
+        self.keys
+            .get(&action)
+            .is_some_and(|&key| input.key_pressed(key))
+    }
+
+    /// Returns whether the key bound to given action is being held down.
+    pub fn held(
+        &self,
+        input: &WinitInputHelper,
+        action: Action,
+    ) -> bool {
+        self.keys
+            .get(&action)
+            .is_some_and(|&key| input.key_held(key))
+    }
+
+    /// Returns all actions (skipping scenes the shader doesn't have), along
+    /// with their names and the keys they are currently bound to.
+    pub fn bindings(
+        &self,
+    ) -> impl Iterator<
+        Item = (&'static str, Action, VirtualKeyCode),
+    > + '_ {
+        ACTIONS
+            .iter()
+            .filter(|(_, action, _)| {
+                !matches!(action, Action::Scene(scene) if *scene > SCENES)
+            })
+            .map(|&(name, action, _)| {
+                (name, action, self.keys[&action])
+            })
+    }
+
+    /// Reloads the keymap, if it's been modified since the last time; when the
+    /// new version is invalid, the previous one is kept.
+    pub fn reload(&mut self) {
+        let modified_at = self
+            .path
+            .metadata()
+            .and_then(|metadata| metadata.modified())
+            .ok();
+
+        if modified_at == self.modified_at {
+            return;
+        }
+
+        self.modified_at = modified_at;
+
+        if modified_at.is_none() {
+            // No keymap - let's go back to the defaults
+            self.keys = defaults();
+            return;
+        }
+
+        match self.load() {
+            Ok(keys) => {
+                info!("Loaded {}", self.path.display());
+                self.keys = keys;
+            }
+
+            Err(err) => {
+                error!(
+                    "Couldn't load {}: {err}",
+                    self.path.display()
+                );
+            }
+        }
+    }
+
+    fn load(
+        &self,
+    ) -> Result<HashMap<Action, VirtualKeyCode>, String>
+    {
+        let keymap = fs::read_to_string(&self.path)
+            .map_err(|err| err.to_string())?
+            .parse::<toml_edit::Document>()
+            .map_err(|err| err.to_string())?;
+
+        let mut keys = defaults();
+
+        for (name, key) in keymap.iter() {
+            let Some(&(_, action, _)) =
+                ACTIONS.iter().find(|(n, ..)| *n == name)
+            else {
+                warn!("Unknown action in keymap: {name}");
+                continue;
+            };
+
+            let key = key.as_str().ok_or_else(|| {
+                format!(
+                    "key for `{name}` should be a string"
+                )
+            })?;
+
+            let key = KEYS
+                .iter()
+                .find(|k| format!("{k:?}") == key)
+                .ok_or_else(|| {
+                    format!("unknown key: {key}")
+                })?;
+
+            keys.insert(action, *key);
+        }
+
+        Ok(keys)
+    }
+}
+
+/// Returns a human-friendly name of given key, e.g. `1` for `Key1`.
+pub fn key_name(key: VirtualKeyCode) -> String {
+    use VirtualKeyCode::*;
+
+    let name = match key {
+        Escape => "Esc",
+        Back => "Backspace",
+        Return => "Enter",
+        Minus => "-",
+        Equals => "=",
+        LBracket => "[",
+        RBracket => "]",
+        Comma => ",",
+        Period => ".",
+        Slash => "/",
+        Backslash => "\\",
+        Semicolon => ";",
+        Apostrophe => "'",
+        Grave => "`",
+        _ => {
+            let name = format!("{key:?}");
+
+            return match name.strip_prefix("Key") {
+                Some(digit) => digit.to_owned(),
+                None => name,
+            };
+        }
+    };
+
+    name.to_owned()
+}
+
+fn defaults() -> HashMap<Action, VirtualKeyCode> {
+    ACTIONS
+        .iter()
+        .map(|&(_, action, key)| (action, key))
+        .collect()
+}
@@ -0,0 +1,83 @@
+use crate::args::value;
+use env_logger::{Builder, Target};
+use log::LevelFilter;
+use std::fs::File;
+use std::io::{self, Write};
+
+/// Initializes the logger according to given command-line arguments:
+///
+/// - `-v`, `-vv`, `-vvv` - log our info, debug or trace messages (together
+///   with wgpu's warnings, which is where validation problems show up),
+/// - `--log <filters>` - per-module filters, in the same format as `RUST_LOG`
+///   (e.g. `wgpu_core=info,sdf_playground::compiler=debug`); they are applied
+///   after `-v`, so they can override it for particular modules,
+/// - `--log-file <path>` - write the log into given file as well, so that
+///   hot-reload failures can be inspected after the fact.
+///
+/// Without any of these, only `RUST_LOG` decides what gets logged (and just
+/// like before, without it only errors do).
+pub fn init(args: &[String]) {
+    let mut builder = Builder::from_default_env();
+
+    let verbosity: usize = args
+        .iter()
+        .filter_map(|arg| arg.strip_prefix('-'))
+        .filter(|flags| {
+            !flags.is_empty()
+                && flags.chars().all(|c| c == 'v')
+        })
+        .map(|flags| flags.len())
+        .sum();
+
+    if verbosity > 0 {
+        let level = match verbosity {
+            1 => LevelFilter::Info,
+            2 => LevelFilter::Debug,
+            _ => LevelFilter::Trace,
+        };
+
+        builder
+            .filter_module("sdf_playground", level)
+            .filter_module("wgpu_core", LevelFilter::Warn)
+            .filter_module("wgpu_hal", LevelFilter::Warn);
+    }
+
+    if let Some(filters) = value(args, "--log") {
+        builder.parse_filters(filters);
+    }
+
+    if let Some(path) = value(args, "--log-file") {
+        match File::create(path) {
+            Ok(file) => {
+                builder.target(Target::Pipe(Box::new(
+                    Tee { file },
+                )));
+            }
+
+            Err(err) => {
+                eprintln!("Couldn't create log file {path}: {err}");
+            }
+        }
+    }
+
+    builder.init();
+}
+
+/// Writer which sends everything both to the terminal and into a file.
+struct Tee {
+    file: File,
+}
+
+impl Write for Tee {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        io::stderr().write_all(buf)?;
+        self.file.write_all(buf)?;
+
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        io::stderr().flush()?;
+        self.file.flush()
+    }
+}
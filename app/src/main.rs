@@ -1,108 +1,1271 @@
+mod args;
+mod bench;
+mod bookmarks;
+mod bounding_box;
+mod bundle;
 mod compiler;
+mod contact_sheet;
+mod demo;
+mod error;
+mod foveation;
+mod frame_graph;
+mod framing;
+mod gizmos;
+mod gpu;
+mod gpu_timings;
+mod headless;
+mod help;
+mod history;
+mod hot;
+mod image;
+mod keymap;
+mod logging;
+mod matcap;
+mod operator;
+mod outline;
+mod palette;
+mod perfect_loop;
+mod post_effects;
+mod probe;
 mod renderer;
+mod scopes;
+mod screensaver;
+mod script;
+mod target_viewer;
+mod temporal_aa;
+mod text;
+mod verify;
+mod wall;
+mod wallpaper;
 
+use self::bookmarks::*;
 use self::compiler::*;
+use self::demo::*;
+use self::error::*;
+use self::foveation::*;
+use self::frame_graph::*;
+use self::framing::*;
+use self::gizmos::*;
+use self::gpu::*;
+use self::gpu_timings::*;
+use self::history::*;
+use self::hot::*;
+use self::keymap::*;
+use self::matcap::*;
+use self::operator::*;
+use self::outline::*;
+use self::palette::*;
+use self::post_effects::*;
 use self::renderer::*;
+use self::scopes::*;
+use self::screensaver::*;
+use self::script::*;
+use self::target_viewer::*;
+use self::temporal_aa::*;
+use self::text::*;
 use log::*;
-use pixels::{Pixels, SurfaceTexture};
-use sdf_playground_common::Params;
-use std::mem;
-use std::time::Instant;
+use sdf_playground_common::glam::{
+    vec2, vec4, Vec2, Vec3, Vec4,
+};
+use sdf_playground_common::{
+    debug_view, quality, scene_name, shading, stereo,
+    Params, SCENES,
+};
+use std::time::{Duration, Instant};
+use std::{env, mem, process};
 use winit::dpi::LogicalSize;
-use winit::event::{Event, VirtualKeyCode};
+use winit::event::{Event, VirtualKeyCode, WindowEvent};
 use winit::event_loop::{ControlFlow, EventLoop};
-use winit::window::WindowBuilder;
+use winit::window::{Window, WindowBuilder};
 use winit_input_helper::WinitInputHelper;
 
+/// How long it takes to blend between the old and the new shader after it's
+/// been reloaded.
+const CROSSFADE: Duration = Duration::from_millis(300);
+
+/// How often the window's title gets refreshed - updating it every frame would
+/// make the FPS unreadable (and some window managers slow).
+const TITLE_INTERVAL: Duration = Duration::from_millis(250);
+
+/// How fast the arrows orbit the camera, in radians per second.
+const ORBIT_SPEED: f32 = 1.0;
+
+/// Shutter time used when motion blur is enabled - corresponds to a 180°
+/// shutter at 30 FPS, which is what movies usually go for.
+const SHUTTER: f32 = 1.0 / 60.0;
+
+/// How much each frame contributes to a pixel with temporal anti-aliasing
+/// turned on - the less, the smoother the edges, but the longer it takes for
+/// a pixel to recover once its history gets rejected.
+const TAA: f32 = 0.1;
+
+/// How many frames it takes for the TAA's jitter to repeat.
+const TAA_JITTER: u32 = 8;
+
+/// Scene shown right after starting the playground.
+pub const START_SCENE: u32 = 5;
+
 fn main() {
-    env_logger::init();
+    // (before anything else, since there can't be any other threads yet)
+    compiler::set_target_dir();
+
+    let args: Vec<_> = env::args().skip(1).collect();
+
+    logging::init(&args);
+
+    // (no window nor GPU needed here, so this goes before the event loop -
+    // which would fail without a display)
+    if args.iter().any(|arg| arg == "--verify") {
+        if args.iter().any(|arg| arg == "--f16") {
+            verify::run_half();
+        } else {
+            verify::run(
+                args.iter().any(|arg| arg == "--update"),
+            );
+        }
+
+        return;
+    }
+
+    if let Some(path) = args::value(&args, "--export") {
+        let scene = args::scene(&args);
+
+        if let Err(err) =
+            bundle::export(path.as_ref(), scene)
+        {
+            eprintln!("Couldn't export sketch: {err}");
+            process::exit(1);
+        }
+
+        println!("Sketch exported into {path}");
+        return;
+    }
+
+    if let Some(path) = args::value(&args, "--import") {
+        match bundle::import(path.as_ref()) {
+            Ok(backup) => {
+                println!(
+                    "Sketch imported from {path} (the previous one has been \
+                     saved into {})",
+                    backup.display()
+                );
+            }
+
+            Err(err) => {
+                eprintln!("Couldn't import sketch: {err}");
+                process::exit(1);
+            }
+        }
+
+        return;
+    }
 
     let event_loop = EventLoop::new();
 
-    let window = WindowBuilder::new()
-        .with_title("sdf-playground")
-        .with_inner_size(LogicalSize::new(700, 700))
+    if let Some(dir) = args::value(&args, "--loop") {
+        let scene = args::scene(&args);
+
+        let seconds = |flag| {
+            args::value(&args, flag)
+                .and_then(|value| value.parse().ok())
+        };
+
+        perfect_loop::run(
+            &event_loop,
+            dir.as_ref(),
+            scene,
+            seconds("--period"),
+            seconds("--fps"),
+        );
+
+        return;
+    }
+
+    if args.iter().any(|arg| arg == "--bench") {
+        bench::run(
+            &event_loop,
+            args.iter().any(|arg| arg == "--json"),
+        );
+
+        return;
+    }
+
+    let mut screensaver = args
+        .iter()
+        .any(|arg| arg == "--screensaver")
+        .then(Screensaver::default);
+
+    let wallpaper =
+        args.iter().any(|arg| arg == "--wallpaper");
+
+    // Whether the window spans all the monitors (see `wall`)
+    let span = screensaver.is_some()
+        || wallpaper
+        || args.iter().any(|arg| arg == "--span");
+
+    // Whether the window is see-through where nothing's been hit
+    let transparent =
+        args.iter().any(|arg| arg == "--transparent");
+
+    let window = if screensaver.is_some() {
+        Screensaver::window(&event_loop)
+    } else if wallpaper {
+        wallpaper::window(&event_loop)
+    } else if span {
+        wall::window(&event_loop)
+    } else {
+        WindowBuilder::new()
+            .with_title("sdf-playground")
+            .with_inner_size(LogicalSize::new(700, 700))
+    };
+
+    let window = window
+        .with_transparent(transparent)
         .build(&event_loop)
-        .unwrap();
+        .unwrap_or_else(|err| fail(err.into()));
 
     let mut params = Params {
-        width: window.inner_size().width,
-        height: window.inner_size().height,
-        time: 0.0,
+        scene: START_SCENE,
+        ..Params::new(
+            window.inner_size().width,
+            window.inner_size().height,
+        )
     };
 
-    let mut pixels = {
-        let surface = SurfaceTexture::new(
-            params.width,
-            params.height,
-            &window,
-        );
+    if span {
+        params.frustum =
+            wall::frustum(params.width, params.height);
+    }
 
-        Pixels::new(params.width, params.height, surface)
-            .unwrap()
-    };
+    if transparent {
+        params.background = 0;
+    }
+
+    // Background color, as `RRGGBBAA`
+    if let Ok(background) =
+        env::var("SDF_PLAYGROUND_BACKGROUND")
+    {
+        match u32::from_str_radix(&background, 16) {
+            Ok(background) => {
+                params.background = background;
+            }
+
+            Err(err) => {
+                warn!("Invalid SDF_PLAYGROUND_BACKGROUND: {err}");
+            }
+        }
+    }
+
+    let mut gpu = Gpu::new(&window, transparent)
+        .unwrap_or_else(|err| fail(err));
+
+    let mut surface_lost = false;
+    let mut suspended = false;
+    let mut focused = true;
+    let mut occluded = false;
 
     let compiler = Compiler::spawn();
     let mut renderer: Option<Renderer> = None;
+    let mut prev_renderer: Option<(Renderer, Instant)> =
+        None;
     let mut input = WinitInputHelper::new();
+
+    // Position of the split-screen's divider (0.0 = left edge, 1.0 = right
+    // edge), if split-screen is enabled
+    let mut split: Option<f32> = None;
+
+    // Whether the previous version of the shader is being shown instead of
+    // the current one (for as long as its key is held)
+    let mut show_previous = false;
+
+    // Where the region of interest has started being drawn, if it's being
+    // drawn at the moment
+    let mut roi_start: Option<Vec2> = None;
+
+    // Whether we're showing the 2D SDF instead of the scene
+    let mut view_2d = false;
+
+    // Image used by the matcap shading; can be replaced by drag & dropping a
+    // PNG onto the window
+    let mut matcap = Matcap::from_env();
+
+    // Percentage of pixels that can run out of steps before we warn about it
+    let exhausted_warning: f32 =
+        env::var("SDF_PLAYGROUND_EXHAUSTED_WARNING")
+            .ok()
+            .and_then(|val| val.parse().ok())
+            .unwrap_or(5.0);
+
+    // What's shown in the window's title - the compiler's status and an
+    // optional warning about pixels running out of steps
+    let mut status = String::new();
+    let mut warning: Option<String> = None;
+
+    // Frame rate shown in the window's title, measured over the frames
+    // rendered since the title's been refreshed
+    let mut fps = 0.0;
+    let mut title_frames = 0;
+    let mut title_updated_at = Instant::now();
+
+    let mut demo = args
+        .iter()
+        .any(|arg| arg == "--demo")
+        .then(Demo::new);
+
+    if screensaver.is_some() {
+        Screensaver::randomize(&mut params);
+        window.set_cursor_visible(false);
+
+        // Staring at a single scene for hours would be boring
+        demo = Some(Demo::new());
+    }
+
+    let mut operator = args
+        .iter()
+        .any(|arg| arg == "--operator")
+        .then(|| Operator::new(&event_loop, &gpu))
+        .transpose()
+        .unwrap_or_else(|err| {
+            warn!("Couldn't open operator window: {err}");
+            None
+        });
+
+    let mut script = Script::new();
+    let mut bookmarks = Bookmarks::new();
+    let mut framing = Framing::default();
+
+    // Which way the camera is being orbited with the arrows (-1.0 = left,
+    // 1.0 = right, 0.0 = it isn't)
+    let mut orbiting = 0.0;
+    let mut keymap = Keymap::new();
+    let mut frame_graph = FrameGraph::new(&gpu);
+    let mut gpu_timings = GpuTimings::new(&gpu);
+    let mut show_frame_graph = false;
+    let mut scopes = Scopes::new(&gpu);
+    let mut outline = Outline::new(&gpu);
+    let mut gizmos = Gizmos::new(&gpu);
+    let mut foveation = Foveation::new(&gpu);
+    let mut temporal_aa = TemporalAa::new(&gpu);
+    let mut post_effects = PostEffects::new(&gpu);
+    let mut target_viewer = TargetViewer::new(&gpu);
+    let mut foveated = false;
+    let mut show_scopes = false;
+    let mut text = Text::new(&gpu);
+    let mut show_help = false;
+    let mut show_probe = false;
+    let mut take_contact_sheet = false;
+    let mut palette: Option<Palette> = None;
+    let mut history: Option<HistoryBrowser> = None;
+    let mut hot_variables = HotVariables::default();
+    let mut show_hot_variables = false;
+
+    // Panel (help, palette etc.) shown in the operator window instead of the
+    // main one
+    let mut operator_panel: Option<Vec<String>> = None;
+
     let mut delta = Instant::now();
 
     event_loop.run(move |event, _, control_flow| {
-        if let Some(path) = compiler.poll() {
-            renderer = Some(Renderer::new(
-                &pixels,
-                params.width,
-                params.height,
-                path,
-            ));
-        }
-
-        if let Event::RedrawRequested(_) = event {
-            if let Some(renderer) = &renderer {
-                pixels
-                    .render_with(
-                        |encoder, target, context| {
-                            let texture =
-                                renderer.texture_view();
-
-                            context
-                                .scaling_renderer
-                                .render(encoder, texture);
-
-                            renderer.update(
-                                &context.queue,
-                                &params,
-                            );
+        if let Some(screensaver) = &mut screensaver {
+            if screensaver.interrupted(&event) {
+                *control_flow = ControlFlow::Exit;
+                return;
+            }
+        }
+
+        if let Some(event) = compiler.poll() {
+            status = event.status();
+
+            if let CompilerEvent::Succeeded {
+                path, ..
+            } = event
+            {
+                match replace_renderer(
+                    &mut renderer,
+                    &mut prev_renderer,
+                    Renderer::new(&gpu, path),
+                ) {
+                    Ok(()) => {
+                        compiler.promote();
+                        hot_variables.scan();
+                        load_operator(&gpu, &mut operator, &renderer);
+                    }
+
+                    // There's no renderer to keep using (e.g. the cached
+                    // shader we've started with got rejected), so let's load
+                    // the one that has worked most recently
+                    Err(err) if renderer.is_none() => {
+                        let last_good = compiler.last_good().map(|path| {
+                            replace_renderer(
+                                &mut renderer,
+                                &mut prev_renderer,
+                                Renderer::new(&gpu, path),
+                            )
+                        });
+
+                        status = if let Some(Ok(())) = last_good {
+                            load_operator(&gpu, &mut operator, &renderer);
+                            format!("{err}, using last good version")
+                        } else {
+                            err.to_string()
+                        };
+                    }
+
+                    Err(err) => {
+                        status = format!("{err}, using last good version");
+                    }
+                }
+            }
+
+            set_title(&window, &params, fps, &status, &warning);
+        }
 
-                            renderer
-                                .render(encoder, target);
+        if let Some(op) = &mut operator {
+            if op.handle(&gpu, &event, &mut params) {
+                operator = None;
+            } else if event == Event::RedrawRequested(op.id()) {
+                if let Err(err) = op.render(
+                    &gpu,
+                    &params,
+                    &frame_graph,
+                    operator_panel.as_deref(),
+                    fps,
+                    &status,
+                ) {
+                    warn!("Couldn't render operator window: {err}");
+                }
+            }
+        }
+
+        if operator
+            .as_ref()
+            .is_some_and(|operator| !operator.forwards(&event))
+        {
+            return;
+        }
+
+        match &event {
+            Event::WindowEvent {
+                event: WindowEvent::Focused(val),
+                ..
+            } => {
+                focused = *val;
+            }
+
+            Event::WindowEvent {
+                event: WindowEvent::Occluded(val),
+                ..
+            } => {
+                occluded = *val;
+            }
+
+            Event::WindowEvent {
+                event: WindowEvent::DroppedFile(path),
+                ..
+            } => {
+                if Renderer::supports(path) {
+                    info!("Loading {}", path.display());
+
+                    status = path.display().to_string();
+                    set_title(&window, &params, fps, &status, &warning);
+
+                    match replace_renderer(
+                        &mut renderer,
+                        &mut prev_renderer,
+                        Renderer::new(&gpu, path.clone()),
+                    ) {
+                        Ok(()) => {
+                            load_operator(&gpu, &mut operator, &renderer);
+                        }
+
+                        Err(err) => {
+                            status = err.to_string();
+                            set_title(&window, &params, fps, &status, &warning);
+                        }
+                    }
+                } else if Matcap::supports(path) {
+                    match Matcap::load(path) {
+                        Ok(new_matcap) => {
+                            info!("Loaded matcap from {}", path.display());
+                            matcap = new_matcap;
+                        }
 
-                            let delta = mem::replace(
-                                &mut delta,
-                                Instant::now(),
+                        Err(err) => {
+                            warn!(
+                                "Couldn't load matcap from {}: {err}",
+                                path.display()
                             );
+                        }
+                    }
+                } else {
+                    warn!(
+                        "Don't know how to load {}",
+                        path.display()
+                    );
+                }
+            }
+
+            Event::Suspended => {
+                suspended = true;
+            }
+
+            Event::Resumed if suspended => {
+                // Some platforms (e.g. Android) destroy the surface when the
+                // app gets suspended, so let's start afresh
+                suspended = false;
+                surface_lost = true;
+            }
+
+            _ => (),
+        }
 
-                            params.time += delta
-                                .elapsed()
-                                .as_secs_f32();
+        if surface_lost {
+            surface_lost = false;
+
+            gpu = Gpu::new(&window, transparent)
+                .unwrap_or_else(|err| fail(err));
+
+            if let Some(Err(err)) = renderer
+                .as_mut()
+                .map(|renderer| renderer.recreate(&gpu))
+            {
+                // The new GPU doesn't like our shader - it's not the end of
+                // the world, the next successful compilation will bring it
+                // back
+                error!("Couldn't recreate renderer: {err}");
 
-                            Ok(())
-                        },
+                renderer = None;
+                status = err.to_string();
+                set_title(&window, &params, fps, &status, &warning);
+            }
+
+            prev_renderer = None;
+            frame_graph.recreate(&gpu);
+            gpu_timings.recreate(&gpu);
+            scopes.recreate(&gpu);
+            outline.recreate(&gpu);
+            gizmos.recreate(&gpu);
+            foveation.recreate(&gpu);
+            temporal_aa.recreate(&gpu);
+            post_effects.recreate(&gpu);
+            target_viewer.recreate(&gpu);
+            text.recreate(&gpu);
+
+            if let Some(Err(err)) = operator
+                .as_mut()
+                .map(|operator| operator.recreate(&gpu))
+            {
+                warn!("Couldn't recreate operator window: {err}");
+                operator = None;
+            }
+        }
+
+        if event == Event::RedrawRequested(window.id()) {
+            let result = gpu.render(|encoder, target| {
+                let Some(renderer) = &mut renderer else {
+                    Gpu::clear(encoder, target);
+                    return;
+                };
+
+                gpu_timings.poll(gpu.device());
+                gpu_timings.begin(encoder);
+
+                renderer.update(&gpu, &params);
+                renderer.set_view_2d(view_2d);
+                renderer.set_matcap(&gpu, &matcap);
+
+                let fade = prev_renderer.as_ref().map_or(
+                    1.0,
+                    |(_, replaced_at)| {
+                        replaced_at.elapsed().as_secs_f32()
+                            / CROSSFADE.as_secs_f32()
+                    },
+                );
+
+                // Post effects work on the complete frame (after crossfading,
+                // foveation etc.), so when they are enabled, the frame gets
+                // composed in an intermediate texture first
+                let post_effects_enabled =
+                    post_effects.enabled(&params);
+
+                let frame_target = if post_effects_enabled {
+                    post_effects.input(&gpu, &params)
+                } else {
+                    target
+                };
+
+                // Whether the frame is made of more than one renderer's image
+                // (in which case there's no single history to anti-alias)
+                let mut blended = false;
+
+                // Renderer whose image ends up on the screen - post-processing
+                // passes should work on its output
+                let displayed: &Renderer = match (&mut prev_renderer, split) {
+                    (Some((prev_renderer, _)), _) if show_previous => {
+                        prev_renderer.update(&gpu, &params);
+                        prev_renderer.set_view_2d(view_2d);
+                        prev_renderer.set_matcap(&gpu, &matcap);
+                        prev_renderer.render(encoder, frame_target, 1.0, None);
+                        prev_renderer
+                    }
+
+                    (Some((prev_renderer, _)), Some(split)) => {
+                        // Previous version of the shader on the left, current
+                        // one on the right
+                        let split = ((split * params.width as f32)
+                            as u32)
+                            .clamp(1, params.width);
+
+                        prev_renderer.update(&gpu, &params);
+                        prev_renderer.set_view_2d(view_2d);
+                        prev_renderer.set_matcap(&gpu, &matcap);
+                        renderer.render(encoder, frame_target, 1.0, None);
+
+                        prev_renderer.render(
+                            encoder,
+                            frame_target,
+                            1.0,
+                            Some([0, 0, split, params.height]),
+                        );
+
+                        blended = true;
+                        renderer
+                    }
+
+                    (Some((prev_renderer, _)), None) if fade < 1.0 => {
+                        // We've just reloaded the shader - blend the previous
+                        // version into the new one instead of switching
+                        // abruptly
+                        prev_renderer.update(&gpu, &params);
+                        prev_renderer.set_view_2d(view_2d);
+                        prev_renderer.set_matcap(&gpu, &matcap);
+                        prev_renderer.render(encoder, frame_target, 1.0, None);
+                        renderer.render(encoder, frame_target, fade, None);
+                        blended = true;
+                        renderer
+                    }
+
+                    _ => {
+                        renderer.render(encoder, frame_target, 1.0, None);
+                        renderer
+                    }
+                };
+
+                gpu_timings.mark(encoder, "scene");
+
+                // (foveation fills in the tiles skipped by the renderer, so it
+                // has to go before post effects)
+                if displayed.fovea().is_some() {
+                    foveation.render(
+                        &gpu,
+                        encoder,
+                        frame_target,
+                        displayed,
+                        &params,
+                    );
+
+                    gpu_timings.mark(encoder, "foveation");
+                }
+
+                if !view_2d && !blended && params.taa_enabled() {
+                    temporal_aa.render(
+                        &gpu,
+                        encoder,
+                        frame_target,
+                        displayed,
+                        &params,
+                    );
+
+                    gpu_timings.mark(encoder, "temporal aa");
+                } else {
+                    temporal_aa.reset();
+                }
+
+                if post_effects_enabled {
+                    post_effects.render(
+                        &gpu, encoder, target, &params,
+                    );
+
+                    gpu_timings.mark(encoder, "post effects");
+                }
+
+                outline.render(
+                    &gpu,
+                    encoder,
+                    target,
+                    displayed.aov(),
+                );
+
+                gpu_timings.mark(encoder, "outline");
+
+                // (gizmos and the grid follow the regular camera, so they'd
+                // be misplaced in 2D mode and for the eyes in stereo)
+                if !view_2d && params.stereo == stereo::OFF {
+                    gizmos.render(
+                        &gpu,
+                        encoder,
+                        target,
+                        displayed.aov(),
+                        &params,
+                    );
+
+                    gpu_timings.mark(encoder, "gizmos");
+                }
+
+                target_viewer.render(
+                    &gpu, encoder, target, displayed, &params,
+                );
+
+                gpu_timings.mark(encoder, "target viewer");
+
+                if show_scopes {
+                    scopes.render(
+                        &gpu,
+                        encoder,
+                        target,
+                        displayed.output(),
+                        params.width,
+                        params.height,
+                    );
+
+                    gpu_timings.mark(encoder, "scopes");
+                }
+
+                if show_frame_graph {
+                    frame_graph.render(
+                        gpu.queue(),
+                        encoder,
+                        target,
+                        params.width,
+                        params.height,
+                    );
+
+                    gpu_timings.mark(encoder, "frame graph");
+                }
+
+                let panel = if let Some(palette) = &palette {
+                    Some(palette.text(&keymap))
+                } else if let Some(history) = &history {
+                    Some(history.text())
+                } else if show_hot_variables {
+                    Some(hot_variables.text())
+                } else if show_help {
+                    Some(help::text(
+                        &keymap,
+                        &params,
+                        renderer.stats(),
+                    ))
+                } else if show_probe {
+                    Some(probe::text(&params, renderer.probe()))
+                } else {
+                    None
+                };
+
+                // With the operator window open, panels go there instead, so
+                // that the main window stays clean
+                let panel = if operator.is_some() {
+                    operator_panel = panel;
+                    None
+                } else {
+                    panel
+                };
+
+                let overlay = panel.or_else(|| {
+                    if show_previous {
+                        Some(vec![if prev_renderer.is_some() {
+                            "previous shader".to_owned()
+                        } else {
+                            "no previous shader yet".to_owned()
+                        }])
+                    } else if show_frame_graph {
+                        Some(gpu_timings.text())
+                    } else {
+                        target_viewer.target.map(|target| {
+                            vec![format!("target: {target}")]
+                        })
+                    }
+                });
+
+                if let Some(overlay) = overlay {
+                    text.render(
+                        &gpu,
+                        encoder,
+                        target,
+                        overlay,
+                        params.width,
+                        params.height,
+                    );
+
+                    gpu_timings.mark(encoder, "text");
+                }
+
+                gpu_timings.finish(encoder);
+
+                let frame_time =
+                    mem::replace(&mut delta, Instant::now())
+                        .elapsed();
+
+                params.time += frame_time.as_secs_f32();
+                params.frame = params.frame.wrapping_add(1);
+                params.prev_camera = vec4(params.orbit, 1.0, 0.0, 0.0);
+                params.prev_framing = params.framing;
+
+                // (Halton sequence, so that the offsets cover the pixel evenly
+                // no matter how many frames get accumulated)
+                let jitter = params.frame % TAA_JITTER + 1;
+
+                params.taa.y = halton(jitter, 2) - 0.5;
+                params.taa.z = halton(jitter, 3) - 0.5;
+                frame_graph.push(frame_time);
+
+                if let Some(demo) = &mut demo {
+                    demo.update(&mut params, frame_time);
+                }
+
+                if orbiting != 0.0 {
+                    bookmarks.cancel();
+                    params.orbit += orbiting * ORBIT_SPEED * frame_time.as_secs_f32();
+                }
+
+                bookmarks.update(&mut params, frame_time);
+                framing.update(&mut params, frame_time);
+
+                script.run(&mut params);
+            });
+
+            // Something we've sent to the GPU got rejected - most likely the
+            // current shader, which (despite being accepted when its pipeline
+            // got created) doesn't match what we bind to it; let's go back to
+            // the previous one (or to the black screen, if there's none)
+            if let Some(err) = gpu.take_error() {
+                renderer = prev_renderer
+                    .take()
+                    .map(|(renderer, _)| renderer);
+
+                load_operator(&gpu, &mut operator, &renderer);
+
+                // (full message is in the log, title gets just the gist)
+                status = format!(
+                    "GPU error, rolled back: {}",
+                    err.lines().next().unwrap_or_default()
+                );
+                set_title(&window, &params, fps, &status, &warning);
+            }
+
+            if mem::take(&mut take_contact_sheet) {
+                if let Some(renderer) = &renderer {
+                    save_contact_sheet(&gpu, renderer);
+                }
+            }
+
+            let new_warning = renderer
+                .as_ref()
+                .and_then(|renderer| renderer.exhausted())
+                .map(|exhausted| {
+                    100.0 * exhausted as f32
+                        / (params.width * params.height).max(1)
+                            as f32
+                })
+                .filter(|&exhausted| exhausted > exhausted_warning)
+                .map(|exhausted| {
+                    format!(
+                        "{exhausted:.0}% of pixels ran out of steps"
                     )
-                    .unwrap();
-            } else {
-                pixels.render().unwrap();
+                });
+
+            if new_warning != warning {
+                warning = new_warning;
+                set_title(&window, &params, fps, &status, &warning);
+            }
+
+            title_frames += 1;
+
+            if title_updated_at.elapsed() >= TITLE_INTERVAL {
+                fps = title_frames as f32
+                    / title_updated_at.elapsed().as_secs_f32();
+
+                title_frames = 0;
+                title_updated_at = Instant::now();
+                set_title(&window, &params, fps, &status, &warning);
+            }
+
+            match result {
+                Ok(()) => {
+                    //
+                }
+
+                Err(wgpu::SurfaceError::Outdated) => {
+                    // Surface got out of sync with the window (e.g. during
+                    // resizing) - reconfiguring it is enough
+                    gpu.resize(params.width, params.height);
+                }
+
+                Err(wgpu::SurfaceError::Lost) => {
+                    // Surface (or the entire device, e.g. after a driver reset)
+                    // is gone - we have to recreate everything; note that this
+                    // preserves `params`, so the time doesn't jump back
+                    warn!("Surface lost, recreating it");
+
+                    surface_lost = true;
+                }
+
+                Err(wgpu::SurfaceError::Timeout) => {
+                    warn!(
+                        "Surface timed out, skipping frame"
+                    );
+                }
+
+                Err(err) => {
+                    error!("Couldn't render frame: {err}");
+
+                    *control_flow = ControlFlow::Exit;
+                    return;
+                }
             }
         }
 
         if input.update(&event) {
-            if input.key_pressed(VirtualKeyCode::Escape)
+            keymap.reload();
+
+            // Whether the keys are used for typing into the command palette
+            // or browsing the history or hot variables (including the ones
+            // that open and close them)
+            let typing = palette.is_some()
+                || history.is_some()
+                || show_hot_variables
+                || (input.held_control()
+                    && input.key_pressed(VirtualKeyCode::P));
+
+            // Action picked from the command palette, triggered as if its key
+            // has been pressed
+            let mut picked = None;
+
+            if show_hot_variables {
+                show_hot_variables = hot_variables.update(&input);
+            } else if let Some(history_ref) = &mut history {
+                match history_ref.update(&input) {
+                    HistoryEvent::Idle => (),
+
+                    HistoryEvent::Closed => {
+                        history = None;
+                    }
+
+                    HistoryEvent::Picked(snapshot) => {
+                        history = None;
+                        restore_snapshot(&compiler, &snapshot);
+                    }
+                }
+            } else if let Some(palette_ref) = &mut palette {
+                match palette_ref.update(&input, &keymap) {
+                    PaletteEvent::Idle => (),
+
+                    PaletteEvent::Closed => {
+                        palette = None;
+                    }
+
+                    PaletteEvent::Picked(action) => {
+                        palette = None;
+                        picked = Some(action);
+                    }
+                }
+            } else if typing {
+                palette = Some(Palette::default());
+            }
+
+            let pressed = |action| {
+                picked == Some(action)
+                    || (!typing && keymap.pressed(&input, action))
+            };
+
+            show_previous =
+                !typing && keymap.held(&input, Action::PreviousShader);
+
+            let held = |action| !typing && keymap.held(&input, action);
+
+            orbiting = match (held(Action::OrbitLeft), held(Action::OrbitRight)) {
+                (true, false) => -1.0,
+                (false, true) => 1.0,
+                _ => 0.0,
+            };
+
+            for bookmark in 1..=BOOKMARKS {
+                if pressed(Action::Bookmark(bookmark)) {
+                    if input.held_shift() {
+                        bookmarks.save(bookmark, &params);
+                    } else {
+                        bookmarks.jump(bookmark, &params);
+                    }
+                }
+            }
+
+            if pressed(Action::FrameScene) {
+                framing.frame(&mut params);
+            }
+
+            if pressed(Action::Quit)
                 || input.close_requested()
             {
                 *control_flow = ControlFlow::Exit;
                 return;
             }
 
+            if pressed(Action::Help) {
+                show_help = !show_help;
+            }
+
+            if pressed(Action::Foveated) {
+                foveated = !foveated;
+
+                info!("Foveated rendering: {foveated}");
+            }
+
+            if pressed(Action::ViewTarget) {
+                target_viewer.target =
+                    Target::next(target_viewer.target);
+
+                match target_viewer.target {
+                    Some(target) => info!("Viewing target: {target}"),
+                    None => info!("Viewing target: none"),
+                }
+            }
+
+            if pressed(Action::UpscaleFilter) {
+                foveation.filter = foveation.filter.next();
+
+                info!("Upscaling filter: {}", foveation.filter);
+            }
+
+            if pressed(Action::Probe) {
+                show_probe = !show_probe;
+            }
+
+            if pressed(Action::History) {
+                match compiler.history() {
+                    Some(history_ref) => {
+                        history = Some(HistoryBrowser::new(
+                            history_ref,
+                            &shader_dir(),
+                        ));
+                    }
+
+                    None => {
+                        warn!(
+                            "Shader history is disabled - set \
+                             SDF_PLAYGROUND_HISTORY=1 to enable it"
+                        );
+                    }
+                }
+            }
+
+            if pressed(Action::HotVariables) {
+                hot_variables.scan();
+                show_hot_variables = true;
+            }
+
+            if pressed(Action::ContactSheet) {
+                take_contact_sheet = true;
+            }
+
+            if pressed(Action::Vsync) {
+                gpu.set_vsync(!gpu.vsync());
+
+                info!("VSync: {}", gpu.vsync());
+            }
+
+            if pressed(Action::Demo) {
+                demo = match demo {
+                    Some(_) => {
+                        Demo::stop(&mut params);
+                        None
+                    }
+                    None => Some(Demo::new()),
+                };
+
+                info!("Demo: {}", demo.is_some());
+            }
+
+            if pressed(Action::View2d) {
+                view_2d = !view_2d;
+
+                info!("2D mode: {view_2d}");
+            }
+
+            if pressed(Action::FrameGraph) {
+                show_frame_graph = !show_frame_graph;
+            }
+
+            if pressed(Action::Outline) {
+                outline.thickness = (outline.thickness + 1) % 3;
+
+                info!("Outline thickness: {}", outline.thickness);
+            }
+
+            if pressed(Action::Gizmos) {
+                gizmos.enabled = !gizmos.enabled;
+
+                info!("Gizmos: {}", gizmos.enabled);
+            }
+
+            if pressed(Action::Grid) {
+                gizmos.grid = !gizmos.grid;
+
+                info!("Grid: {}", gizmos.grid);
+            }
+
+            if pressed(Action::Fxaa) {
+                post_effects.fxaa = !post_effects.fxaa;
+
+                info!("FXAA: {}", post_effects.fxaa);
+            }
+
+            if pressed(Action::Taa) {
+                params.taa.x =
+                    if params.taa.x > 0.0 { 0.0 } else { TAA };
+
+                info!("TAA: {}", params.taa.x > 0.0);
+            }
+
+            if pressed(Action::Scopes) {
+                show_scopes = !show_scopes;
+            }
+
+            if pressed(Action::SplitScreen) {
+                split = match split {
+                    Some(_) => None,
+                    None => Some(0.5),
+                };
+
+                info!("Split-screen: {}", split.is_some());
+            }
+
+            if pressed(Action::Gallery) {
+                params.gallery ^= 1;
+
+                info!("Gallery: {}", params.gallery);
+            }
+
+            if params.gallery != 0 && input.mouse_pressed(0) {
+                if let Some((x, y)) = input.mouse() {
+                    let (scene, _) =
+                        params.gallery_scene_at(vec2(x, y));
+
+                    if scene != 0 {
+                        params.scene = scene;
+                        params.gallery = 0;
+
+                        info!("Scene: {scene}");
+                    }
+                }
+            } else if split.is_some() && input.mouse_held(0) {
+                if let Some((x, _)) = input.mouse() {
+                    split = Some(x / params.width as f32);
+                }
+            }
+
+            update_roi(&input, &mut roi_start, &mut params);
+            update_probe(&input, show_probe, &mut params);
+            update_fovea(&input, foveated, &mut params);
+
+            if pressed(Action::DebugView) {
+                params.debug_view = (params.debug_view + 1)
+                    % debug_view::COUNT;
+
+                info!("Debug view: {}", params.debug_view);
+            }
+
+            for scene in 1..=SCENES {
+                if pressed(Action::Scene(scene)) {
+                    params.scene = scene;
+
+                    info!("Scene: {scene}");
+                }
+            }
+
+            if pressed(Action::MotionBlur) {
+                params.shutter = if params.shutter > 0.0 {
+                    0.0
+                } else {
+                    SHUTTER
+                };
+
+                info!("Motion blur: {}", params.shutter > 0.0);
+            }
+
+            if pressed(Action::Coarse) {
+                params.coarse = match params.coarse {
+                    0 => 2,
+                    2 => 4,
+                    _ => 0,
+                };
+
+                info!("Coarse pre-pass: {}", params.coarse);
+            }
+
+            if pressed(Action::ShadowCatcher) {
+                params.shadow_catcher ^= 1;
+
+                info!("Shadow catcher: {}", params.shadow_catcher);
+            }
+
+            if pressed(Action::Subsurface) {
+                params.subsurface = if params.subsurface > 0.0 {
+                    0.0
+                } else {
+                    1.0
+                };
+
+                info!("Subsurface scattering: {}", params.subsurface);
+            }
+
+            if pressed(Action::Shading) {
+                params.shading = (params.shading + 1) % shading::COUNT;
+
+                info!("Shading: {}", params.shading);
+            }
+
+            for quality in [
+                quality::LOW,
+                quality::MEDIUM,
+                quality::HIGH,
+                quality::ULTRA,
+            ] {
+                if pressed(Action::Quality(quality)) {
+                    params.set_quality(quality);
+
+                    info!("Quality: {quality}");
+                }
+            }
+
+            if pressed(Action::Checkerboard) {
+                params.checkerboard ^= 1;
+
+                info!("Checkerboard: {}", params.checkerboard);
+            }
+
+            if pressed(Action::Stereo) {
+                params.stereo =
+                    (params.stereo + 1) % stereo::COUNT;
+
+                info!("Stereo: {}", params.stereo);
+            }
+
+            if pressed(Action::Relief) {
+                params.relief.x =
+                    if params.relief.x != 0.0 { 0.0 } else { 1.0 };
+
+                info!("Relief mode: {}", params.relief.x != 0.0);
+            }
+
+            if params.stereo != stereo::OFF {
+                update_eye_separation(&pressed, &mut params);
+            }
+
+            if params.debug_view == debug_view::SLICE {
+                update_slice(&pressed, &mut params);
+            }
+
+            hot_variables.apply(&mut params);
+
             if let Some(window_size) =
                 input.window_resized()
             {
@@ -111,23 +1274,311 @@ fn main() {
                 params.width = window_size.width;
                 params.height = window_size.height;
 
-                pixels
-                    .resize_surface(
-                        params.width,
-                        params.height,
-                    )
-                    .unwrap();
-
-                if let Some(renderer) = &mut renderer {
-                    renderer.resize(
-                        &pixels,
-                        params.width,
-                        params.height,
-                    );
+                if span {
+                    params.frustum =
+                        wall::frustum(params.width, params.height);
                 }
+
+                gpu.resize(params.width, params.height);
             }
 
-            window.request_redraw();
+            if occluded
+                || params.width == 0
+                || params.height == 0
+            {
+                // Window is minimized or hidden - there's no point in
+                // rendering anything, but let's wake up from time to time to
+                // check up on the compiler
+                *control_flow = ControlFlow::WaitUntil(
+                    Instant::now()
+                        + Duration::from_millis(100),
+                );
+            } else if !focused {
+                // Window is visible, but in the background - most likely
+                // you're live-coding the shader with an editor opened next to
+                // it, so we keep rendering, just at a lower rate
+                *control_flow = ControlFlow::WaitUntil(
+                    Instant::now()
+                        + Duration::from_millis(33),
+                );
+
+                window.request_redraw();
+
+                if let Some(operator) = &operator {
+                    operator.request_redraw();
+                }
+            } else if wallpaper {
+                *control_flow = ControlFlow::WaitUntil(
+                    Instant::now() + wallpaper::FRAME_TIME,
+                );
+
+                window.request_redraw();
+            } else {
+                *control_flow = ControlFlow::Poll;
+                window.request_redraw();
+
+                if let Some(operator) = &operator {
+                    operator.request_redraw();
+                }
+            }
         }
     });
 }
+
+/// Updates the window's title - it works as a minimal status line, showing the
+/// scene, frame rate, resolution, compiler's status and warnings (if any).
+fn set_title(
+    window: &Window,
+    params: &Params,
+    fps: f32,
+    status: &str,
+    warning: &Option<String>,
+) {
+    let scene = if params.gallery != 0 {
+        "gallery"
+    } else {
+        scene_name(params.scene)
+    };
+
+    let mut title = format!(
+        "sdf-playground - {scene} - {fps:.0} FPS - {}x{}",
+        params.width, params.height
+    );
+
+    if !status.is_empty() {
+        title += " - ";
+        title += status;
+    }
+
+    if let Some(warning) = warning {
+        title += " - ⚠ ";
+        title += warning;
+    }
+
+    if window.title() != title {
+        window.set_title(&title);
+    }
+}
+
+/// Replaces the current renderer with a new one, keeping the old one around so
+/// that we can crossfade between both (and compare them in split-screen).
+///
+/// If the new renderer couldn't be created, the current one is kept (so that a
+/// broken shader doesn't take down the entire application) and the error is
+/// returned, for the window's title.
+fn replace_renderer(
+    renderer: &mut Option<Renderer>,
+    prev_renderer: &mut Option<(Renderer, Instant)>,
+    new_renderer: Result<Renderer, Error>,
+) -> Result<(), Error> {
+    match new_renderer {
+        Ok(new_renderer) => {
+            *prev_renderer = renderer
+                .replace(new_renderer)
+                .map(|renderer| (renderer, Instant::now()));
+
+            Ok(())
+        }
+
+        Err(err) => {
+            error!("Couldn't load shader: {err}");
+            Err(err)
+        }
+    }
+}
+
+/// Switches the operator window (if it's open) to the same shader as given
+/// renderer is using.
+fn load_operator(
+    gpu: &Gpu,
+    operator: &mut Option<Operator>,
+    renderer: &Option<Renderer>,
+) {
+    if let (Some(operator), Some(renderer)) =
+        (operator, renderer)
+    {
+        if let Err(err) = operator.load(gpu, renderer) {
+            warn!("Couldn't load shader into operator window: {err}");
+        }
+    }
+}
+
+/// Restores given snapshot of the shader - which then gets picked up by the
+/// compiler, just like any other change.
+fn restore_snapshot(compiler: &Compiler, snapshot: &str) {
+    let Some(history) = compiler.history() else {
+        return;
+    };
+
+    match history.restore(snapshot, &shader_dir()) {
+        Ok(()) => info!("Restored shader from {snapshot}"),
+        Err(err) => {
+            error!("Couldn't restore shader: {err}")
+        }
+    }
+}
+
+/// Saves the frame that's just been rendered as a contact sheet.
+fn save_contact_sheet(gpu: &Gpu, renderer: &Renderer) {
+    let Some((size, pixels)) = renderer.read_output(gpu)
+    else {
+        error!(
+            "Couldn't download the frame for contact sheet"
+        );
+        return;
+    };
+
+    match contact_sheet::save(size, &pixels) {
+        Ok(path) => {
+            info!(
+                "Contact sheet saved as {}",
+                path.display()
+            )
+        }
+        Err(err) => {
+            error!("Couldn't save contact sheet: {err}")
+        }
+    }
+}
+
+/// Returns `idx`th element of the Halton sequence with given base - a number
+/// in `0..1`.
+fn halton(mut idx: u32, base: u32) -> f32 {
+    let mut result = 0.0;
+    let mut fraction = 1.0;
+
+    while idx > 0 {
+        fraction /= base as f32;
+        result += fraction * (idx % base) as f32;
+        idx /= base;
+    }
+
+    result
+}
+
+/// Logs given error and quits - used for errors we can't recover from, e.g.
+/// when there's no GPU to render with.
+fn fail(err: Error) -> ! {
+    error!("{err}");
+    process::exit(1);
+}
+
+/// Handles drawing the region of interest (see [`Params::roi`]) by dragging
+/// with the right mouse button; a click without dragging clears it.
+fn update_roi(
+    input: &WinitInputHelper,
+    roi_start: &mut Option<Vec2>,
+    params: &mut Params,
+) {
+    let Some((x, y)) = input.mouse() else {
+        return;
+    };
+
+    let mouse = vec2(x, y);
+
+    if input.mouse_pressed(1) {
+        *roi_start = Some(mouse);
+    }
+
+    if let Some(start) = *roi_start {
+        let min = start.min(mouse);
+        let max = start.max(mouse);
+
+        params.roi = vec4(min.x, min.y, max.x, max.y);
+
+        if !input.mouse_held(1) {
+            *roi_start = None;
+        }
+    }
+}
+
+/// Points [`Params::probe`] at the pixel under the mouse cursor (or disables
+/// it, if the probe is not shown).
+fn update_probe(
+    input: &WinitInputHelper,
+    show_probe: bool,
+    params: &mut Params,
+) {
+    params.probe = match input.mouse() {
+        Some((x, y)) if show_probe => vec4(x, y, 1.0, 0.0),
+        _ => Vec4::ZERO,
+    };
+}
+
+/// Focuses foveated rendering (see [`Params::fovea`]) on the mouse cursor, or
+/// on the center of the screen if the cursor is elsewhere.
+fn update_fovea(
+    input: &WinitInputHelper,
+    foveated: bool,
+    params: &mut Params,
+) {
+    if !foveated || params.gallery != 0 {
+        params.fovea = Vec4::ZERO;
+        return;
+    }
+
+    let size =
+        vec2(params.width as f32, params.height as f32);
+
+    let center = input
+        .mouse()
+        .map(|(x, y)| vec2(x, y))
+        .filter(|mouse| {
+            mouse.cmpge(Vec2::ZERO).all()
+                && mouse.cmplt(size).all()
+        })
+        .unwrap_or(size * 0.5);
+
+    // Full resolution within a sixth of the screen, halved with each further
+    // sixth
+    let radius = size.min_element() / 6.0;
+
+    params.fovea = center.extend(radius).extend(0.0);
+}
+
+/// Handles keys controlling the cutting plane of [`debug_view::SLICE`] (by
+/// default):
+///
+/// - `X`, `Y`, `Z` make the plane perpendicular to given axis,
+/// - `[` and `]` move the plane along its normal.
+fn update_slice(
+    pressed: &impl Fn(Action) -> bool,
+    params: &mut Params,
+) {
+    for (action, normal) in [
+        (Action::SliceX, Vec3::X),
+        (Action::SliceY, Vec3::Y),
+        (Action::SliceZ, Vec3::Z),
+    ] {
+        if pressed(action) {
+            params.slice = normal.extend(params.slice.w);
+        }
+    }
+
+    if pressed(Action::SliceBackward) {
+        params.slice.w -= 0.25;
+    }
+
+    if pressed(Action::SliceForward) {
+        params.slice.w += 0.25;
+    }
+}
+
+/// Handles keys controlling the distance between eyes in stereo modes (by
+/// default):
+///
+/// - `-` brings the eyes closer together (flattening the scene),
+/// - `=` moves them apart (deepening the scene).
+fn update_eye_separation(
+    pressed: &impl Fn(Action) -> bool,
+    params: &mut Params,
+) {
+    if pressed(Action::EyesCloser) {
+        params.eye_separation =
+            (params.eye_separation - 0.05).max(0.0);
+    }
+
+    if pressed(Action::EyesApart) {
+        params.eye_separation += 0.05;
+    }
+}
@@ -0,0 +1,156 @@
+use log::warn;
+use sdf_playground_common::MATCAP_SIZE;
+use std::fs::File;
+use std::path::Path;
+use std::sync::Arc;
+use std::{env, io};
+
+/// Image used by [`sdf_playground_common::shading::MATCAP`] - a picture of a
+/// lit sphere, from which each pixel of the scene picks the color of the point
+/// whose normal matches its own.
+///
+/// Pixels are 8-bit sRGB, always `MATCAP_SIZE x MATCAP_SIZE` (images of other
+/// sizes get resampled), so that the shader doesn't have to care about the
+/// size.
+#[derive(Clone, Debug)]
+pub struct Matcap {
+    pixels: Arc<[u8]>,
+}
+
+impl Matcap {
+    /// Loads the matcap pointed at by `SDF_PLAYGROUND_MATCAP`, falling back
+    /// to [`Self::clay()`].
+    pub fn from_env() -> Self {
+        let Some(path) =
+            env::var_os("SDF_PLAYGROUND_MATCAP")
+        else {
+            return Self::clay();
+        };
+
+        match Self::load(path.as_ref()) {
+            Ok(matcap) => matcap,
+
+            Err(err) => {
+                warn!(
+                    "Couldn't load matcap from {}: {err}",
+                    Path::new(&path).display()
+                );
+
+                Self::clay()
+            }
+        }
+    }
+
+    /// Returns whether given file looks like something [`Self::load()`] can
+    /// load.
+    pub fn supports(path: &Path) -> bool {
+        path.extension().and_then(|ext| ext.to_str())
+            == Some("png")
+    }
+
+    /// Loads matcap from given PNG file.
+    pub fn load(
+        path: &Path,
+    ) -> Result<Self, png::DecodingError> {
+        let mut decoder = png::Decoder::new(
+            io::BufReader::new(File::open(path)?),
+        );
+
+        // Palettes and 16-bit channels get converted into plain 8-bit ones
+        decoder.set_transformations(
+            png::Transformations::normalize_to_color8(),
+        );
+
+        let mut reader = decoder.read_info()?;
+        let mut image =
+            vec![0; reader.output_buffer_size()];
+        let info = reader.next_frame(&mut image)?;
+
+        let channels = info.color_type.samples();
+        let size = MATCAP_SIZE as usize;
+        let mut pixels =
+            Vec::with_capacity(4 * size * size);
+
+        for y in 0..size {
+            for x in 0..size {
+                let x = x * info.width as usize / size;
+                let y = y * info.height as usize / size;
+                let offset =
+                    y * info.line_size + x * channels;
+                let pixel =
+                    &image[offset..offset + channels];
+
+                pixels.extend_from_slice(&match pixel {
+                    [l] | [l, _] => [*l, *l, *l, 255],
+                    [r, g, b] | [r, g, b, _] => {
+                        [*r, *g, *b, 255]
+                    }
+                    _ => unreachable!(),
+                });
+            }
+        }
+
+        Ok(Self {
+            pixels: pixels.into(),
+        })
+    }
+
+    /// Neutral, clay-like matcap - lit from the top-left, with a soft
+    /// highlight; used when no other matcap has been provided.
+    pub fn clay() -> Self {
+        let size = MATCAP_SIZE as usize;
+        let mut pixels =
+            Vec::with_capacity(4 * size * size);
+
+        for y in 0..size {
+            for x in 0..size {
+                // Point on the sphere, with `z` pointing at the viewer
+                let nx = (x as f32 + 0.5) / size as f32
+                    * 2.0
+                    - 1.0;
+                let ny = 1.0
+                    - (y as f32 + 0.5) / size as f32 * 2.0;
+                let nz = (1.0 - nx * nx - ny * ny)
+                    .max(0.0)
+                    .sqrt();
+
+                let light =
+                    (-0.5 * nx + 0.6 * ny + 0.6 * nz)
+                        .max(0.0);
+
+                let intensity = 0.1
+                    + 0.8 * light
+                    + 0.3 * light.powf(30.0);
+
+                let channel = |albedo: f32| {
+                    let linear =
+                        (albedo * intensity).min(1.0);
+
+                    (linear.powf(1.0 / 2.2) * 255.0) as u8
+                };
+
+                pixels.extend_from_slice(&[
+                    channel(0.8),
+                    channel(0.7),
+                    channel(0.6),
+                    255,
+                ]);
+            }
+        }
+
+        Self {
+            pixels: pixels.into(),
+        }
+    }
+
+    /// Returns pixels of the image, as RGBA.
+    pub fn pixels(&self) -> &[u8] {
+        &self.pixels
+    }
+
+    /// Returns whether both matcaps are the same image (i.e. one has been
+    /// cloned from the other).
+    pub fn same(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.pixels, &other.pixels)
+    }
+}
@@ -0,0 +1,284 @@
+//! Operator window (`--operator`) - a second window showing the gallery of all
+//! scenes (click one to switch the output to it), the frame-time graph and
+//! stats of the main window (scene, FPS, resolution, quality and the shader's
+//! status), so that the main window can be projected without any overlays on
+//! it.
+//!
+//! Keyboard controls work in both windows; panels opened with them (help,
+//! command palette, history, hot variables and the probe) are shown here, in
+//! place of the stats, instead of in the main window.
+
+use crate::error::Error;
+use crate::frame_graph::FrameGraph;
+use crate::gpu::Gpu;
+use crate::renderer::Renderer;
+use crate::text::Text;
+use sdf_playground_common::glam::{vec2, Vec2, Vec4};
+use sdf_playground_common::{scene_name, Params, FRUSTUM};
+use winit::dpi::LogicalSize;
+use winit::event::{
+    ElementState, Event, MouseButton, WindowEvent,
+};
+use winit::event_loop::EventLoop;
+use winit::window::{Window, WindowBuilder, WindowId};
+
+#[derive(Debug)]
+pub struct Operator {
+    // (declared before the window, so that it gets dropped first)
+    surface: wgpu::Surface,
+    config: wgpu::SurfaceConfiguration,
+    window: Window,
+    renderer: Option<Renderer>,
+    text: Text,
+    cursor: Vec2,
+}
+
+impl Operator {
+    pub fn new(
+        event_loop: &EventLoop<()>,
+        gpu: &Gpu,
+    ) -> Result<Self, Error> {
+        let window = WindowBuilder::new()
+            .with_title("sdf-playground - operator")
+            .with_inner_size(LogicalSize::new(600, 400))
+            .build(event_loop)?;
+
+        // Safety: the surface is dropped before the window, see the struct
+        let (surface, config) =
+            unsafe { gpu.create_surface(&window) }?;
+
+        Ok(Self {
+            surface,
+            config,
+            window,
+            renderer: None,
+            text: Text::new(gpu),
+            cursor: Vec2::ZERO,
+        })
+    }
+
+    /// Recreates the surface on given GPU - used after the previous GPU has
+    /// been lost.
+    pub fn recreate(
+        &mut self,
+        gpu: &Gpu,
+    ) -> Result<(), Error> {
+        (self.surface, self.config) =
+            unsafe { gpu.create_surface(&self.window) }?;
+
+        if let Some(renderer) = &mut self.renderer {
+            renderer.recreate(gpu)?;
+        }
+
+        self.text.recreate(gpu);
+
+        Ok(())
+    }
+
+    /// Switches to the same shader as given renderer is using.
+    pub fn load(
+        &mut self,
+        gpu: &Gpu,
+        renderer: &Renderer,
+    ) -> Result<(), Error> {
+        self.renderer = Some(renderer.duplicate(gpu)?);
+
+        Ok(())
+    }
+
+    pub fn id(&self) -> WindowId {
+        self.window.id()
+    }
+
+    pub fn request_redraw(&self) {
+        self.window.request_redraw();
+    }
+
+    /// Returns whether given event should be handled by the main window, too;
+    /// out of the operator window's events, only keyboard input is forwarded.
+    pub fn forwards(&self, event: &Event<()>) -> bool {
+        match event {
+            Event::RedrawRequested(window_id) => {
+                *window_id != self.id()
+            }
+
+            Event::WindowEvent { window_id, event }
+                if *window_id == self.id() =>
+            {
+                matches!(
+                    event,
+                    WindowEvent::KeyboardInput { .. }
+                        | WindowEvent::ModifiersChanged(_)
+                )
+            }
+
+            _ => true,
+        }
+    }
+
+    /// Handles events of the operator window (resizing, clicking on the
+    /// gallery etc.); returns whether the window has been closed.
+    pub fn handle(
+        &mut self,
+        gpu: &Gpu,
+        event: &Event<()>,
+        params: &mut Params,
+    ) -> bool {
+        let Event::WindowEvent { window_id, event } = event
+        else {
+            return false;
+        };
+
+        if *window_id != self.id() {
+            return false;
+        }
+
+        match event {
+            WindowEvent::CloseRequested => {
+                return true;
+            }
+
+            WindowEvent::Resized(size) => {
+                // Minimized windows report a size of zero, which is not
+                // something we can configure the surface with
+                if size.width > 0 && size.height > 0 {
+                    self.config.width = size.width;
+                    self.config.height = size.height;
+                    self.surface.configure(
+                        gpu.device(),
+                        &self.config,
+                    );
+                }
+            }
+
+            WindowEvent::CursorMoved {
+                position, ..
+            } => {
+                self.cursor = vec2(
+                    position.x as f32,
+                    position.y as f32,
+                );
+            }
+
+            WindowEvent::MouseInput {
+                state: ElementState::Pressed,
+                button: MouseButton::Left,
+                ..
+            } => {
+                let (scene, _) = self
+                    .params(params)
+                    .gallery_scene_at(self.cursor);
+
+                if scene != 0 {
+                    params.scene = scene;
+                }
+            }
+
+            _ => (),
+        }
+
+        false
+    }
+
+    /// Renders the gallery, the frame graph and the stats (or given panel, if
+    /// there's one open).
+    pub fn render(
+        &mut self,
+        gpu: &Gpu,
+        params: &Params,
+        frame_graph: &FrameGraph,
+        panel: Option<&[String]>,
+        fps: f32,
+        status: &str,
+    ) -> Result<(), wgpu::SurfaceError> {
+        let text = panel.map_or_else(
+            || stats(params, fps, status),
+            |panel| panel.to_vec(),
+        );
+        let params = self.params(params);
+
+        let title = format!(
+            "sdf-playground - operator - {}",
+            scene_name(params.scene)
+        );
+
+        if self.window.title() != title {
+            self.window.set_title(&title);
+        }
+
+        gpu.render_to(&self.surface, |encoder, target| {
+            let Some(renderer) = &mut self.renderer else {
+                Gpu::clear(encoder, target);
+                return;
+            };
+
+            renderer.update(gpu, &params);
+            renderer.render(encoder, target, 1.0, None);
+
+            frame_graph.render(
+                gpu.queue(),
+                encoder,
+                target,
+                params.width,
+                params.height,
+            );
+
+            self.text.render(
+                gpu,
+                encoder,
+                target,
+                text,
+                params.width,
+                params.height,
+            );
+        })
+    }
+
+    /// Returns params for rendering the operator window, based on the params
+    /// of the main one.
+    fn params(&self, params: &Params) -> Params {
+        Params {
+            width: self.config.width,
+            height: self.config.height,
+            gallery: 1,
+            checkerboard: 0,
+            roi: Vec4::ZERO,
+            frustum: FRUSTUM,
+            probe: Vec4::ZERO,
+            fovea: Vec4::ZERO,
+            ..*params
+        }
+    }
+}
+
+/// Returns stats of the main window, shown in the operator one.
+fn stats(
+    params: &Params,
+    fps: f32,
+    status: &str,
+) -> Vec<String> {
+    let mut stats = vec![
+        format!(
+            "scene {}: {}",
+            params.scene,
+            scene_name(params.scene)
+        ),
+        format!(
+            "{fps:.0} fps ({:.1} ms)",
+            1000.0 / fps.max(1.0)
+        ),
+        format!(
+            "{}x{}, {} steps, {} samples",
+            params.width,
+            params.height,
+            params.steps,
+            params.samples
+        ),
+    ];
+
+    if !status.is_empty() {
+        stats.push(status.into());
+    }
+
+    stats
+}
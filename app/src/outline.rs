@@ -0,0 +1,200 @@
+use crate::gpu::Gpu;
+
+/// Toon-style outlines, drawn over the scene by a post-process edge detector
+/// which compares normals and distances of neighbouring pixels (see
+/// [`crate::renderer::Renderer::aov()`]) - makes silhouettes and creases pop.
+#[derive(Debug)]
+pub struct Outline {
+    /// How far apart (in pixels) are the neighbours being compared - the
+    /// larger, the thicker the outlines get; zero disables outlines.
+    pub thickness: u32,
+
+    /// How different the neighbours have to be to get an outline between
+    /// them; the smaller, the more outlines there are.
+    pub threshold: f32,
+
+    pipeline: wgpu::RenderPipeline,
+    bind_group_layout: wgpu::BindGroupLayout,
+    buffer: wgpu::Buffer,
+}
+
+impl Outline {
+    pub fn new(gpu: &Gpu) -> Self {
+        let device = gpu.device();
+
+        let module = device.create_shader_module(
+            wgpu::ShaderModuleDescriptor {
+                label: Some("outline_shader"),
+                source: wgpu::ShaderSource::Wgsl(
+                    include_str!("outline.wgsl").into(),
+                ),
+            },
+        );
+
+        let buffer =
+            device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some("outline_buffer"),
+                size: 4 * 4,
+                usage: wgpu::BufferUsages::UNIFORM
+                    | wgpu::BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            });
+
+        let bind_group_layout = device
+            .create_bind_group_layout(
+                &wgpu::BindGroupLayoutDescriptor {
+                    label: Some("outline_bind_group_layout"),
+                    entries: &[
+                        wgpu::BindGroupLayoutEntry {
+                            binding: 0,
+                            visibility:
+                                wgpu::ShaderStages::FRAGMENT,
+                            ty: wgpu::BindingType::Texture {
+                                sample_type:
+                                    wgpu::TextureSampleType::Float {
+                                        filterable: false,
+                                    },
+                                view_dimension:
+                                    wgpu::TextureViewDimension::D2,
+                                multisampled: false,
+                            },
+                            count: None,
+                        },
+                        wgpu::BindGroupLayoutEntry {
+                            binding: 1,
+                            visibility:
+                                wgpu::ShaderStages::FRAGMENT,
+                            ty: wgpu::BindingType::Buffer {
+                                ty: wgpu::BufferBindingType::Uniform,
+                                has_dynamic_offset: false,
+                                min_binding_size: None,
+                            },
+                            count: None,
+                        },
+                    ],
+                },
+            );
+
+        let pipeline_layout = device
+            .create_pipeline_layout(
+                &wgpu::PipelineLayoutDescriptor {
+                    label: Some("outline_pipeline_layout"),
+                    bind_group_layouts: &[
+                        &bind_group_layout,
+                    ],
+                    push_constant_ranges: &[],
+                },
+            );
+
+        let pipeline = device.create_render_pipeline(
+            &wgpu::RenderPipelineDescriptor {
+                label: Some("outline_pipeline"),
+                layout: Some(&pipeline_layout),
+                vertex: wgpu::VertexState {
+                    module: &module,
+                    entry_point: "main_vs",
+                    buffers: &[],
+                },
+                primitive: wgpu::PrimitiveState::default(),
+                depth_stencil: None,
+                multisample: wgpu::MultisampleState::default(),
+                fragment: Some(wgpu::FragmentState {
+                    module: &module,
+                    entry_point: "main_fs",
+                    targets: &[Some(wgpu::ColorTargetState {
+                        format: gpu.format(),
+                        blend: Some(
+                            wgpu::BlendState::ALPHA_BLENDING,
+                        ),
+                        write_mask: wgpu::ColorWrites::ALL,
+                    })],
+                }),
+                multiview: None,
+            },
+        );
+
+        Self {
+            thickness: 0,
+            threshold: 0.1,
+            pipeline,
+            bind_group_layout,
+            buffer,
+        }
+    }
+
+    /// Recreates the pipeline on given GPU, preserving the settings - used
+    /// after the previous GPU has been lost.
+    pub fn recreate(&mut self, gpu: &Gpu) {
+        *self = Self {
+            thickness: self.thickness,
+            threshold: self.threshold,
+            ..Self::new(gpu)
+        };
+    }
+
+    /// Draws outlines of the scene described by `aov` over whatever the
+    /// target already contains.
+    pub fn render(
+        &self,
+        gpu: &Gpu,
+        encoder: &mut wgpu::CommandEncoder,
+        target: &wgpu::TextureView,
+        aov: &wgpu::TextureView,
+    ) {
+        if self.thickness == 0 {
+            return;
+        }
+
+        gpu.queue().write_buffer(
+            &self.buffer,
+            0,
+            bytemuck::cast_slice(&[
+                self.thickness as f32,
+                self.threshold,
+                0.0,
+                0.0,
+            ]),
+        );
+
+        let bind_group = gpu.device().create_bind_group(
+            &wgpu::BindGroupDescriptor {
+                label: Some("outline_bind_group"),
+                layout: &self.bind_group_layout,
+                entries: &[
+                    wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource:
+                            wgpu::BindingResource::TextureView(
+                                aov,
+                            ),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 1,
+                        resource: self.buffer.as_entire_binding(),
+                    },
+                ],
+            },
+        );
+
+        let mut pass = encoder.begin_render_pass(
+            &wgpu::RenderPassDescriptor {
+                label: Some("outline_render_pass"),
+                color_attachments: &[Some(
+                    wgpu::RenderPassColorAttachment {
+                        view: target,
+                        resolve_target: None,
+                        ops: wgpu::Operations {
+                            load: wgpu::LoadOp::Load,
+                            store: true,
+                        },
+                    },
+                )],
+                depth_stencil_attachment: None,
+            },
+        );
+
+        pass.set_pipeline(&self.pipeline);
+        pass.set_bind_group(0, &bind_group, &[]);
+        pass.draw(0..3, 0..1);
+    }
+}
@@ -0,0 +1,63 @@
+// Draws outlines over the scene, see `outline.rs`.
+
+struct Settings {
+    thickness: f32,
+    threshold: f32,
+}
+
+@group(0) @binding(0)
+var aov: texture_2d<f32>;
+
+@group(0) @binding(1)
+var<uniform> settings: Settings;
+
+// Full-screen triangle, same as in the main shader
+@vertex
+fn main_vs(@builtin(vertex_index) id: u32) -> @builtin(position) vec4<f32> {
+    let uv = vec2<f32>(f32((id << 1u) & 2u), f32(id & 2u));
+
+    return vec4<f32>(uv.x * 2.0 - 1.0, 1.0 - uv.y * 2.0, 0.0, 1.0);
+}
+
+fn fetch(pos: vec2<i32>) -> vec4<f32> {
+    let size = vec2<i32>(textureDimensions(aov));
+
+    return textureLoad(aov, clamp(pos, vec2<i32>(0), size - 1), 0);
+}
+
+// Returns whether there's an edge between two pixels - either because one of
+// them is much farther away than the other (silhouettes) or because their
+// surfaces face different directions (creases)
+fn edge(a: vec4<f32>, b: vec4<f32>) -> bool {
+    // Pixels that haven't been rendered during this frame
+    if a.w < 0.0 || b.w < 0.0 {
+        return false;
+    }
+
+    let depth = abs(a.w - b.w) / max(min(a.w, b.w), 0.001);
+    let normal = (1.0 - dot(a.xyz, b.xyz)) * 0.5;
+
+    return depth > settings.threshold || normal > settings.threshold;
+}
+
+@fragment
+fn main_fs(@builtin(position) pos: vec4<f32>) -> @location(0) vec4<f32> {
+    let center = vec2<i32>(pos.xy);
+    let t = i32(settings.thickness);
+
+    // We compare both the diagonal and the horizontal / vertical neighbours,
+    // since an edge running along one of those pairs is missed by it
+    let diagonal =
+        edge(fetch(center + vec2<i32>(-t, -t)), fetch(center + vec2<i32>(t, t)))
+        || edge(fetch(center + vec2<i32>(t, -t)), fetch(center + vec2<i32>(-t, t)));
+
+    let straight =
+        edge(fetch(center + vec2<i32>(-t, 0)), fetch(center + vec2<i32>(t, 0)))
+        || edge(fetch(center + vec2<i32>(0, -t)), fetch(center + vec2<i32>(0, t)));
+
+    if diagonal || straight {
+        return vec4<f32>(0.0, 0.0, 0.0, 1.0);
+    }
+
+    return vec4<f32>(0.0);
+}
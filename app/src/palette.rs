@@ -0,0 +1,168 @@
+//! Command palette (`Ctrl+P`) - lets you trigger any action by typing a part
+//! of its name (e.g. `sh ca` for `shadow catcher`), so that features remain
+//! reachable without remembering all the keys.
+
+use crate::keymap::{key_name, Action, Keymap};
+use winit::event::VirtualKeyCode;
+use winit_input_helper::{TextChar, WinitInputHelper};
+
+/// How many matching actions are shown at once.
+const RESULTS: usize = 12;
+
+#[derive(Debug, Default)]
+pub struct Palette {
+    query: String,
+
+    /// Index of the highlighted action, within the matching ones.
+    selected: usize,
+}
+
+/// What happened to the palette after processing the input.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PaletteEvent {
+    /// Palette is still open.
+    Idle,
+
+    /// Palette has been closed without picking anything.
+    Closed,
+
+    /// Given action has been picked - the palette should be closed and the
+    /// action triggered.
+    Picked(Action),
+}
+
+impl Palette {
+    /// Handles typing, moving the selection with arrows and confirming it with
+    /// `Enter` (or closing the palette with `Esc`).
+    pub fn update(
+        &mut self,
+        input: &WinitInputHelper,
+        keymap: &Keymap,
+    ) -> PaletteEvent {
+        if input.key_pressed(VirtualKeyCode::Escape) {
+            return PaletteEvent::Closed;
+        }
+
+        for ch in input.text() {
+            match ch {
+                TextChar::Char(ch) if !ch.is_control() => {
+                    self.query.push(ch);
+                    self.selected = 0;
+                }
+
+                TextChar::Back => {
+                    self.query.pop();
+                    self.selected = 0;
+                }
+
+                _ => (),
+            }
+        }
+
+        let matches = self.matches(keymap);
+
+        if input.key_pressed(VirtualKeyCode::Up) {
+            self.selected = self.selected.saturating_sub(1);
+        }
+
+        if input.key_pressed(VirtualKeyCode::Down) {
+            self.selected = (self.selected + 1)
+                .min(matches.len().saturating_sub(1));
+        }
+
+        if input.key_pressed(VirtualKeyCode::Return) {
+            return match matches.get(self.selected) {
+                Some(&(_, action, _)) => {
+                    PaletteEvent::Picked(action)
+                }
+                None => PaletteEvent::Closed,
+            };
+        }
+
+        PaletteEvent::Idle
+    }
+
+    /// Returns the palette's text - the query followed by the matching actions
+    /// (and their keys).
+    pub fn text(&self, keymap: &Keymap) -> Vec<String> {
+        let matches = self.matches(keymap);
+
+        // Scroll the results so that the selection is always visible
+        let skip =
+            self.selected.saturating_sub(RESULTS - 1);
+
+        let mut text = vec![
+            format!("> {}_", self.query),
+            String::new(),
+        ];
+
+        text.extend(
+            matches
+                .iter()
+                .enumerate()
+                .skip(skip)
+                .take(RESULTS)
+                .map(|(idx, (name, _, key))| {
+                    let cursor = if idx == self.selected {
+                        '>'
+                    } else {
+                        ' '
+                    };
+
+                    format!(
+                        "{cursor} {name:<16}  {}",
+                        key_name(*key)
+                    )
+                }),
+        );
+
+        if matches.is_empty() {
+            text.push("  no matching actions".into());
+        }
+
+        text
+    }
+
+    /// Returns actions matching the query, the best matches first.
+    fn matches(
+        &self,
+        keymap: &Keymap,
+    ) -> Vec<(String, Action, VirtualKeyCode)> {
+        let mut matches: Vec<_> = keymap
+            .bindings()
+            .filter_map(|(name, action, key)| {
+                let name = name.replace('_', " ");
+                let score = score(&name, &self.query)?;
+
+                Some((score, (name, action, key)))
+            })
+            .collect();
+
+        // (stable, so equally good matches stay in the keymap's order)
+        matches.sort_by_key(|(score, _)| *score);
+        matches.into_iter().map(|(_, item)| item).collect()
+    }
+}
+
+/// Checks whether all characters of `query` appear in `name` in the same order
+/// (ignoring case and spaces), returning how far apart they are - the lower,
+/// the better the match.
+fn score(name: &str, query: &str) -> Option<usize> {
+    let mut name = name.chars().enumerate();
+    let mut score = 0;
+    let mut prev = None;
+
+    for ch in query.chars().filter(|ch| *ch != ' ') {
+        let ch = ch.to_ascii_lowercase();
+        let (idx, _) = name.find(|(_, c)| *c == ch)?;
+
+        score += match prev {
+            Some(prev) => idx - prev - 1,
+            None => idx,
+        };
+
+        prev = Some(idx);
+    }
+
+    Some(score)
+}
@@ -0,0 +1,286 @@
+//! Perfect-loop mode (`--loop <dir>`) - renders exactly one period of a
+//! scene's animation as a sequence of PNGs, so that GIFs and videos made out
+//! of them loop seamlessly:
+//!
+//! ```text
+//! $ cargo run --release -- --loop frames --scene 3 -v
+//! $ ffmpeg -framerate 30 -i frames/frame-%04d.png scene-3.gif
+//! ```
+//!
+//! The period is, in order of preference:
+//!
+//! - whatever's given through `--period <seconds>`,
+//! - the period of the time curve set up by the script, if it's ping-pong or
+//!   sawtooth (see [`Params::time_curve`]),
+//! - found by sampling the scene's distance field (see [`detect_period()`]) -
+//!   which works for animations driven by `sin()`, `cos()` etc., but not for
+//!   the ones that never repeat (such as the ocean).
+//!
+//! Frames are evenly spread over the period, with the last one stopping just
+//! short of its end - so that the next frame (which would be the first one
+//! again) isn't duplicated.
+//!
+//! Progress is logged as info, so it's shown with `-v`.
+
+use crate::contact_sheet::srgb;
+use crate::error::Error;
+use crate::headless::{fail, Headless};
+use crate::image::encode_png;
+use crate::script::Script;
+use log::info;
+use sdf_playground_common::glam::{
+    vec3, UVec2, Vec3, Vec4,
+};
+use sdf_playground_common::{quality, time_curve, Params};
+use sdf_playground_shader::{bounds, scene, Bounds};
+use std::fs;
+use std::path::Path;
+use winit::event_loop::EventLoop;
+
+const WIDTH: u32 = 640;
+const HEIGHT: u32 = 360;
+
+/// Frames per second, unless specified through `--fps`.
+const FPS: f32 = 30.0;
+
+/// Periods checked by [`detect_period()`], in seconds.
+const MIN_PERIOD: f32 = 0.1;
+const MAX_PERIOD: f32 = 60.0;
+
+/// Step by which [`detect_period()`] scans the periods, in seconds.
+const PERIOD_STEP: f32 = 1.0 / 60.0;
+
+/// Average difference in distance (between the first moment and a candidate
+/// period) below which the scene is considered to be back where it started.
+const TOLERANCE: f32 = 1e-3;
+
+pub fn run(
+    event_loop: &EventLoop<()>,
+    dir: &Path,
+    scene_id: u32,
+    period: Option<f32>,
+    fps: Option<f32>,
+) {
+    let mut params = Params {
+        scene: scene_id,
+        ..Params::new(WIDTH, HEIGHT)
+    };
+
+    params.set_quality(quality::HIGH);
+
+    let mut script = Script::new();
+
+    // (the script might set up a time curve, which decides the period)
+    script.run(&mut params);
+
+    // (easing doesn't wrap the time around, so it doesn't decide anything on
+    // its own)
+    let curve_period = matches!(
+        params.time_curve.x as u32,
+        time_curve::PING_PONG | time_curve::SAWTOOTH
+    )
+    .then_some(params.time_curve.y)
+    .filter(|&period| period > 0.0);
+
+    let period = period.or(curve_period).unwrap_or_else(|| {
+        info!("Looking for the period of scene {scene_id}...");
+
+        detect_period(&params).unwrap_or_else(|| {
+            fail(format!(
+                "Couldn't find the period of scene {scene_id} (it might \
+                 not be animated, or it might never repeat) - specify it \
+                 with --period <seconds>"
+            ))
+        })
+    });
+
+    let fps = fps.unwrap_or(FPS);
+    let frames = (period * fps).round().max(1.0) as u32;
+
+    info!(
+        "Rendering {frames} frames ({period:.3}s at {fps} FPS) into {}",
+        dir.display()
+    );
+
+    let Headless {
+        gpu,
+        mut renderer,
+        target,
+        ..
+    } = Headless::new(
+        event_loop,
+        "perfect_loop_target",
+        WIDTH,
+        HEIGHT,
+    );
+
+    fs::create_dir_all(dir)
+        .unwrap_or_else(|err| fail(Error::io(dir)(err)));
+
+    for frame in 0..frames {
+        // (the time is derived from the frame, instead of being accumulated,
+        // so that it doesn't drift away from the period)
+        params.time = period * frame as f32 / frames as f32;
+        params.frame = frame;
+
+        script.run(&mut params);
+        renderer.update(&gpu, &params);
+
+        let mut encoder =
+            gpu.device().create_command_encoder(
+                &wgpu::CommandEncoderDescriptor {
+                    label: Some(
+                        "perfect_loop_command_encoder",
+                    ),
+                },
+            );
+
+        renderer.render(&mut encoder, &target, 1.0, None);
+        gpu.queue().submit(Some(encoder.finish()));
+
+        let Some((size, pixels)) =
+            renderer.read_output(&gpu)
+        else {
+            fail("Couldn't download the frame");
+        };
+
+        let path =
+            dir.join(format!("frame-{frame:04}.png"));
+
+        save(&path, size, &pixels)
+            .unwrap_or_else(|err| fail(err));
+    }
+
+    info!("Done");
+}
+
+/// Finds the shortest period after which the scene's distance field repeats
+/// itself, by comparing it at a couple of points (spread over the scene's
+/// bounds) and moments with how it was one period earlier.
+///
+/// Only the distance field is checked - if the scene's shading is animated on
+/// its own, the period has to be specified by hand.
+fn detect_period(params: &Params) -> Option<f32> {
+    let extent = match bounds(params.scene) {
+        Some(Bounds::Sphere { radius }) => {
+            Vec3::splat(radius)
+        }
+        Some(Bounds::Cuboid { half_size }) => half_size,
+        None => Vec3::splat(5.0),
+    };
+
+    // (a bit larger than the bounds, so that the surface is in between)
+    let points: Vec<_> = (0..125)
+        .map(|idx| {
+            let cell = vec3(
+                (idx % 5) as f32,
+                (idx / 5 % 5) as f32,
+                (idx / 25) as f32,
+            );
+
+            (cell / 2.0 - 1.0) * extent * 1.2
+        })
+        .collect();
+
+    let distances = |time: f32| {
+        let params = Params { time, ..*params };
+        let time = params.scene_time();
+
+        points.iter().map(move |point| {
+            scene(
+                params.scene,
+                time,
+                &params.hot,
+                *point,
+                0.0,
+            )
+        })
+    };
+
+    // Comparing just with the first moment isn't enough - e.g. a pendulum
+    // passes through the same spot twice per period, just in the opposite
+    // directions - so we compare a couple of (arbitrary) moments
+    let error = |period: f32| {
+        [0.0, 0.37, 1.13]
+            .into_iter()
+            .flat_map(|time| {
+                distances(time + period)
+                    .zip(distances(time))
+            })
+            .map(|(a, b)| (a - b).abs())
+            .sum::<f32>()
+            / (3 * points.len()) as f32
+    };
+
+    let steps =
+        ((MAX_PERIOD - MIN_PERIOD) / PERIOD_STEP) as u32;
+
+    // The error drops to zero at the period, but since we're checking just
+    // some of the moments, we'll most likely land near it - so let's look for
+    // the first local minimum that's low enough and then zoom into it
+    let mut prev = error(MIN_PERIOD);
+    let mut curr = error(MIN_PERIOD + PERIOD_STEP);
+
+    // (a scene that doesn't change at all has nothing to loop)
+    let mut animated = prev > TOLERANCE;
+
+    for step in 2..steps {
+        let time = MIN_PERIOD + step as f32 * PERIOD_STEP;
+        let next = error(time);
+
+        animated |= curr > TOLERANCE;
+
+        if animated
+            && curr <= prev
+            && curr <= next
+            && curr < 10.0 * TOLERANCE
+        {
+            let (mut min, mut max) =
+                (time - 2.0 * PERIOD_STEP, time);
+
+            for _ in 0..32 {
+                let a = min + (max - min) / 3.0;
+                let b = max - (max - min) / 3.0;
+
+                if error(a) < error(b) {
+                    max = b;
+                } else {
+                    min = a;
+                }
+            }
+
+            let period = (min + max) / 2.0;
+
+            if error(period) < TOLERANCE {
+                return Some(period);
+            }
+        }
+
+        (prev, curr) = (curr, next);
+    }
+
+    None
+}
+
+/// Saves given frame (as returned from [`Renderer::read_output()`]) as PNG.
+fn save(
+    path: &Path,
+    size: UVec2,
+    pixels: &[Vec4],
+) -> Result<(), Error> {
+    let pixels: Vec<_> = pixels
+        .iter()
+        .flat_map(|pixel| {
+            [srgb(pixel.x), srgb(pixel.y), srgb(pixel.z)]
+        })
+        .collect();
+
+    let png = encode_png(
+        size.x,
+        size.y,
+        png::ColorType::Rgb,
+        &pixels,
+    );
+
+    fs::write(path, png).map_err(Error::io(path))
+}
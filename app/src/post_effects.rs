@@ -0,0 +1,260 @@
+use crate::gpu::Gpu;
+use sdf_playground_common::glam::{uvec2, vec4, UVec2};
+use sdf_playground_common::Params;
+
+/// Post effects - stylized ones (see `Params::post_effects`), i.e. chromatic
+/// aberration, which splits colors apart towards the edges of the screen as
+/// if seen through a cheap lens, and film grain - plus FXAA, a cheap way of
+/// smoothing the edges when supersampling is too expensive.
+///
+/// Effects get applied to the frame exactly as it would've been displayed
+/// otherwise (crossfades, split-screen and all), so it has to be composed in
+/// [`Self::input()`] instead of the target.
+#[derive(Debug)]
+pub struct PostEffects {
+    pub fxaa: bool,
+
+    pipeline: wgpu::RenderPipeline,
+    bind_group_layout: wgpu::BindGroupLayout,
+    buffer: wgpu::Buffer,
+
+    /// Texture the frame gets composed in, see [`Self::input()`].
+    input: wgpu::TextureView,
+    input_size: UVec2,
+}
+
+impl PostEffects {
+    pub fn new(gpu: &Gpu) -> Self {
+        let device = gpu.device();
+
+        let module = device.create_shader_module(
+            wgpu::ShaderModuleDescriptor {
+                label: Some("post_effects_shader"),
+                source: wgpu::ShaderSource::Wgsl(
+                    include_str!("post_effects.wgsl")
+                        .into(),
+                ),
+            },
+        );
+
+        let buffer =
+            device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some("post_effects_buffer"),
+                size: 4 * 4,
+                usage: wgpu::BufferUsages::UNIFORM
+                    | wgpu::BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            });
+
+        let bind_group_layout = device
+            .create_bind_group_layout(
+                &wgpu::BindGroupLayoutDescriptor {
+                    label: Some("post_effects_bind_group_layout"),
+                    entries: &[
+                        wgpu::BindGroupLayoutEntry {
+                            binding: 0,
+                            visibility:
+                                wgpu::ShaderStages::FRAGMENT,
+                            ty: wgpu::BindingType::Texture {
+                                sample_type:
+                                    wgpu::TextureSampleType::Float {
+                                        filterable: false,
+                                    },
+                                view_dimension:
+                                    wgpu::TextureViewDimension::D2,
+                                multisampled: false,
+                            },
+                            count: None,
+                        },
+                        wgpu::BindGroupLayoutEntry {
+                            binding: 1,
+                            visibility:
+                                wgpu::ShaderStages::FRAGMENT,
+                            ty: wgpu::BindingType::Buffer {
+                                ty: wgpu::BufferBindingType::Uniform,
+                                has_dynamic_offset: false,
+                                min_binding_size: None,
+                            },
+                            count: None,
+                        },
+                    ],
+                },
+            );
+
+        let pipeline_layout = device
+            .create_pipeline_layout(
+                &wgpu::PipelineLayoutDescriptor {
+                    label: Some(
+                        "post_effects_pipeline_layout",
+                    ),
+                    bind_group_layouts: &[
+                        &bind_group_layout,
+                    ],
+                    push_constant_ranges: &[],
+                },
+            );
+
+        let pipeline = device.create_render_pipeline(
+            &wgpu::RenderPipelineDescriptor {
+                label: Some("post_effects_pipeline"),
+                layout: Some(&pipeline_layout),
+                vertex: wgpu::VertexState {
+                    module: &module,
+                    entry_point: "main_vs",
+                    buffers: &[],
+                },
+                primitive: wgpu::PrimitiveState::default(),
+                depth_stencil: None,
+                multisample:
+                    wgpu::MultisampleState::default(),
+                fragment: Some(wgpu::FragmentState {
+                    module: &module,
+                    entry_point: "main_fs",
+                    targets: &[Some(
+                        wgpu::ColorTargetState {
+                            format: gpu.format(),
+                            blend: None,
+                            write_mask:
+                                wgpu::ColorWrites::ALL,
+                        },
+                    )],
+                }),
+                multiview: None,
+            },
+        );
+
+        let input_size = UVec2::ONE;
+        let input = create_input(gpu, input_size);
+
+        Self {
+            fxaa: false,
+            pipeline,
+            bind_group_layout,
+            buffer,
+            input,
+            input_size,
+        }
+    }
+
+    /// Recreates the pipeline on given GPU, preserving the settings - used
+    /// after the previous GPU has been lost.
+    pub fn recreate(&mut self, gpu: &Gpu) {
+        *self = Self {
+            fxaa: self.fxaa,
+            ..Self::new(gpu)
+        };
+    }
+
+    /// Returns whether any of the effects is enabled - if not, there's no
+    /// need to go through [`Self::input()`].
+    pub fn enabled(&self, params: &Params) -> bool {
+        self.fxaa
+            || params.post_effects.x > 0.0
+            || params.post_effects.y > 0.0
+    }
+
+    /// Returns the texture the frame should be composed in (instead of the
+    /// target) for the effects to be applied to it by [`Self::render()`];
+    /// it's got the same format as the target.
+    pub fn input(
+        &mut self,
+        gpu: &Gpu,
+        params: &Params,
+    ) -> &wgpu::TextureView {
+        let size = uvec2(params.width, params.height)
+            .max(UVec2::ONE);
+
+        if size != self.input_size {
+            self.input_size = size;
+            self.input = create_input(gpu, size);
+        }
+
+        &self.input
+    }
+
+    /// Draws the frame composed in [`Self::input()`] with the post effects
+    /// applied into the target.
+    pub fn render(
+        &self,
+        gpu: &Gpu,
+        encoder: &mut wgpu::CommandEncoder,
+        target: &wgpu::TextureView,
+        params: &Params,
+    ) {
+        // (the frame's number makes the grain change each frame)
+        gpu.queue().write_buffer(
+            &self.buffer,
+            0,
+            bytemuck::bytes_of(&vec4(
+                params.post_effects.x,
+                params.post_effects.y,
+                params.frame as f32,
+                self.fxaa as u32 as f32,
+            )),
+        );
+
+        let bind_group = gpu.device().create_bind_group(
+            &wgpu::BindGroupDescriptor {
+                label: Some("post_effects_bind_group"),
+                layout: &self.bind_group_layout,
+                entries: &[
+                    wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource:
+                            wgpu::BindingResource::TextureView(
+                                &self.input,
+                            ),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 1,
+                        resource: self.buffer.as_entire_binding(),
+                    },
+                ],
+            },
+        );
+
+        let mut pass = encoder.begin_render_pass(
+            &wgpu::RenderPassDescriptor {
+                label: Some("post_effects_render_pass"),
+                color_attachments: &[Some(
+                    wgpu::RenderPassColorAttachment {
+                        view: target,
+                        resolve_target: None,
+                        ops: wgpu::Operations {
+                            load: wgpu::LoadOp::Load,
+                            store: true,
+                        },
+                    },
+                )],
+                depth_stencil_attachment: None,
+            },
+        );
+
+        pass.set_pipeline(&self.pipeline);
+        pass.set_bind_group(0, &bind_group, &[]);
+        pass.draw(0..3, 0..1);
+    }
+}
+
+fn create_input(
+    gpu: &Gpu,
+    size: UVec2,
+) -> wgpu::TextureView {
+    gpu.device()
+        .create_texture(&wgpu::TextureDescriptor {
+            label: Some("post_effects_input"),
+            size: wgpu::Extent3d {
+                width: size.x,
+                height: size.y,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: gpu.format(),
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT
+                | wgpu::TextureUsages::TEXTURE_BINDING,
+            view_formats: &[],
+        })
+        .create_view(&Default::default())
+}
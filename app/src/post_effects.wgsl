@@ -0,0 +1,154 @@
+// Applies stylized post effects to the frame, see `post_effects.rs`.
+
+struct Settings {
+    // Same as `Params::post_effects`
+    aberration: f32,
+    grain: f32,
+
+    // Number of the current frame, so that the grain changes each frame
+    frame: f32,
+
+    // Whether FXAA is enabled (non-zero)
+    fxaa: f32,
+}
+
+@group(0) @binding(0)
+var frame: texture_2d<f32>;
+
+@group(0) @binding(1)
+var<uniform> settings: Settings;
+
+// Full-screen triangle, same as in the main shader
+@vertex
+fn main_vs(@builtin(vertex_index) id: u32) -> @builtin(position) vec4<f32> {
+    let uv = vec2<f32>(f32((id << 1u) & 2u), f32(id & 2u));
+
+    return vec4<f32>(uv.x * 2.0 - 1.0, 1.0 - uv.y * 2.0, 0.0, 1.0);
+}
+
+fn fetch(pos: vec2<i32>) -> vec4<f32> {
+    let size = vec2<i32>(textureDimensions(frame));
+
+    return textureLoad(frame, clamp(pos, vec2<i32>(0), size - 1), 0);
+}
+
+// Reads the frame at given (fractional) position, interpolating between the
+// pixels - the texture isn't filterable, so it's done by hand
+fn sample(pos: vec2<f32>) -> vec4<f32> {
+    let pos = pos - 0.5;
+    let p0 = vec2<i32>(floor(pos));
+    let t = fract(pos);
+
+    let top = mix(fetch(p0), fetch(p0 + vec2<i32>(1, 0)), t.x);
+    let bottom = mix(fetch(p0 + vec2<i32>(0, 1)), fetch(p0 + vec2<i32>(1, 1)), t.x);
+
+    return mix(top, bottom, t.y);
+}
+
+// Same as `sample()`, but clamped into `0..1` - anti-aliasing works on the
+// image as it's going to be displayed
+fn sample_ldr(pos: vec2<f32>) -> vec3<f32> {
+    return clamp(sample(pos).rgb, vec3<f32>(0.0), vec3<f32>(1.0));
+}
+
+// Perceived brightness of given color - roughly gamma-corrected, so that
+// edges in the shadows are found as eagerly as the ones in highlights
+fn luma(color: vec3<f32>) -> f32 {
+    return sqrt(dot(color, vec3<f32>(0.2126, 0.7152, 0.0722)));
+}
+
+// FXAA (Fast Approximate Anti-Aliasing) - finds the direction of the edge
+// running through given pixel from the brightness of its diagonal neighbours
+// and blurs the pixel along that edge; see Timothy Lottes' "FXAA" whitepaper.
+fn fxaa(pos: vec2<f32>) -> vec3<f32> {
+    // How far (in pixels) the blur can reach along the edge
+    let SPAN_MAX = 8.0;
+
+    // Keep the direction from blowing up on faint edges
+    let REDUCE_MUL = 1.0 / 8.0;
+    let REDUCE_MIN = 1.0 / 128.0;
+
+    let nw = luma(sample_ldr(pos + vec2<f32>(-1.0, -1.0)));
+    let ne = luma(sample_ldr(pos + vec2<f32>(1.0, -1.0)));
+    let sw = luma(sample_ldr(pos + vec2<f32>(-1.0, 1.0)));
+    let se = luma(sample_ldr(pos + vec2<f32>(1.0, 1.0)));
+    let center = sample_ldr(pos);
+    let m = luma(center);
+
+    let luma_min = min(m, min(min(nw, ne), min(sw, se)));
+    let luma_max = max(m, max(max(nw, ne), max(sw, se)));
+
+    // (perpendicular to the gradient, i.e. along the edge)
+    var dir = vec2<f32>(-((nw + ne) - (sw + se)), (nw + sw) - (ne + se));
+
+    let reduce = max((nw + ne + sw + se) * 0.25 * REDUCE_MUL, REDUCE_MIN);
+    let scale = 1.0 / (min(abs(dir.x), abs(dir.y)) + reduce);
+
+    dir = clamp(dir * scale, vec2<f32>(-SPAN_MAX), vec2<f32>(SPAN_MAX));
+
+    // Two taps close to the pixel and - if they don't overshoot the local
+    // contrast, which would mean we've left the edge - two more farther away
+    let near = 0.5 * (
+        sample_ldr(pos + dir * (1.0 / 3.0 - 0.5))
+        + sample_ldr(pos + dir * (2.0 / 3.0 - 0.5))
+    );
+
+    let far = near * 0.5 + 0.25 * (
+        sample_ldr(pos - dir * 0.5)
+        + sample_ldr(pos + dir * 0.5)
+    );
+
+    let far_luma = luma(far);
+
+    if far_luma < luma_min || far_luma > luma_max {
+        return near;
+    }
+
+    return far;
+}
+
+// Returns a pseudo-random number in `0..1` for given pixel and frame
+fn random(pixel: vec2<u32>, frame: u32) -> f32 {
+    var hash = pixel.x * 0x8da6b343u ^ pixel.y * 0xd8163841u ^ frame * 0xcb1ab31fu;
+
+    hash ^= hash >> 16u;
+    hash *= 0x7feb352du;
+    hash ^= hash >> 15u;
+    hash *= 0x846ca68bu;
+    hash ^= hash >> 16u;
+
+    return f32(hash >> 8u) / 16777216.0;
+}
+
+@fragment
+fn main_fs(@builtin(position) pos: vec4<f32>) -> @location(0) vec4<f32> {
+    let size = vec2<f32>(textureDimensions(frame));
+    let center = pos.xy;
+
+    // Chromatic aberration - red and blue get pushed apart along the direction
+    // from the screen's center, the more the farther from it
+    let offset = (center - size * 0.5) * settings.aberration;
+
+    let pixel = fetch(vec2<i32>(center));
+
+    var color = pixel.rgb;
+
+    if settings.fxaa != 0.0 {
+        color = fxaa(center);
+    }
+
+    if settings.aberration > 0.0 {
+        color.r = sample(center + offset).r;
+        color.b = sample(center - offset).b;
+    }
+
+    // Film grain - noise that's most visible in the midtones, just like in the
+    // real film
+    let noise = random(vec2<u32>(center), u32(settings.frame)) - 0.5;
+    let brightness = dot(color, vec3<f32>(0.2126, 0.7152, 0.0722));
+    let midtones = 4.0 * brightness * (1.0 - clamp(brightness, 0.0, 1.0));
+
+    color = max(color + noise * settings.grain * (0.25 + midtones), vec3<f32>(0.0));
+
+    return vec4<f32>(color, pixel.a);
+}
@@ -0,0 +1,63 @@
+//! Probe overlay (`I`) - shows values the shader has recorded for the pixel
+//! under the mouse cursor (see `Params::probe`).
+
+use sdf_playground_common::{shading, Params, Probe};
+
+/// Returns the probe's text.
+pub fn text(
+    params: &Params,
+    probe: Option<Probe>,
+) -> Vec<String> {
+    let pixel = format!(
+        "probe at {}x{}",
+        params.probe.x as u32, params.probe.y as u32
+    );
+
+    let Some(probe) = probe else {
+        return vec![
+            pixel,
+            String::new(),
+            "no data".into(),
+        ];
+    };
+
+    let vec = |values: &[f32]| {
+        values
+            .iter()
+            .map(|value| format!("{value:>7.3}"))
+            .collect::<Vec<_>>()
+            .join(" ")
+    };
+
+    let shading = match probe.shading {
+        shading::REGULAR => "regular",
+        shading::TOON => "toon",
+        shading::MATCAP => "matcap",
+        _ => "?",
+    };
+
+    let mut text = vec![
+        pixel,
+        String::new(),
+        format!("steps       {}", probe.steps),
+        format!("exhausted   {}", probe.exhausted != 0),
+    ];
+
+    if probe.distance.is_finite() {
+        text.extend([
+            format!("distance    {:.3}", probe.distance),
+            format!("normal    {}", vec(&probe.normal)),
+            format!("albedo    {}", vec(&probe.albedo)),
+            format!("shading     {shading}"),
+        ]);
+    } else {
+        text.push("distance    miss".into());
+    }
+
+    text.extend([
+        format!("color     {}", vec(&probe.color)),
+        format!("custom    {}", vec(&probe.custom)),
+    ]);
+
+    text
+}
@@ -1,91 +1,286 @@
-use pixels::wgpu;
-use sdf_playground_common::Params;
-use std::path::PathBuf;
+use crate::error::Error;
+use crate::gpu::Gpu;
+use crate::matcap::Matcap;
+use log::info;
+use sdf_playground_common::glam::{
+    uvec2, vec4, UVec2, Vec4,
+};
+use sdf_playground_common::{
+    Params, Probe, Stats, MATCAP_SIZE,
+};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Arc};
 use std::{fs, mem};
 
 #[derive(Debug)]
 pub struct Renderer {
     path: PathBuf,
-    texture_view: wgpu::TextureView,
-    bind_group: wgpu::BindGroup,
+    shader: Vec<u8>,
     pipeline: wgpu::RenderPipeline,
-    params_buffer: wgpu::Buffer,
+
+    /// Pipeline of the 2D mode (see [`Self::set_view_2d()`]), if the shader
+    /// supports it.
+    pipeline_2d: Option<wgpu::RenderPipeline>,
+    view_2d: bool,
+
+    params: Params,
+    params_buffer: ParamsBuffer,
+    bind_group_layout: wgpu::BindGroupLayout,
+
+    /// Bind groups for even and odd frames, see [`Self::history`].
+    bind_groups: [wgpu::BindGroup; 2],
+
+    stats: StatsReadback,
+
+    /// Pipeline of the coarse pre-pass (see [`Params::coarse`]), if the shader
+    /// supports it.
+    coarse: Option<CoarsePass>,
+
+    /// Texture the coarse pre-pass renders into; when the pre-pass is
+    /// disabled, it's a dummy 1x1 texture.
+    coarse_target: wgpu::TextureView,
+    coarse_size: UVec2,
+
+    /// Pipeline of the foveated rendering's pass (see [`Params::fovea`]), if
+    /// the shader supports it.
+    fovea: Option<wgpu::RenderPipeline>,
+
+    /// Texture the foveated rendering's pass renders into (see
+    /// [`Params::fovea_atlas_size()`]); when foveated rendering is disabled,
+    /// it's a dummy 1x1 texture.
+    fovea_target: wgpu::TextureView,
+    fovea_size: UVec2,
+
+    /// Copies of the previously rendered frames, used for checkerboard
+    /// rendering (see [`Params::checkerboard`]).
+    ///
+    /// Each frame reads one of them and writes into the other one, swapping
+    /// them for the next frame.
+    history: [wgpu::TextureView; 2],
+    history_textures: [wgpu::Texture; 2],
+    history_size: UVec2,
+
+    /// Whether the history has just been (re)created and so doesn't contain
+    /// any frame yet - in that case the next frame gets rendered in full,
+    /// without reusing anything from it.
+    fresh_history: bool,
+
+    /// Normals and distances of the recently rendered frames, for
+    /// post-processing (see `main_fs()` in the shader) and for reprojecting
+    /// the history when the camera moves (see [`Params::prev_camera`]);
+    /// swapped the same way as [`Self::history`].
+    aov: [wgpu::TextureView; 2],
+
+    /// Texture for the matcap shading, along with the image that's been
+    /// uploaded into it (see [`Self::set_matcap()`]).
+    matcap: wgpu::Texture,
+    matcap_image: Option<Matcap>,
 }
 
+/// Uniform buffer through which [`Params`] get delivered to the shader.
+///
+/// (push constants would be cheaper to update, but `Params` are way larger
+/// than what GPUs usually allow to push)
+///
+/// The buffer is actually a ring of [`FRAMES`] slots (selected with a dynamic
+/// offset), so that we never overwrite params that a frame still in flight
+/// might be reading from.
+#[derive(Debug)]
+struct ParamsBuffer {
+    buffer: wgpu::Buffer,
+
+    /// Distance between slots, in bytes.
+    stride: u32,
+
+    /// Offset of the slot used by the current frame, in bytes.
+    offset: u32,
+}
+
+/// How many frames can be in flight at once, i.e. how many slots there are in
+/// [`ParamsBuffer`].
+const FRAMES: u32 = 3;
+
 impl Renderer {
+    /// Creates renderer for given shader.
+    ///
+    /// Shader can be either a SPIR-V module (`.spv`) or WGSL source (`.wgsl`) -
+    /// in both cases it has to provide the same entry points as our shader
+    /// crate (`main_vs` and `main_fs`).
+    ///
+    /// Fails if the shader can't be read or is rejected by the GPU (e.g.
+    /// because its interface doesn't match what we expect), or if its
+    /// [`Params`] differ from ours (see [`check_params()`]).
     pub fn new(
-        pixels: &pixels::Pixels,
-        width: u32,
-        height: u32,
+        gpu: &Gpu,
         path: PathBuf,
-    ) -> Self {
-        let device = pixels.device();
-        let shader = fs::read(&path).unwrap();
+    ) -> Result<Self, Error> {
+        let shader =
+            fs::read(&path).map_err(Error::io(&path))?;
+
+        Self::build(gpu, path, shader)
+    }
+
+    fn build(
+        gpu: &Gpu,
+        path: PathBuf,
+        shader: Vec<u8>,
+    ) -> Result<Self, Error> {
+        check_params(&path, &shader)?;
+
+        let device = gpu.device();
+
+        device.push_error_scope(
+            wgpu::ErrorFilter::Validation,
+        );
+
+        let source =
+            if path.extension() == Some("wgsl".as_ref()) {
+                wgpu::ShaderSource::Wgsl(
+                    String::from_utf8_lossy(&shader),
+                )
+            } else {
+                wgpu::util::make_spirv(&shader)
+            };
 
         let module = device.create_shader_module(
             wgpu::ShaderModuleDescriptor {
                 label: Some("renderer_shader"),
-                source: wgpu::util::make_spirv(&shader),
+                source,
             },
         );
 
-        let texture_descriptor = wgpu::TextureDescriptor {
-            label: Some("renderer_texture_descriptor"),
-            size: wgpu::Extent3d {
-                width,
-                height,
-                depth_or_array_layers: 1,
-            },
-            mip_level_count: 1,
-            sample_count: 1,
-            dimension: wgpu::TextureDimension::D2,
-            format: pixels.render_texture_format(),
-            usage: wgpu::TextureUsages::TEXTURE_BINDING
-                | wgpu::TextureUsages::RENDER_ATTACHMENT,
-            view_formats: &[],
-        };
+        let alignment = device
+            .limits()
+            .min_uniform_buffer_offset_alignment;
 
-        let texture_view = device
-            .create_texture(&texture_descriptor)
-            .create_view(&Default::default());
+        let stride = wgpu::util::align_to(
+            mem::size_of::<Params>() as u32,
+            alignment,
+        );
 
-        let params_buffer =
-            device.create_buffer(&wgpu::BufferDescriptor {
-                label: Some("renderer_params_buffer"),
-                size: mem::size_of::<Params>()
-                    as wgpu::BufferAddress,
-                usage: wgpu::BufferUsages::UNIFORM
-                    | wgpu::BufferUsages::COPY_DST,
-                mapped_at_creation: false,
-            });
+        let params_buffer = ParamsBuffer {
+            buffer: device.create_buffer(
+                &wgpu::BufferDescriptor {
+                    label: Some("renderer_params_buffer"),
+                    size: (stride * FRAMES)
+                        as wgpu::BufferAddress,
+                    usage: wgpu::BufferUsages::UNIFORM
+                        | wgpu::BufferUsages::COPY_DST,
+                    mapped_at_creation: false,
+                },
+            ),
+            stride,
+            offset: 0,
+        };
 
-        let bind_group_layout =
-            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
-                label: Some("renderer_bind_group_layout"),
-                entries: &[
-                    wgpu::BindGroupLayoutEntry {
-                        binding: 0,
-                        visibility: wgpu::ShaderStages::FRAGMENT,
-                        ty: wgpu::BindingType::Buffer {
-                            ty: wgpu::BufferBindingType::Uniform,
-                            has_dynamic_offset: false,
-                            min_binding_size: None,
-                        },
-                        count: None,
-                    },
-                ],
-            });
+        let stats = StatsReadback::new(device);
 
-        let bind_group = device.create_bind_group(
-            &wgpu::BindGroupDescriptor {
-                label: Some("renderer_bind_group"),
-                layout: &bind_group_layout,
-                entries: &[wgpu::BindGroupEntry {
-                    binding: 0,
-                    resource: params_buffer
-                        .as_entire_binding(),
-                }],
+        let layout_entries = [
+            params_layout_entry(),
+            wgpu::BindGroupLayoutEntry {
+                binding: 1,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Storage {
+                        read_only: false,
+                    },
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
             },
+            wgpu::BindGroupLayoutEntry {
+                binding: 2,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Texture {
+                    sample_type:
+                        wgpu::TextureSampleType::Float {
+                            filterable: false,
+                        },
+                    view_dimension:
+                        wgpu::TextureViewDimension::D2,
+                    multisampled: false,
+                },
+                count: None,
+            },
+            wgpu::BindGroupLayoutEntry {
+                binding: 3,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Texture {
+                    sample_type:
+                        wgpu::TextureSampleType::Float {
+                            filterable: false,
+                        },
+                    view_dimension:
+                        wgpu::TextureViewDimension::D2,
+                    multisampled: false,
+                },
+                count: None,
+            },
+            wgpu::BindGroupLayoutEntry {
+                binding: 4,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Texture {
+                    sample_type:
+                        wgpu::TextureSampleType::Float {
+                            filterable: false,
+                        },
+                    view_dimension:
+                        wgpu::TextureViewDimension::D2,
+                    multisampled: false,
+                },
+                count: None,
+            },
+            wgpu::BindGroupLayoutEntry {
+                binding: 5,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Texture {
+                    sample_type:
+                        wgpu::TextureSampleType::Float {
+                            filterable: false,
+                        },
+                    view_dimension:
+                        wgpu::TextureViewDimension::D2,
+                    multisampled: false,
+                },
+                count: None,
+            },
+        ];
+
+        let bind_group_layout = device
+            .create_bind_group_layout(
+                &wgpu::BindGroupLayoutDescriptor {
+                    label: Some(
+                        "renderer_bind_group_layout",
+                    ),
+                    entries: &layout_entries,
+                },
+            );
+
+        let coarse_size = UVec2::ONE;
+        let coarse_target =
+            create_coarse_target(device, coarse_size);
+
+        let fovea_size = UVec2::ONE;
+        let fovea_target =
+            create_fovea_target(device, fovea_size);
+
+        let history_size = UVec2::ONE;
+        let (history_textures, history) =
+            create_history(device, history_size);
+        let aov = create_aov(device, history_size);
+        let matcap = create_matcap(device);
+
+        let bind_groups = create_bind_groups(
+            device,
+            &bind_group_layout,
+            &params_buffer,
+            &stats,
+            &coarse_target,
+            &history,
+            &aov,
+            &matcap,
         );
 
         let pipeline_layout = device
@@ -99,9 +294,99 @@ impl Renderer {
                 },
             );
 
-        let pipeline = device.create_render_pipeline(
+        // Blends using the opacity passed to `render()`
+        let blend = wgpu::BlendState {
+            color: wgpu::BlendComponent {
+                src_factor: wgpu::BlendFactor::Constant,
+                dst_factor:
+                    wgpu::BlendFactor::OneMinusConstant,
+                operation: wgpu::BlendOperation::Add,
+            },
+            alpha: wgpu::BlendComponent::REPLACE,
+        };
+
+        let create_pipeline = |label, entry_point| {
+            device.create_render_pipeline(
+                &wgpu::RenderPipelineDescriptor {
+                    label: Some(label),
+                    layout: Some(&pipeline_layout),
+                    vertex: wgpu::VertexState {
+                        module: &module,
+                        entry_point: "main_vs",
+                        buffers: &[],
+                    },
+                    primitive:
+                        wgpu::PrimitiveState::default(),
+                    depth_stencil: None,
+                    multisample:
+                        wgpu::MultisampleState::default(),
+                    fragment: Some(wgpu::FragmentState {
+                        module: &module,
+                        entry_point,
+                        targets: &[
+                            Some(wgpu::ColorTargetState {
+                                format: gpu.format(),
+                                blend: Some(blend),
+                                write_mask:
+                                    wgpu::ColorWrites::ALL,
+                            }),
+                            Some(wgpu::ColorTargetState {
+                                format: HISTORY_FORMAT,
+                                blend: Some(blend),
+                                write_mask:
+                                    wgpu::ColorWrites::ALL,
+                            }),
+                            Some(wgpu::ColorTargetState {
+                                format: AOV_FORMAT,
+                                blend: None,
+                                write_mask:
+                                    wgpu::ColorWrites::ALL,
+                            }),
+                        ],
+                    }),
+                    multiview: None,
+                },
+            )
+        };
+
+        let pipeline =
+            create_pipeline("renderer_pipeline", "main_fs");
+
+        if let Some(err) =
+            pollster::block_on(device.pop_error_scope())
+        {
+            return Err(err.into());
+        }
+
+        // 2D mode is optional, since shaders other than ours (e.g. drag &
+        // dropped WGSL ones) usually don't support it
+        device.push_error_scope(
+            wgpu::ErrorFilter::Validation,
+        );
+
+        let pipeline_2d = create_pipeline(
+            "renderer_2d_pipeline",
+            "main_fs_2d",
+        );
+
+        let pipeline_2d = match pollster::block_on(
+            device.pop_error_scope(),
+        ) {
+            Some(err) => {
+                info!("2D mode not supported: {err}");
+                None
+            }
+            None => Some(pipeline_2d),
+        };
+
+        // Same goes for foveated rendering
+        device.push_error_scope(
+            wgpu::ErrorFilter::Validation,
+        );
+
+        let fovea = device.create_render_pipeline(
             &wgpu::RenderPipelineDescriptor {
-                label: Some("renderer_pipeline"),
+                label: Some("renderer_fovea_pipeline"),
                 layout: Some(&pipeline_layout),
                 vertex: wgpu::VertexState {
                     module: &module,
@@ -114,14 +399,11 @@ impl Renderer {
                     wgpu::MultisampleState::default(),
                 fragment: Some(wgpu::FragmentState {
                     module: &module,
-                    entry_point: "main_fs",
+                    entry_point: "main_fovea",
                     targets: &[Some(
                         wgpu::ColorTargetState {
-                            format: pixels
-                                .render_texture_format(),
-                            blend: Some(
-                                wgpu::BlendState::REPLACE,
-                            ),
+                            format: HISTORY_FORMAT,
+                            blend: None,
                             write_mask:
                                 wgpu::ColorWrites::ALL,
                         },
@@ -131,53 +413,631 @@ impl Renderer {
             },
         );
 
-        Self {
+        let fovea = match pollster::block_on(
+            device.pop_error_scope(),
+        ) {
+            Some(err) => {
+                info!(
+                    "Foveated rendering not supported: {err}"
+                );
+                None
+            }
+            None => Some(fovea),
+        };
+
+        let coarse =
+            CoarsePass::new(gpu, &module, &params_buffer);
+
+        Ok(Self {
             path,
-            texture_view,
-            bind_group,
+            shader,
             pipeline,
+            pipeline_2d,
+            view_2d: false,
+            params: Default::default(),
             params_buffer,
+            bind_group_layout,
+            bind_groups,
+            stats,
+            coarse,
+            coarse_target,
+            coarse_size,
+            fovea,
+            fovea_target,
+            fovea_size,
+            history,
+            history_textures,
+            history_size,
+            fresh_history: true,
+            aov,
+            matcap,
+            matcap_image: None,
+        })
+    }
+
+    /// Returns whether given file looks like something [`Self::new()`] can
+    /// load.
+    pub fn supports(path: &Path) -> bool {
+        matches!(
+            path.extension().and_then(|ext| ext.to_str()),
+            Some("spv" | "wgsl")
+        )
+    }
+
+    /// Creates another renderer for the same shader - used to render it into
+    /// another window, since each renderer's targets match a single window.
+    ///
+    /// Fails if the shader got rejected this time (e.g. because `gpu` is not
+    /// the same GPU that accepted it before).
+    pub fn duplicate(
+        &self,
+        gpu: &Gpu,
+    ) -> Result<Self, Error> {
+        let mut renderer = Self::build(
+            gpu,
+            self.path.clone(),
+            self.shader.clone(),
+        )?;
+
+        if let Some(matcap) = &self.matcap_image {
+            renderer.set_matcap(gpu, matcap);
         }
+
+        Ok(renderer)
     }
 
-    pub fn texture_view(&self) -> &wgpu::TextureView {
-        &self.texture_view
+    /// Recreates the pipeline on given GPU - used after the previous GPU has
+    /// been lost; on failure, the renderer is left as it was.
+    pub fn recreate(
+        &mut self,
+        gpu: &Gpu,
+    ) -> Result<(), Error> {
+        *self = self.duplicate(gpu)?;
+
+        Ok(())
     }
 
-    pub fn resize(
+    /// Uploads params for the next frame.
+    ///
+    /// Each call moves to the next slot of the uniform buffer, so it should be
+    /// called once per frame, before [`Self::render()`].
+    pub fn update(&mut self, gpu: &Gpu, params: &Params) {
+        self.stats.poll(gpu.device());
+
+        let coarse_size = if params.coarse_enabled() {
+            params.coarse_size()
+        } else {
+            UVec2::ONE
+        };
+
+        let fovea_size = if params.fovea_enabled() {
+            params.fovea_atlas_size()
+        } else {
+            UVec2::ONE
+        };
+
+        // (the atlas isn't a part of any bind group, so it can be replaced
+        // right away)
+        if fovea_size != self.fovea_size {
+            self.fovea_size = fovea_size;
+
+            self.fovea_target = create_fovea_target(
+                gpu.device(),
+                fovea_size,
+            );
+        }
+
+        let history_size =
+            uvec2(params.width, params.height)
+                .max(UVec2::ONE);
+
+        if coarse_size != self.coarse_size
+            || history_size != self.history_size
+        {
+            if coarse_size != self.coarse_size {
+                self.coarse_size = coarse_size;
+
+                self.coarse_target = create_coarse_target(
+                    gpu.device(),
+                    coarse_size,
+                );
+            }
+
+            if history_size != self.history_size {
+                self.history_size = history_size;
+
+                (self.history_textures, self.history) =
+                    create_history(
+                        gpu.device(),
+                        history_size,
+                    );
+
+                self.aov =
+                    create_aov(gpu.device(), history_size);
+
+                self.fresh_history = true;
+            }
+
+            self.bind_groups = create_bind_groups(
+                gpu.device(),
+                &self.bind_group_layout,
+                &self.params_buffer,
+                &self.stats,
+                &self.coarse_target,
+                &self.history,
+                &self.aov,
+                &self.matcap,
+            );
+        }
+
+        self.params = *params;
+
+        // A freshly created history contains just zeros, so there's nothing
+        // that checkerboard rendering, region of interest or reprojection
+        // could reuse from it
+        if mem::take(&mut self.fresh_history) {
+            self.params.checkerboard = 0;
+            self.params.roi = Vec4::ZERO;
+            self.params.prev_camera.y = 0.0;
+        }
+
+        let ParamsBuffer {
+            buffer,
+            stride,
+            offset,
+        } = &mut self.params_buffer;
+
+        *offset = (*offset + *stride) % (*stride * FRAMES);
+
+        gpu.queue().write_buffer(
+            buffer,
+            *offset as wgpu::BufferAddress,
+            bytemuck::bytes_of(&self.params),
+        );
+    }
+
+    /// Renders the scene into given target.
+    ///
+    /// When `opacity` is below 1.0, the scene is blended over whatever the
+    /// target already contains (used for crossfading between shaders); when
+    /// `scissor` (`[x, y, width, height]`) is given, only that part of the
+    /// target gets drawn over (used for split-screen). Otherwise the target is
+    /// overwritten.
+    pub fn render(
         &mut self,
-        pixels: &pixels::Pixels,
-        width: u32,
-        height: u32,
+        encoder: &mut wgpu::CommandEncoder,
+        target: &wgpu::TextureView,
+        opacity: f32,
+        scissor: Option<[u32; 4]>,
     ) {
-        *self = Self::new(
-            pixels,
-            width,
-            height,
-            mem::take(&mut self.path),
+        let load = if opacity < 1.0 || scissor.is_some() {
+            wgpu::LoadOp::Load
+        } else {
+            wgpu::LoadOp::Clear(wgpu::Color::BLACK)
+        };
+
+        encoder.clear_buffer(&self.stats.buffer, 0, None);
+
+        if let Some(coarse) = &self.coarse {
+            if self.params.coarse_enabled() {
+                coarse.render(
+                    encoder,
+                    &self.coarse_target,
+                    &self.params_buffer,
+                );
+            }
+        }
+
+        // Each frame reads the history written by the previous one
+        let frame = (self.params.frame % 2) as usize;
+        let bind_group = &self.bind_groups[frame];
+
+        if let Some(fovea) = self.fovea_pipeline() {
+            let mut pass = encoder.begin_render_pass(
+                &wgpu::RenderPassDescriptor {
+                    label: Some(
+                        "renderer_fovea_render_pass",
+                    ),
+                    color_attachments: &[Some(
+                        wgpu::RenderPassColorAttachment {
+                            view: &self.fovea_target,
+                            resolve_target: None,
+                            ops: wgpu::Operations {
+                                load: wgpu::LoadOp::Clear(
+                                    wgpu::Color::BLACK,
+                                ),
+                                store: true,
+                            },
+                        },
+                    )],
+                    depth_stencil_attachment: None,
+                },
+            );
+
+            pass.set_pipeline(fovea);
+            self.bind(&mut pass, bind_group);
+            pass.draw(0..3, 0..1);
+        }
+
+        let mut pass = encoder.begin_render_pass(
+            &wgpu::RenderPassDescriptor {
+                label: Some("renderer_render_pass"),
+                color_attachments: &[
+                    Some(wgpu::RenderPassColorAttachment {
+                        view: target,
+                        resolve_target: None,
+                        ops: wgpu::Operations {
+                            load,
+                            store: true,
+                        },
+                    }),
+                    Some(wgpu::RenderPassColorAttachment {
+                        view: &self.history[1 - frame],
+                        resolve_target: None,
+                        ops: wgpu::Operations {
+                            load,
+                            store: true,
+                        },
+                    }),
+                    Some(wgpu::RenderPassColorAttachment {
+                        view: &self.aov[1 - frame],
+                        resolve_target: None,
+                        ops: wgpu::Operations {
+                            load,
+                            store: true,
+                        },
+                    }),
+                ],
+                depth_stencil_attachment: None,
+            },
         );
+
+        match &self.pipeline_2d {
+            Some(pipeline_2d) if self.view_2d => {
+                pass.set_pipeline(pipeline_2d);
+            }
+            _ => {
+                pass.set_pipeline(&self.pipeline);
+            }
+        }
+
+        if let Some([x, y, width, height]) = scissor {
+            pass.set_scissor_rect(x, y, width, height);
+        }
+
+        pass.set_blend_constant(wgpu::Color {
+            r: opacity as f64,
+            g: opacity as f64,
+            b: opacity as f64,
+            a: opacity as f64,
+        });
+
+        self.bind(&mut pass, bind_group);
+        pass.draw(0..3, 0..1);
+        drop(pass);
+
+        self.stats.copy(encoder);
     }
 
-    pub fn update(
-        &self,
-        queue: &wgpu::Queue,
-        params: &Params,
+    /// Binds params and given bind group for the main pipeline (or for one
+    /// sharing its layout).
+    fn bind<'a>(
+        &'a self,
+        pass: &mut wgpu::RenderPass<'a>,
+        bind_group: &'a wgpu::BindGroup,
     ) {
-        queue.write_buffer(
-            &self.params_buffer,
+        pass.set_bind_group(
             0,
-            bytemuck::bytes_of(params),
+            bind_group,
+            &[self.params_buffer.offset],
         );
     }
 
-    pub fn render(
+    /// Returns the foveated rendering's pipeline, if the current frame should
+    /// be rendered with it.
+    fn fovea_pipeline(
+        &self,
+    ) -> Option<&wgpu::RenderPipeline> {
+        if self.view_2d || !self.params.fovea_enabled() {
+            return None;
+        }
+
+        self.fovea.as_ref()
+    }
+
+    /// Switches between rendering the scene and the 2D mode, which shows a 2D
+    /// SDF from the top (see `main_fs_2d()` in the shader).
+    pub fn set_view_2d(&mut self, view_2d: bool) {
+        self.view_2d = view_2d;
+    }
+
+    /// Switches to given matcap image; cheap to call each frame, since the
+    /// image gets uploaded only when it changes.
+    pub fn set_matcap(
+        &mut self,
+        gpu: &Gpu,
+        matcap: &Matcap,
+    ) {
+        if let Some(current) = &self.matcap_image {
+            if current.same(matcap) {
+                return;
+            }
+        }
+
+        gpu.queue().write_texture(
+            self.matcap.as_image_copy(),
+            matcap.pixels(),
+            wgpu::ImageDataLayout {
+                offset: 0,
+                bytes_per_row: Some(4 * MATCAP_SIZE),
+                rows_per_image: Some(MATCAP_SIZE),
+            },
+            self.matcap.size(),
+        );
+
+        self.matcap_image = Some(matcap.clone());
+    }
+
+    /// Returns the texture containing the most recently rendered frame, in
+    /// linear colors.
+    pub fn output(&self) -> &wgpu::TextureView {
+        &self.history[1 - (self.params.frame % 2) as usize]
+    }
+
+    /// Downloads the most recently rendered frame (see [`Self::output()`]),
+    /// returning its size and pixels - row by row, in linear colors.
+    ///
+    /// This waits for the GPU to finish everything it's been given, so it's
+    /// meant for one-off captures rather than for doing it every frame.
+    pub fn read_output(
+        &self,
+        gpu: &Gpu,
+    ) -> Option<(UVec2, Vec<Vec4>)> {
+        const PIXEL_SIZE: u32 = 8;
+
+        let texture = &self.history_textures
+            [1 - (self.params.frame % 2) as usize];
+
+        let size = self.history_size;
+
+        // Rows of the buffer have to be aligned, so there's some padding at
+        // the end of each one
+        let align = wgpu::COPY_BYTES_PER_ROW_ALIGNMENT;
+        let row_size = (size.x * PIXEL_SIZE + align - 1)
+            / align
+            * align;
+
+        let buffer = gpu.device().create_buffer(
+            &wgpu::BufferDescriptor {
+                label: Some("renderer_output_readback"),
+                size: (row_size * size.y)
+                    as wgpu::BufferAddress,
+                usage: wgpu::BufferUsages::MAP_READ
+                    | wgpu::BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            },
+        );
+
+        let mut encoder =
+            gpu.device().create_command_encoder(
+                &wgpu::CommandEncoderDescriptor {
+                    label: Some(
+                        "renderer_output_readback_encoder",
+                    ),
+                },
+            );
+
+        encoder.copy_texture_to_buffer(
+            texture.as_image_copy(),
+            wgpu::ImageCopyBuffer {
+                buffer: &buffer,
+                layout: wgpu::ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: Some(row_size),
+                    rows_per_image: Some(size.y),
+                },
+            },
+            texture.size(),
+        );
+
+        gpu.queue().submit(Some(encoder.finish()));
+
+        let (tx, rx) = mpsc::channel();
+
+        buffer.slice(..).map_async(
+            wgpu::MapMode::Read,
+            move |result| {
+                _ = tx.send(result);
+            },
+        );
+
+        gpu.device().poll(wgpu::Maintain::Wait);
+        rx.recv().ok()?.ok()?;
+
+        let bytes = buffer.slice(..).get_mapped_range();
+
+        let pixels = bytes
+            .chunks_exact(row_size as usize)
+            .flat_map(|row| {
+                row[..(size.x * PIXEL_SIZE) as usize]
+                    .chunks_exact(PIXEL_SIZE as usize)
+            })
+            .map(|pixel| {
+                let channel = |idx: usize| {
+                    f16_to_f32(u16::from_le_bytes([
+                        pixel[2 * idx],
+                        pixel[2 * idx + 1],
+                    ]))
+                };
+
+                vec4(
+                    channel(0),
+                    channel(1),
+                    channel(2),
+                    channel(3),
+                )
+            })
+            .collect();
+
+        Some((size, pixels))
+    }
+
+    /// Returns the texture containing normals (`xyz`) and distances (`w`) of
+    /// the most recently rendered frame; pixels that weren't rendered (e.g.
+    /// due to checkerboard rendering) have a negative distance.
+    pub fn aov(&self) -> &wgpu::TextureView {
+        &self.aov[1 - (self.params.frame % 2) as usize]
+    }
+
+    /// Returns the texture containing normals and distances of the frame
+    /// rendered before the most recent one (see [`Self::aov()`]).
+    pub fn prev_aov(&self) -> &wgpu::TextureView {
+        &self.aov[(self.params.frame % 2) as usize]
+    }
+
+    /// Returns the texture containing the lower-resolution parts of the most
+    /// recently rendered frame (see [`Params::fovea_atlas_size()`]) - or
+    /// `None` if it's been rendered without foveated rendering.
+    pub fn fovea(&self) -> Option<&wgpu::TextureView> {
+        self.fovea_pipeline().map(|_| &self.fovea_target)
+    }
+
+    /// Returns the texture containing distances found by the coarse pre-pass
+    /// (see [`Params::coarse`]); when the pre-pass is disabled, it's a dummy
+    /// 1x1 texture.
+    pub fn coarse(&self) -> &wgpu::TextureView {
+        &self.coarse_target
+    }
+
+    /// Returns how many pixels ran out of steps in `march()` during one of the
+    /// recent frames (statistics arrive with a couple of frames of delay, since
+    /// we don't want to stall the GPU waiting for them).
+    ///
+    /// That's the classic symptom of a broken (non-Lipschitz) SDF or of a scene
+    /// that's too large for the step budget.
+    pub fn exhausted(&self) -> Option<u32> {
+        self.stats.stats.map(|stats| stats.exhausted)
+    }
+
+    /// Returns statistics of one of the recent frames (see [`Stats`]).
+    pub fn stats(&self) -> Option<Stats> {
+        self.stats.stats
+    }
+
+    /// Returns values recorded for the pixel pointed at by [`Params::probe`]
+    /// during one of the recent frames.
+    pub fn probe(&self) -> Option<Probe> {
+        self.stats.probe
+    }
+}
+
+/// Pipeline marching the scene at a lower resolution, see [`Params::coarse`].
+#[derive(Debug)]
+struct CoarsePass {
+    pipeline: wgpu::RenderPipeline,
+
+    /// Bind group with params.
+    bind_group: wgpu::BindGroup,
+}
+
+impl CoarsePass {
+    /// Creates the pre-pass for given shader; returns `None` if the shader
+    /// doesn't support it (e.g. drag & dropped WGSL shaders usually don't).
+    fn new(
+        gpu: &Gpu,
+        module: &wgpu::ShaderModule,
+        params_buffer: &ParamsBuffer,
+    ) -> Option<Self> {
+        let device = gpu.device();
+
+        device.push_error_scope(
+            wgpu::ErrorFilter::Validation,
+        );
+
+        let bind_group_layout = device
+            .create_bind_group_layout(
+                &wgpu::BindGroupLayoutDescriptor {
+                    label: Some(
+                        "renderer_coarse_bind_group_layout",
+                    ),
+                    entries: &[params_layout_entry()],
+                },
+            );
+
+        let bind_group = device.create_bind_group(
+            &wgpu::BindGroupDescriptor {
+                label: Some("renderer_coarse_bind_group"),
+                layout: &bind_group_layout,
+                entries: &[params_entry(
+                    &params_buffer.buffer,
+                )],
+            },
+        );
+
+        let pipeline_layout = device
+            .create_pipeline_layout(
+                &wgpu::PipelineLayoutDescriptor {
+                    label: Some(
+                        "renderer_coarse_pipeline_layout",
+                    ),
+                    bind_group_layouts: &[
+                        &bind_group_layout,
+                    ],
+                    push_constant_ranges: &[],
+                },
+            );
+
+        let pipeline = device.create_render_pipeline(
+            &wgpu::RenderPipelineDescriptor {
+                label: Some("renderer_coarse_pipeline"),
+                layout: Some(&pipeline_layout),
+                vertex: wgpu::VertexState {
+                    module,
+                    entry_point: "main_vs",
+                    buffers: &[],
+                },
+                primitive: wgpu::PrimitiveState::default(),
+                depth_stencil: None,
+                multisample:
+                    wgpu::MultisampleState::default(),
+                fragment: Some(wgpu::FragmentState {
+                    module,
+                    entry_point: "main_coarse",
+                    targets: &[Some(
+                        wgpu::ColorTargetState {
+                            format: COARSE_FORMAT,
+                            blend: None,
+                            write_mask:
+                                wgpu::ColorWrites::ALL,
+                        },
+                    )],
+                }),
+                multiview: None,
+            },
+        );
+
+        if let Some(err) =
+            pollster::block_on(device.pop_error_scope())
+        {
+            info!("Coarse pre-pass not supported: {err}");
+            return None;
+        }
+
+        Some(Self {
+            pipeline,
+            bind_group,
+        })
+    }
+
+    fn render(
         &self,
         encoder: &mut wgpu::CommandEncoder,
         target: &wgpu::TextureView,
+        params_buffer: &ParamsBuffer,
     ) {
         let mut pass = encoder.begin_render_pass(
             &wgpu::RenderPassDescriptor {
-                label: Some("renderer_render_pass"),
+                label: Some("renderer_coarse_render_pass"),
                 color_attachments: &[Some(
                     wgpu::RenderPassColorAttachment {
                         view: target,
@@ -195,7 +1055,467 @@ impl Renderer {
         );
 
         pass.set_pipeline(&self.pipeline);
-        pass.set_bind_group(0, &self.bind_group, &[]);
+
+        pass.set_bind_group(
+            0,
+            &self.bind_group,
+            &[params_buffer.offset],
+        );
+
         pass.draw(0..3, 0..1);
     }
 }
+
+/// Format of the coarse pre-pass' target - each texel contains how far the ray
+/// could go before getting close to any surface.
+const COARSE_FORMAT: wgpu::TextureFormat =
+    wgpu::TextureFormat::R32Float;
+
+fn create_coarse_target(
+    device: &wgpu::Device,
+    size: UVec2,
+) -> wgpu::TextureView {
+    device
+        .create_texture(&wgpu::TextureDescriptor {
+            label: Some("renderer_coarse_target"),
+            size: wgpu::Extent3d {
+                width: size.x,
+                height: size.y,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: COARSE_FORMAT,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT
+                | wgpu::TextureUsages::TEXTURE_BINDING,
+            view_formats: &[],
+        })
+        .create_view(&Default::default())
+}
+
+fn create_fovea_target(
+    device: &wgpu::Device,
+    size: UVec2,
+) -> wgpu::TextureView {
+    device
+        .create_texture(&wgpu::TextureDescriptor {
+            label: Some("renderer_fovea_target"),
+            size: wgpu::Extent3d {
+                width: size.x,
+                height: size.y,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: HISTORY_FORMAT,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT
+                | wgpu::TextureUsages::TEXTURE_BINDING,
+            view_formats: &[],
+        })
+        .create_view(&Default::default())
+}
+
+/// Format of [`Renderer::history`] - floating-point, so that reusing pixels
+/// doesn't lose any precision.
+pub const HISTORY_FORMAT: wgpu::TextureFormat =
+    wgpu::TextureFormat::Rgba16Float;
+
+fn create_history(
+    device: &wgpu::Device,
+    size: UVec2,
+) -> ([wgpu::Texture; 2], [wgpu::TextureView; 2]) {
+    let textures = [(); 2].map(|_| {
+        device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("renderer_history"),
+            size: wgpu::Extent3d {
+                width: size.x,
+                height: size.y,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: HISTORY_FORMAT,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT
+                | wgpu::TextureUsages::TEXTURE_BINDING
+                | wgpu::TextureUsages::COPY_SRC,
+            view_formats: &[],
+        })
+    });
+
+    let views = [0, 1].map(|idx| {
+        textures[idx].create_view(&Default::default())
+    });
+
+    (textures, views)
+}
+
+/// Converts a half-precision float (as stored in [`HISTORY_FORMAT`]) into a
+/// regular one.
+fn f16_to_f32(bits: u16) -> f32 {
+    let sign = if bits & 0x8000 == 0 { 1.0 } else { -1.0 };
+    let exponent = (bits >> 10) & 0x1f;
+    let mantissa = (bits & 0x3ff) as f32 / 1024.0;
+
+    sign * match exponent {
+        0 => mantissa * 2f32.powi(-14),
+        0x1f if mantissa == 0.0 => f32::INFINITY,
+        0x1f => f32::NAN,
+        _ => {
+            (1.0 + mantissa)
+                * 2f32.powi(exponent as i32 - 15)
+        }
+    }
+}
+
+/// Format of [`Renderer::aov`].
+const AOV_FORMAT: wgpu::TextureFormat =
+    wgpu::TextureFormat::Rgba16Float;
+
+fn create_aov(
+    device: &wgpu::Device,
+    size: UVec2,
+) -> [wgpu::TextureView; 2] {
+    [(); 2].map(|_| {
+        device
+            .create_texture(&wgpu::TextureDescriptor {
+                label: Some("renderer_aov"),
+                size: wgpu::Extent3d {
+                    width: size.x,
+                    height: size.y,
+                    depth_or_array_layers: 1,
+                },
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: wgpu::TextureDimension::D2,
+                format: AOV_FORMAT,
+                usage: wgpu::TextureUsages::RENDER_ATTACHMENT
+                    | wgpu::TextureUsages::TEXTURE_BINDING,
+                view_formats: &[],
+            })
+            .create_view(&Default::default())
+    })
+}
+
+/// Format of [`Renderer::matcap`] - sRGB, so that the shader gets linear
+/// colors.
+const MATCAP_FORMAT: wgpu::TextureFormat =
+    wgpu::TextureFormat::Rgba8UnormSrgb;
+
+fn create_matcap(device: &wgpu::Device) -> wgpu::Texture {
+    device.create_texture(&wgpu::TextureDescriptor {
+        label: Some("renderer_matcap"),
+        size: wgpu::Extent3d {
+            width: MATCAP_SIZE,
+            height: MATCAP_SIZE,
+            depth_or_array_layers: 1,
+        },
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        format: MATCAP_FORMAT,
+        usage: wgpu::TextureUsages::TEXTURE_BINDING
+            | wgpu::TextureUsages::COPY_DST,
+        view_formats: &[],
+    })
+}
+
+/// Creates bind groups for even and odd frames - they differ only in which
+/// history and AOV textures get read.
+#[allow(clippy::too_many_arguments)]
+fn create_bind_groups(
+    device: &wgpu::Device,
+    layout: &wgpu::BindGroupLayout,
+    params_buffer: &ParamsBuffer,
+    stats: &StatsReadback,
+    coarse_target: &wgpu::TextureView,
+    history: &[wgpu::TextureView; 2],
+    aov: &[wgpu::TextureView; 2],
+    matcap: &wgpu::Texture,
+) -> [wgpu::BindGroup; 2] {
+    let matcap = matcap.create_view(&Default::default());
+
+    [0, 1].map(|frame| {
+        let entries = [
+            params_entry(&params_buffer.buffer),
+            wgpu::BindGroupEntry {
+                binding: 1,
+                resource: stats.buffer.as_entire_binding(),
+            },
+            wgpu::BindGroupEntry {
+                binding: 2,
+                resource:
+                    wgpu::BindingResource::TextureView(
+                        coarse_target,
+                    ),
+            },
+            wgpu::BindGroupEntry {
+                binding: 3,
+                resource:
+                    wgpu::BindingResource::TextureView(
+                        &history[frame],
+                    ),
+            },
+            wgpu::BindGroupEntry {
+                binding: 4,
+                resource:
+                    wgpu::BindingResource::TextureView(
+                        &matcap,
+                    ),
+            },
+            wgpu::BindGroupEntry {
+                binding: 5,
+                resource:
+                    wgpu::BindingResource::TextureView(
+                        &aov[frame],
+                    ),
+            },
+        ];
+
+        device.create_bind_group(
+            &wgpu::BindGroupDescriptor {
+                label: Some("renderer_bind_group"),
+                layout,
+                entries: &entries,
+            },
+        )
+    })
+}
+
+/// Checks whether the SPIR-V shader's [`Params`] have the same size as ours.
+///
+/// The shader gets hot-reloaded, but the application doesn't - so after
+/// adding a field to `Params`, the shader expects the new layout while we keep
+/// sending the old one; a shader expecting more than we send gets rejected by
+/// wgpu anyway, but one expecting less would silently read garbage.
+///
+/// (fields reordered without changing the size slip through, though)
+fn check_params(
+    path: &Path,
+    shader: &[u8],
+) -> Result<(), Error> {
+    // (WGSL shaders are written by hand, so there's no `common` they could
+    // drift away from)
+    if path.extension() == Some("wgsl".as_ref()) {
+        return Ok(());
+    }
+
+    let module = naga::front::spv::parse_u8_slice(
+        shader,
+        &Default::default(),
+    )
+    .ok();
+
+    // If the shader is broken, wgpu will tell what's wrong with it in a
+    // moment, with better diagnostics
+    let Some(module) = module else {
+        return Ok(());
+    };
+
+    let app = mem::size_of::<Params>() as u32;
+
+    for (_, var) in module.global_variables.iter() {
+        let is_params = var.space
+            == naga::AddressSpace::Uniform
+            && var.binding
+                == Some(naga::ResourceBinding {
+                    group: 0,
+                    binding: 0,
+                });
+
+        if !is_params {
+            continue;
+        }
+
+        let shader = module.types[var.ty]
+            .inner
+            .size(&module.constants);
+
+        if shader != app {
+            return Err(Error::ParamsLayout {
+                shader,
+                app,
+            });
+        }
+    }
+
+    Ok(())
+}
+
+/// Layout of params, see [`ParamsBuffer`].
+fn params_layout_entry() -> wgpu::BindGroupLayoutEntry {
+    wgpu::BindGroupLayoutEntry {
+        binding: 0,
+        visibility: wgpu::ShaderStages::FRAGMENT,
+        ty: wgpu::BindingType::Buffer {
+            ty: wgpu::BufferBindingType::Uniform,
+            has_dynamic_offset: true,
+            min_binding_size: wgpu::BufferSize::new(
+                mem::size_of::<Params>() as u64,
+            ),
+        },
+        count: None,
+    }
+}
+
+fn params_entry(
+    buffer: &wgpu::Buffer,
+) -> wgpu::BindGroupEntry<'_> {
+    wgpu::BindGroupEntry {
+        binding: 0,
+        resource: wgpu::BindingResource::Buffer(
+            wgpu::BufferBinding {
+                buffer,
+                offset: 0,
+                size: wgpu::BufferSize::new(
+                    mem::size_of::<Params>() as u64,
+                ),
+            },
+        ),
+    }
+}
+
+/// Downloads [`Stats`] from the GPU, without blocking.
+#[derive(Debug)]
+struct StatsReadback {
+    /// Buffer the shader writes into.
+    buffer: wgpu::Buffer,
+
+    /// Buffer we copy `buffer` into, so that it can be mapped while the shader
+    /// keeps on rendering further frames.
+    readback: wgpu::Buffer,
+
+    state: ReadbackState,
+
+    /// Set by `map_async()`'s callback, once `readback` is ready to be read.
+    mapped: Arc<AtomicBool>,
+
+    /// Most recently downloaded stats.
+    stats: Option<Stats>,
+
+    /// Most recently downloaded value of [`Stats::probe`], if the shader has
+    /// probed anything.
+    probe: Option<Probe>,
+}
+
+/// State of a non-blocking download from the GPU (see [`StatsReadback`] and
+/// `Scopes`).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ReadbackState {
+    /// `readback` is free - we'll copy the data into it during the next
+    /// frame.
+    Idle,
+
+    /// Copy has been recorded; once it's submitted, we can start mapping.
+    Copied,
+
+    /// We're waiting for `readback` to get mapped.
+    Mapping,
+}
+
+impl StatsReadback {
+    fn new(device: &wgpu::Device) -> Self {
+        let size =
+            mem::size_of::<Stats>() as wgpu::BufferAddress;
+
+        let buffer =
+            device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some("renderer_stats_buffer"),
+                size,
+                usage: wgpu::BufferUsages::STORAGE
+                    | wgpu::BufferUsages::COPY_DST
+                    | wgpu::BufferUsages::COPY_SRC,
+                mapped_at_creation: false,
+            });
+
+        let readback =
+            device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some("renderer_stats_readback"),
+                size,
+                usage: wgpu::BufferUsages::MAP_READ
+                    | wgpu::BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            });
+
+        Self {
+            buffer,
+            readback,
+            state: ReadbackState::Idle,
+            mapped: Default::default(),
+            stats: None,
+            probe: None,
+        }
+    }
+
+    /// Records copying the stats into `readback`, if it's free.
+    fn copy(&mut self, encoder: &mut wgpu::CommandEncoder) {
+        if self.state != ReadbackState::Idle {
+            return;
+        }
+
+        encoder.copy_buffer_to_buffer(
+            &self.buffer,
+            0,
+            &self.readback,
+            0,
+            self.buffer.size(),
+        );
+
+        self.state = ReadbackState::Copied;
+    }
+
+    /// Moves the download forward; called once per frame, before rendering
+    /// (i.e. after the previous frame has been submitted).
+    fn poll(&mut self, device: &wgpu::Device) {
+        match self.state {
+            ReadbackState::Idle => {
+                //
+            }
+
+            ReadbackState::Copied => {
+                let mapped = self.mapped.clone();
+
+                self.readback.slice(..).map_async(
+                    wgpu::MapMode::Read,
+                    move |result| {
+                        if result.is_ok() {
+                            mapped.store(
+                                true,
+                                Ordering::Release,
+                            );
+                        }
+                    },
+                );
+
+                self.state = ReadbackState::Mapping;
+            }
+
+            ReadbackState::Mapping => {
+                device.poll(wgpu::Maintain::Poll);
+
+                if !self
+                    .mapped
+                    .swap(false, Ordering::Acquire)
+                {
+                    return;
+                }
+
+                let stats: Stats = *bytemuck::from_bytes(
+                    &self
+                        .readback
+                        .slice(..)
+                        .get_mapped_range(),
+                );
+
+                self.readback.unmap();
+                self.stats = Some(stats);
+
+                self.probe = (stats.probe.written != 0)
+                    .then_some(stats.probe);
+                self.state = ReadbackState::Idle;
+            }
+        }
+    }
+}
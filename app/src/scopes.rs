@@ -0,0 +1,482 @@
+use crate::gpu::Gpu;
+use crate::renderer::ReadbackState;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+/// Resolution the frame gets downsampled to before being analyzed - it's also
+/// the resolution of each scope, so that each column of the frame corresponds
+/// to a column of the waveform.
+const WIDTH: u32 = 128;
+const HEIGHT: u32 = 64;
+
+/// How many times the scopes are magnified on the screen.
+const SCALE: u32 = 2;
+
+/// Both the downsampled frame and the scopes are 8-bit sRGB, so that the
+/// analyzed values are the ones that actually land on the screen.
+const FORMAT: wgpu::TextureFormat =
+    wgpu::TextureFormat::Rgba8UnormSrgb;
+
+/// Image-analysis scopes drawn in the bottom-right corner of the window - the
+/// luminance histogram (with clipped shadows and highlights marked red) and
+/// the RGB waveform (showing, for each column of the frame, how its pixels are
+/// distributed from black at the bottom to white at the top).
+///
+/// Scopes are computed on the CPU from a downsampled copy of the frame, which
+/// gets downloaded without stalling the GPU, so they lag a couple of frames
+/// behind.
+#[derive(Debug)]
+pub struct Scopes {
+    downsample_pipeline: wgpu::RenderPipeline,
+    downsample: wgpu::Texture,
+
+    /// Buffer we copy `downsample` into, so that it can be mapped.
+    readback: wgpu::Buffer,
+    state: ReadbackState,
+
+    /// Set by `map_async()`'s callback, once `readback` is ready to be read.
+    mapped: Arc<AtomicBool>,
+
+    pipeline: wgpu::RenderPipeline,
+
+    /// Texture with both scopes side by side, uploaded from the CPU.
+    scopes: wgpu::Texture,
+    bind_group_layout: wgpu::BindGroupLayout,
+    bind_group: wgpu::BindGroup,
+}
+
+impl Scopes {
+    pub fn new(gpu: &Gpu) -> Self {
+        let device = gpu.device();
+
+        let module = device.create_shader_module(
+            wgpu::ShaderModuleDescriptor {
+                label: Some("scopes_shader"),
+                source: wgpu::ShaderSource::Wgsl(
+                    include_str!("scopes.wgsl").into(),
+                ),
+            },
+        );
+
+        let bind_group_layout = device
+            .create_bind_group_layout(
+            &wgpu::BindGroupLayoutDescriptor {
+                label: Some("scopes_bind_group_layout"),
+                entries: &[wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility:
+                        wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type:
+                            wgpu::TextureSampleType::Float {
+                                filterable: false,
+                            },
+                        view_dimension:
+                            wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                }],
+            },
+        );
+
+        let pipeline_layout = device
+            .create_pipeline_layout(
+                &wgpu::PipelineLayoutDescriptor {
+                    label: Some("scopes_pipeline_layout"),
+                    bind_group_layouts: &[
+                        &bind_group_layout,
+                    ],
+                    push_constant_ranges: &[],
+                },
+            );
+
+        let create_pipeline =
+            |label, entry_point, format, blend| {
+                device.create_render_pipeline(
+                    &wgpu::RenderPipelineDescriptor {
+                        label: Some(label),
+                        layout: Some(&pipeline_layout),
+                        vertex: wgpu::VertexState {
+                            module: &module,
+                            entry_point: "main_vs",
+                            buffers: &[],
+                        },
+                        primitive: wgpu::PrimitiveState::default(
+                        ),
+                        depth_stencil: None,
+                        multisample:
+                            wgpu::MultisampleState::default(),
+                        fragment: Some(wgpu::FragmentState {
+                            module: &module,
+                            entry_point,
+                            targets: &[Some(
+                                wgpu::ColorTargetState {
+                                    format,
+                                    blend,
+                                    write_mask:
+                                        wgpu::ColorWrites::ALL,
+                                },
+                            )],
+                        }),
+                        multiview: None,
+                    },
+                )
+            };
+
+        let downsample_pipeline = create_pipeline(
+            "scopes_downsample_pipeline",
+            "main_fs_downsample",
+            FORMAT,
+            None,
+        );
+
+        let pipeline = create_pipeline(
+            "scopes_pipeline",
+            "main_fs",
+            gpu.format(),
+            Some(wgpu::BlendState::ALPHA_BLENDING),
+        );
+
+        let create_texture = |label, width, usage| {
+            device.create_texture(
+                &wgpu::TextureDescriptor {
+                    label: Some(label),
+                    size: wgpu::Extent3d {
+                        width,
+                        height: HEIGHT,
+                        depth_or_array_layers: 1,
+                    },
+                    mip_level_count: 1,
+                    sample_count: 1,
+                    dimension: wgpu::TextureDimension::D2,
+                    format: FORMAT,
+                    usage,
+                    view_formats: &[],
+                },
+            )
+        };
+
+        let downsample = create_texture(
+            "scopes_downsample",
+            WIDTH,
+            wgpu::TextureUsages::RENDER_ATTACHMENT
+                | wgpu::TextureUsages::COPY_SRC,
+        );
+
+        let scopes = create_texture(
+            "scopes_texture",
+            2 * WIDTH,
+            wgpu::TextureUsages::TEXTURE_BINDING
+                | wgpu::TextureUsages::COPY_DST,
+        );
+
+        let readback =
+            device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some("scopes_readback"),
+                size: (4 * WIDTH * HEIGHT)
+                    as wgpu::BufferAddress,
+                usage: wgpu::BufferUsages::MAP_READ
+                    | wgpu::BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            });
+
+        let bind_group = device.create_bind_group(
+            &wgpu::BindGroupDescriptor {
+                label: Some("scopes_bind_group"),
+                layout: &bind_group_layout,
+                entries: &[wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource:
+                        wgpu::BindingResource::TextureView(
+                            &scopes.create_view(
+                                &Default::default(),
+                            ),
+                        ),
+                }],
+            },
+        );
+
+        Self {
+            downsample_pipeline,
+            downsample,
+            readback,
+            state: ReadbackState::Idle,
+            mapped: Default::default(),
+            pipeline,
+            scopes,
+            bind_group_layout,
+            bind_group,
+        }
+    }
+
+    /// Recreates the scopes on given GPU - used after the previous GPU has been
+    /// lost.
+    pub fn recreate(&mut self, gpu: &Gpu) {
+        *self = Self::new(gpu);
+    }
+
+    /// Analyzes `frame` (see [`crate::renderer::Renderer::output()`]) and
+    /// draws the scopes over whatever the target already contains.
+    pub fn render(
+        &mut self,
+        gpu: &Gpu,
+        encoder: &mut wgpu::CommandEncoder,
+        target: &wgpu::TextureView,
+        frame: &wgpu::TextureView,
+        width: u32,
+        height: u32,
+    ) {
+        self.poll(gpu);
+
+        if self.state == ReadbackState::Idle {
+            self.download(gpu, encoder, frame);
+        }
+
+        let mut pass = encoder.begin_render_pass(
+            &wgpu::RenderPassDescriptor {
+                label: Some("scopes_render_pass"),
+                color_attachments: &[Some(
+                    wgpu::RenderPassColorAttachment {
+                        view: target,
+                        resolve_target: None,
+                        ops: wgpu::Operations {
+                            load: wgpu::LoadOp::Load,
+                            store: true,
+                        },
+                    },
+                )],
+                depth_stencil_attachment: None,
+            },
+        );
+
+        let scopes_width = (2 * WIDTH * SCALE).min(width);
+        let scopes_height = (HEIGHT * SCALE).min(height);
+
+        pass.set_pipeline(&self.pipeline);
+        pass.set_bind_group(0, &self.bind_group, &[]);
+
+        pass.set_viewport(
+            (width - scopes_width) as f32,
+            (height - scopes_height) as f32,
+            scopes_width as f32,
+            scopes_height as f32,
+            0.0,
+            1.0,
+        );
+
+        pass.draw(0..3, 0..1);
+    }
+
+    /// Records downsampling `frame` and copying it into `readback`.
+    fn download(
+        &mut self,
+        gpu: &Gpu,
+        encoder: &mut wgpu::CommandEncoder,
+        frame: &wgpu::TextureView,
+    ) {
+        let bind_group = gpu.device().create_bind_group(
+            &wgpu::BindGroupDescriptor {
+                label: Some("scopes_downsample_bind_group"),
+                layout: &self.bind_group_layout,
+                entries: &[wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource:
+                        wgpu::BindingResource::TextureView(
+                            frame,
+                        ),
+                }],
+            },
+        );
+
+        let view = self
+            .downsample
+            .create_view(&Default::default());
+
+        let mut pass = encoder.begin_render_pass(
+            &wgpu::RenderPassDescriptor {
+                label: Some(
+                    "scopes_downsample_render_pass",
+                ),
+                color_attachments: &[Some(
+                    wgpu::RenderPassColorAttachment {
+                        view: &view,
+                        resolve_target: None,
+                        ops: wgpu::Operations {
+                            load: wgpu::LoadOp::Clear(
+                                wgpu::Color::BLACK,
+                            ),
+                            store: true,
+                        },
+                    },
+                )],
+                depth_stencil_attachment: None,
+            },
+        );
+
+        pass.set_pipeline(&self.downsample_pipeline);
+        pass.set_bind_group(0, &bind_group, &[]);
+        pass.draw(0..3, 0..1);
+        drop(pass);
+
+        encoder.copy_texture_to_buffer(
+            self.downsample.as_image_copy(),
+            wgpu::ImageCopyBuffer {
+                buffer: &self.readback,
+                layout: wgpu::ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: Some(4 * WIDTH),
+                    rows_per_image: Some(HEIGHT),
+                },
+            },
+            self.downsample.size(),
+        );
+
+        self.state = ReadbackState::Copied;
+    }
+
+    /// Moves the download forward; once it's finished, refreshes the scopes.
+    fn poll(&mut self, gpu: &Gpu) {
+        match self.state {
+            ReadbackState::Idle => {
+                //
+            }
+
+            ReadbackState::Copied => {
+                let mapped = self.mapped.clone();
+
+                self.readback.slice(..).map_async(
+                    wgpu::MapMode::Read,
+                    move |result| {
+                        if result.is_ok() {
+                            mapped.store(
+                                true,
+                                Ordering::Release,
+                            );
+                        }
+                    },
+                );
+
+                self.state = ReadbackState::Mapping;
+            }
+
+            ReadbackState::Mapping => {
+                gpu.device().poll(wgpu::Maintain::Poll);
+
+                if !self
+                    .mapped
+                    .swap(false, Ordering::Acquire)
+                {
+                    return;
+                }
+
+                let scopes = analyze(
+                    &self
+                        .readback
+                        .slice(..)
+                        .get_mapped_range(),
+                );
+
+                self.readback.unmap();
+                self.state = ReadbackState::Idle;
+
+                gpu.queue().write_texture(
+                    self.scopes.as_image_copy(),
+                    &scopes,
+                    wgpu::ImageDataLayout {
+                        offset: 0,
+                        bytes_per_row: Some(4 * 2 * WIDTH),
+                        rows_per_image: Some(HEIGHT),
+                    },
+                    self.scopes.size(),
+                );
+            }
+        }
+    }
+}
+
+/// Computes scopes for given downsampled frame (`WIDTH x HEIGHT`, RGBA),
+/// returning them as an image (`2 * WIDTH x HEIGHT`, RGBA) with the histogram
+/// on the left and the waveform on the right.
+fn analyze(frame: &[u8]) -> Vec<u8> {
+    let width = WIDTH as usize;
+    let height = HEIGHT as usize;
+
+    // Maps 8-bit value into a row of the scope, with 255 at the top
+    let row = |value: u8| {
+        (height - 1) - value as usize * (height - 1) / 255
+    };
+
+    let mut histogram = vec![0; width];
+    let mut waveform = vec![[0; 3]; width * height];
+
+    for (idx, pixel) in frame.chunks_exact(4).enumerate() {
+        let x = idx % width;
+
+        let luma = 0.2126 * pixel[0] as f32
+            + 0.7152 * pixel[1] as f32
+            + 0.0722 * pixel[2] as f32;
+
+        histogram[(luma as usize * width / 256)
+            .min(width - 1)] += 1;
+
+        for channel in 0..3 {
+            waveform[row(pixel[channel]) * width + x]
+                [channel] += 1;
+        }
+    }
+
+    let tallest =
+        histogram.iter().copied().max().unwrap_or(0).max(1);
+    let mut scopes = vec![0; 4 * 2 * width * height];
+
+    for y in 0..height {
+        for x in 0..width {
+            // Histogram
+            let bar = (histogram[x] * height + tallest - 1)
+                / tallest;
+
+            let color = if y < height - bar {
+                [0, 0, 0, 160]
+            } else if x == 0 || x == width - 1 {
+                [255, 40, 40, 255]
+            } else {
+                [200, 200, 200, 255]
+            };
+
+            let offset = 4 * (y * 2 * width + x);
+            scopes[offset..offset + 4]
+                .copy_from_slice(&color);
+
+            // Waveform, with guide lines at 0%, 50% and 100%; a dozen or so
+            // pixels landing at the same spot is enough to make it fully
+            // bright
+            let counts = waveform[y * width + x];
+
+            let color = if counts != [0; 3] {
+                let [r, g, b] = counts.map(|count| {
+                    if count > 0 {
+                        (64 + count * 16).min(255) as u8
+                    } else {
+                        0
+                    }
+                });
+
+                [r, g, b, 255]
+            } else if y == 0
+                || y == height / 2
+                || y == height - 1
+            {
+                [80, 80, 80, 160]
+            } else {
+                [0, 0, 0, 160]
+            };
+
+            let offset = 4 * (y * 2 * width + width + x);
+            scopes[offset..offset + 4]
+                .copy_from_slice(&color);
+        }
+    }
+
+    scopes
+}
@@ -0,0 +1,41 @@
+// Downsamples the frame and draws the scopes, see `scopes.rs`.
+
+struct VertexOutput {
+    @builtin(position) pos: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+}
+
+@group(0) @binding(0)
+var source: texture_2d<f32>;
+
+// Full-screen triangle, same as in the main shader - the viewport takes care of
+// putting it in the corner
+@vertex
+fn main_vs(@builtin(vertex_index) id: u32) -> VertexOutput {
+    let uv = vec2<f32>(f32((id << 1u) & 2u), f32(id & 2u));
+
+    var out: VertexOutput;
+    out.pos = vec4<f32>(uv.x * 2.0 - 1.0, 1.0 - uv.y * 2.0, 0.0, 1.0);
+    out.uv = uv;
+    return out;
+}
+
+// Picks the source's pixel corresponding to given pixel of the target - works
+// both for shrinking the frame (nearest-neighbor is good enough for scopes) and
+// for stretching the scopes over the screen
+fn load(uv: vec2<f32>) -> vec4<f32> {
+    let size = textureDimensions(source);
+    let pos = min(vec2<u32>(uv * vec2<f32>(size)), size - 1u);
+
+    return textureLoad(source, pos, 0);
+}
+
+@fragment
+fn main_fs_downsample(in: VertexOutput) -> @location(0) vec4<f32> {
+    return vec4<f32>(load(in.uv).rgb, 1.0);
+}
+
+@fragment
+fn main_fs(in: VertexOutput) -> @location(0) vec4<f32> {
+    return load(in.uv);
+}
@@ -0,0 +1,78 @@
+//! Screensaver mode (`--screensaver`) - a borderless window covering all the
+//! monitors, showing a random scene from a random angle and closing as soon as
+//! the user touches the mouse or keyboard.
+
+use crate::wall;
+use sdf_playground_common::{Params, SCENES};
+use std::f32::consts::TAU;
+use std::time::{SystemTime, UNIX_EPOCH};
+use winit::dpi::PhysicalPosition;
+use winit::event::{Event, WindowEvent};
+use winit::event_loop::EventLoop;
+use winit::window::{WindowBuilder, WindowLevel};
+
+/// How far (in pixels) the mouse has to move to interrupt the screensaver -
+/// some mice (and some platforms) report tiny movements every now and then.
+const CURSOR_THRESHOLD: f64 = 10.0;
+
+#[derive(Debug, Default)]
+pub struct Screensaver {
+    /// Where the cursor was located when we've first seen it.
+    cursor: Option<PhysicalPosition<f64>>,
+}
+
+impl Screensaver {
+    /// Returns a window spanning all the monitors, staying on top of other
+    /// windows.
+    pub fn window(
+        event_loop: &EventLoop<()>,
+    ) -> WindowBuilder {
+        wall::window(event_loop)
+            .with_window_level(WindowLevel::AlwaysOnTop)
+    }
+
+    /// Picks a random scene and camera angle.
+    pub fn randomize(params: &mut Params) {
+        // No need for a proper random number generator here, the current time
+        // is unpredictable enough
+        let seed = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .subsec_nanos();
+
+        params.scene = seed % SCENES + 1;
+        params.orbit =
+            (seed / SCENES % 360) as f32 / 360.0 * TAU;
+    }
+
+    /// Returns whether given event means that the user is back and the
+    /// screensaver should be closed.
+    pub fn interrupted(
+        &mut self,
+        event: &Event<()>,
+    ) -> bool {
+        let Event::WindowEvent { event, .. } = event else {
+            return false;
+        };
+
+        match event {
+            WindowEvent::KeyboardInput { .. }
+            | WindowEvent::MouseInput { .. }
+            | WindowEvent::MouseWheel { .. }
+            | WindowEvent::Touch(_) => true,
+
+            WindowEvent::CursorMoved {
+                position, ..
+            } => {
+                let origin =
+                    *self.cursor.get_or_insert(*position);
+
+                (position.x - origin.x)
+                    .hypot(position.y - origin.y)
+                    > CURSOR_THRESHOLD
+            }
+
+            _ => false,
+        }
+    }
+}
@@ -0,0 +1,278 @@
+//! Script (`script.rhai`, or whatever `SDF_PLAYGROUND_SCRIPT` points at) run on
+//! the CPU each frame to compute some of the params - e.g. to move the camera
+//! around procedurally.
+//!
+//! Just like the shader, the script gets reloaded as soon as it's modified,
+//! but since it's interpreted, there's no waiting for the compiler.
+
+use log::{error, info};
+use rhai::{Engine, Scope, AST};
+use sdf_playground_common::glam::Vec3;
+use sdf_playground_common::{time_curve, Params, SCENES};
+use std::env;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+/// How many operations a single run of the script can perform - prevents an
+/// accidental infinite loop from freezing the application.
+const MAX_OPERATIONS: u64 = 1_000_000;
+
+#[derive(Debug)]
+pub struct Script {
+    path: PathBuf,
+    engine: Engine,
+
+    /// Most recent version of the script that has compiled successfully.
+    ast: Option<AST>,
+
+    /// When the script has been modified when we've last loaded it.
+    modified_at: Option<SystemTime>,
+
+    /// Most recent error reported by the script, so that we don't flood the
+    /// logs with the same error each frame.
+    error: Option<String>,
+}
+
+impl Script {
+    pub fn new() -> Self {
+        let path = Self::path();
+        let mut engine = Engine::new();
+
+        engine.set_max_operations(MAX_OPERATIONS);
+
+        Self {
+            path,
+            engine,
+            ast: None,
+            modified_at: None,
+            error: None,
+        }
+    }
+
+    /// Returns path of the script - `script.rhai`, or whatever
+    /// `SDF_PLAYGROUND_SCRIPT` points at.
+    pub fn path() -> PathBuf {
+        env::var_os("SDF_PLAYGROUND_SCRIPT")
+            .map(PathBuf::from)
+            .unwrap_or_else(|| {
+                Path::new(env!("CARGO_MANIFEST_DIR"))
+                    .parent()
+                    .unwrap()
+                    .join("script.rhai")
+            })
+    }
+
+    /// Reloads the script (if it's been modified) and runs it, updating given
+    /// params.
+    pub fn run(&mut self, params: &mut Params) {
+        self.reload();
+
+        let Some(ast) = &self.ast else {
+            return;
+        };
+
+        let mut scope = Scope::new();
+
+        scope
+            .push_constant("time", params.time as f64)
+            .push_constant("frame", params.frame as i64)
+            .push_constant("width", params.width as i64)
+            .push_constant("height", params.height as i64)
+            .push("scene", params.scene as i64)
+            .push("debug_view", params.debug_view as i64)
+            .push("orbit", params.orbit as f64)
+            .push(
+                "eye_separation",
+                params.eye_separation as f64,
+            )
+            .push("shutter", params.shutter as f64)
+            .push("near", params.near as f64)
+            .push("far", params.far as f64)
+            .push("subsurface", params.subsurface as f64)
+            .push("shading", params.shading as i64)
+            .push("toon_bands", params.toon_bands as i64)
+            .push("rim", params.rim as f64)
+            .push("rim_width", params.rim_width as f64)
+            .push("epsilon", params.epsilon as f64)
+            .push("time_curve", params.time_curve.x as i64)
+            .push("time_period", params.time_curve.y as f64)
+            .push("glow", params.glow.w as f64)
+            .push(
+                "glow_color",
+                color(params.glow.truncate()),
+            )
+            .push("fog", params.fog.x as f64)
+            .push("fog_falloff", params.fog.y as f64)
+            .push("ambient", params.ambient.x as f64)
+            .push(
+                "aberration",
+                params.post_effects.x as f64,
+            )
+            .push("grain", params.post_effects.y as f64);
+
+        if let Err(err) =
+            self.engine.run_ast_with_scope(&mut scope, ast)
+        {
+            self.report(err.to_string());
+            return;
+        }
+
+        self.error = None;
+
+        let int = |name| scope.get_value::<i64>(name);
+        let float = |name| scope.get_value::<f64>(name);
+
+        if let Some(scene) = int("scene") {
+            params.scene = (scene as u32).clamp(1, SCENES);
+        }
+
+        if let Some(debug_view) = int("debug_view") {
+            params.debug_view = debug_view as u32;
+        }
+
+        if let Some(orbit) = float("orbit") {
+            params.orbit = orbit as f32;
+        }
+
+        if let Some(eye_separation) =
+            float("eye_separation")
+        {
+            params.eye_separation = eye_separation as f32;
+        }
+
+        if let Some(shutter) = float("shutter") {
+            params.shutter = shutter as f32;
+        }
+
+        if let Some(near) = float("near") {
+            params.near = near as f32;
+        }
+
+        if let Some(far) = float("far") {
+            params.far = far as f32;
+        }
+
+        if let Some(subsurface) = float("subsurface") {
+            params.subsurface = subsurface as f32;
+        }
+
+        if let Some(shading) = int("shading") {
+            params.shading = shading as u32;
+        }
+
+        if let Some(toon_bands) = int("toon_bands") {
+            params.toon_bands = (toon_bands as u32).max(2);
+        }
+
+        if let Some(rim) = float("rim") {
+            params.rim = rim as f32;
+        }
+
+        if let Some(rim_width) = float("rim_width") {
+            params.rim_width = rim_width as f32;
+        }
+
+        if let Some(epsilon) = float("epsilon") {
+            params.epsilon = epsilon as f32;
+        }
+
+        if let Some(time_curve) = int("time_curve") {
+            params.time_curve.x = (time_curve as u32)
+                .min(time_curve::COUNT - 1)
+                as f32;
+        }
+
+        if let Some(time_period) = float("time_period") {
+            params.time_curve.y = time_period as f32;
+        }
+
+        if let Some(glow) = float("glow") {
+            params.glow.w = glow.max(0.0) as f32;
+        }
+
+        if let Some(fog) = float("fog") {
+            params.fog.x = fog.max(0.0) as f32;
+        }
+
+        if let Some(fog_falloff) = float("fog_falloff") {
+            params.fog.y = fog_falloff.max(0.0) as f32;
+        }
+
+        if let Some(ambient) = float("ambient") {
+            params.ambient.x = ambient.max(0.0) as f32;
+        }
+
+        if let Some(aberration) = float("aberration") {
+            params.post_effects.x =
+                aberration.max(0.0) as f32;
+        }
+
+        if let Some(grain) = float("grain") {
+            params.post_effects.y = grain.max(0.0) as f32;
+        }
+
+        if let Some(glow_color) = int("glow_color") {
+            let channel = |shift: i64| {
+                ((glow_color >> shift) & 0xff) as f32
+                    / 255.0
+            };
+
+            params.glow.x = channel(16);
+            params.glow.y = channel(8);
+            params.glow.z = channel(0);
+        }
+    }
+
+    /// Compiles the script if it's been modified since the last time; if it
+    /// doesn't compile, the previous version is kept.
+    fn reload(&mut self) {
+        let modified_at = self
+            .path
+            .metadata()
+            .and_then(|metadata| metadata.modified())
+            .ok();
+
+        if modified_at == self.modified_at {
+            return;
+        }
+
+        self.modified_at = modified_at;
+
+        if modified_at.is_none() {
+            // No script - nothing to do
+            self.ast = None;
+            return;
+        }
+
+        match self.engine.compile_file(self.path.clone()) {
+            Ok(ast) => {
+                info!("Loaded {}", self.path.display());
+
+                self.ast = Some(ast);
+                self.error = None;
+            }
+
+            Err(err) => {
+                self.report(err.to_string());
+            }
+        }
+    }
+
+    fn report(&mut self, err: String) {
+        if self.error.as_ref() != Some(&err) {
+            error!("Script failed: {err}");
+            self.error = Some(err);
+        }
+    }
+}
+
+/// Encodes given (linear) color as `0xRRGGBB`, the way the script sees colors.
+fn color(color: Vec3) -> i64 {
+    let channel = |value: f32| {
+        (value.clamp(0.0, 1.0) * 255.0).round() as i64
+    };
+
+    (channel(color.x) << 16)
+        | (channel(color.y) << 8)
+        | channel(color.z)
+}
@@ -0,0 +1,259 @@
+use crate::gpu::Gpu;
+use crate::renderer::Renderer;
+use sdf_playground_common::Params;
+use std::fmt;
+
+/// Debug viewer of the intermediate render targets - shows one of the textures
+/// the frame gets assembled from over the entire screen, before any of the
+/// post passes touch it.
+#[derive(Debug)]
+pub struct TargetViewer {
+    pipeline: wgpu::RenderPipeline,
+    bind_group_layout: wgpu::BindGroupLayout,
+    buffer: wgpu::Buffer,
+
+    /// Target being shown, if any.
+    pub target: Option<Target>,
+}
+
+/// Render targets that can be viewed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Target {
+    /// Frame as rendered by the shader - in linear colors and before post
+    /// effects, foveation etc.; squashed into `0..1` (Reinhard), so that
+    /// overly bright areas still show some detail.
+    Hdr,
+
+    /// Normals of the surfaces (see [`Renderer::aov()`]), mapped from
+    /// `-1..1` into colors.
+    Normals,
+
+    /// Distances to the surfaces (see [`Renderer::aov()`]), from black (at
+    /// the camera) to white (at the far plane).
+    Depth,
+
+    /// Distances found by the coarse pre-pass (see [`Params::coarse`]), same
+    /// as [`Self::Depth`]; empty when the pre-pass is disabled.
+    Coarse,
+}
+
+impl Target {
+    /// Returns the target after given one, `None` meaning no target (the
+    /// regular image).
+    pub fn next(target: Option<Self>) -> Option<Self> {
+        match target {
+            None => Some(Target::Hdr),
+            Some(Target::Hdr) => Some(Target::Normals),
+            Some(Target::Normals) => Some(Target::Depth),
+            Some(Target::Depth) => Some(Target::Coarse),
+            Some(Target::Coarse) => None,
+        }
+    }
+}
+
+impl fmt::Display for Target {
+    fn fmt(
+        &self,
+        f: &mut fmt::Formatter<'_>,
+    ) -> fmt::Result {
+        match self {
+            Target::Hdr => write!(f, "hdr"),
+            Target::Normals => write!(f, "normals"),
+            Target::Depth => write!(f, "depth"),
+            Target::Coarse => write!(f, "coarse"),
+        }
+    }
+}
+
+impl TargetViewer {
+    pub fn new(gpu: &Gpu) -> Self {
+        let device = gpu.device();
+
+        let module = device.create_shader_module(
+            wgpu::ShaderModuleDescriptor {
+                label: Some("target_viewer_shader"),
+                source: wgpu::ShaderSource::Wgsl(
+                    include_str!("target_viewer.wgsl")
+                        .into(),
+                ),
+            },
+        );
+
+        let buffer =
+            device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some("target_viewer_buffer"),
+                size: 4 * 4,
+                usage: wgpu::BufferUsages::UNIFORM
+                    | wgpu::BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            });
+
+        let bind_group_layout = device
+            .create_bind_group_layout(
+                &wgpu::BindGroupLayoutDescriptor {
+                    label: Some("target_viewer_bind_group_layout"),
+                    entries: &[
+                        wgpu::BindGroupLayoutEntry {
+                            binding: 0,
+                            visibility:
+                                wgpu::ShaderStages::FRAGMENT,
+                            ty: wgpu::BindingType::Texture {
+                                sample_type:
+                                    wgpu::TextureSampleType::Float {
+                                        filterable: false,
+                                    },
+                                view_dimension:
+                                    wgpu::TextureViewDimension::D2,
+                                multisampled: false,
+                            },
+                            count: None,
+                        },
+                        wgpu::BindGroupLayoutEntry {
+                            binding: 1,
+                            visibility:
+                                wgpu::ShaderStages::FRAGMENT,
+                            ty: wgpu::BindingType::Buffer {
+                                ty: wgpu::BufferBindingType::Uniform,
+                                has_dynamic_offset: false,
+                                min_binding_size: None,
+                            },
+                            count: None,
+                        },
+                    ],
+                },
+            );
+
+        let pipeline_layout = device
+            .create_pipeline_layout(
+                &wgpu::PipelineLayoutDescriptor {
+                    label: Some(
+                        "target_viewer_pipeline_layout",
+                    ),
+                    bind_group_layouts: &[
+                        &bind_group_layout,
+                    ],
+                    push_constant_ranges: &[],
+                },
+            );
+
+        let pipeline = device.create_render_pipeline(
+            &wgpu::RenderPipelineDescriptor {
+                label: Some("target_viewer_pipeline"),
+                layout: Some(&pipeline_layout),
+                vertex: wgpu::VertexState {
+                    module: &module,
+                    entry_point: "main_vs",
+                    buffers: &[],
+                },
+                primitive: wgpu::PrimitiveState::default(),
+                depth_stencil: None,
+                multisample:
+                    wgpu::MultisampleState::default(),
+                fragment: Some(wgpu::FragmentState {
+                    module: &module,
+                    entry_point: "main_fs",
+                    targets: &[Some(
+                        wgpu::ColorTargetState {
+                            format: gpu.format(),
+                            blend: None,
+                            write_mask:
+                                wgpu::ColorWrites::ALL,
+                        },
+                    )],
+                }),
+                multiview: None,
+            },
+        );
+
+        Self {
+            pipeline,
+            bind_group_layout,
+            buffer,
+            target: None,
+        }
+    }
+
+    /// Recreates the pipeline on given GPU - used after the previous GPU has
+    /// been lost.
+    pub fn recreate(&mut self, gpu: &Gpu) {
+        *self = Self {
+            target: self.target,
+            ..Self::new(gpu)
+        };
+    }
+
+    /// Draws the selected target over the entire screen; does nothing if no
+    /// target is selected.
+    pub fn render(
+        &self,
+        gpu: &Gpu,
+        encoder: &mut wgpu::CommandEncoder,
+        target: &wgpu::TextureView,
+        renderer: &Renderer,
+        params: &Params,
+    ) {
+        let Some(selected) = self.target else {
+            return;
+        };
+
+        let texture = match selected {
+            Target::Hdr => renderer.output(),
+            Target::Normals | Target::Depth => {
+                renderer.aov()
+            }
+            Target::Coarse => renderer.coarse(),
+        };
+
+        gpu.queue().write_buffer(
+            &self.buffer,
+            0,
+            bytemuck::cast_slice(&[
+                selected as u32 as f32,
+                params.far,
+                params.coarse as f32,
+                0.0,
+            ]),
+        );
+
+        let bind_group = gpu.device().create_bind_group(
+            &wgpu::BindGroupDescriptor {
+                label: Some("target_viewer_bind_group"),
+                layout: &self.bind_group_layout,
+                entries: &[
+                    wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource:
+                            wgpu::BindingResource::TextureView(
+                                texture,
+                            ),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 1,
+                        resource: self.buffer.as_entire_binding(),
+                    },
+                ],
+            },
+        );
+
+        let mut pass = encoder.begin_render_pass(
+            &wgpu::RenderPassDescriptor {
+                label: Some("target_viewer_render_pass"),
+                color_attachments: &[Some(
+                    wgpu::RenderPassColorAttachment {
+                        view: target,
+                        resolve_target: None,
+                        ops: wgpu::Operations {
+                            load: wgpu::LoadOp::Load,
+                            store: true,
+                        },
+                    },
+                )],
+                depth_stencil_attachment: None,
+            },
+        );
+
+        pass.set_pipeline(&self.pipeline);
+        pass.set_bind_group(0, &bind_group, &[]);
+        pass.draw(0..3, 0..1);
+    }
+}
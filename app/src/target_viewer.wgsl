@@ -0,0 +1,73 @@
+// Shows one of the intermediate render targets, see `target_viewer.rs`.
+
+struct Settings {
+    // Same as `Target`
+    view: f32,
+
+    // Same as `Params::far`
+    far: f32,
+
+    // Same as `Params::coarse`
+    coarse: f32,
+}
+
+// Same as `Target`
+const HDR: u32 = 0u;
+const NORMALS: u32 = 1u;
+const DEPTH: u32 = 2u;
+const COARSE: u32 = 3u;
+
+@group(0) @binding(0)
+var frame: texture_2d<f32>;
+
+@group(0) @binding(1)
+var<uniform> settings: Settings;
+
+// Full-screen triangle, same as in the main shader
+@vertex
+fn main_vs(@builtin(vertex_index) id: u32) -> @builtin(position) vec4<f32> {
+    let uv = vec2<f32>(f32((id << 1u) & 2u), f32(id & 2u));
+
+    return vec4<f32>(uv.x * 2.0 - 1.0, 1.0 - uv.y * 2.0, 0.0, 1.0);
+}
+
+// Maps distance into grayscale; pixels that haven't been rendered (negative
+// distance) are dark red, so that they don't pass for the nearby ones
+fn depth(distance: f32) -> vec3<f32> {
+    if distance < 0.0 {
+        return vec3<f32>(0.3, 0.0, 0.0);
+    }
+
+    return vec3<f32>(clamp(distance / settings.far, 0.0, 1.0));
+}
+
+@fragment
+fn main_fs(@builtin(position) pos: vec4<f32>) -> @location(0) vec4<f32> {
+    let view = u32(settings.view);
+
+    // (the coarse pre-pass has a lower resolution, so it gets stretched over
+    // the screen - and when it's disabled, there's just a single pixel)
+    var pixel = vec2<i32>(pos.xy);
+
+    if view == COARSE {
+        let size = vec2<i32>(textureDimensions(frame));
+
+        pixel = min(pixel / max(i32(settings.coarse), 1), size - 1);
+    }
+
+    let value = textureLoad(frame, pixel, 0);
+
+    if view == HDR {
+        return vec4<f32>(value.rgb / (1.0 + value.rgb), 1.0);
+    }
+
+    if view == NORMALS {
+        return vec4<f32>(value.xyz * 0.5 + 0.5, 1.0);
+    }
+
+    if view == DEPTH {
+        return vec4<f32>(depth(value.w), 1.0);
+    }
+
+    return vec4<f32>(depth(value.r), 1.0);
+}
@@ -0,0 +1,343 @@
+use crate::gpu::Gpu;
+use crate::renderer::{self, Renderer};
+use sdf_playground_common::glam::{
+    uvec2, vec4, Mat3, UVec2, Vec3, Vec4,
+};
+use sdf_playground_common::{look_at, Params};
+use sdf_playground_shader::camera;
+
+/// Post-process pass of temporal anti-aliasing (see `Params::taa`) - blends
+/// the renderer's most recent frame (rendered with jitter) with the frames
+/// accumulated so far, reprojected to account for the camera's movement.
+///
+/// Unlike the renderer, which sees just a single pixel at a time, this pass
+/// sees the entire frame - so the accumulated colors can be kept within the
+/// range of what's currently around the pixel, which is what keeps animated
+/// surfaces from ghosting.
+#[derive(Debug)]
+pub struct TemporalAa {
+    pipeline: wgpu::RenderPipeline,
+    bind_group_layout: wgpu::BindGroupLayout,
+    buffer: wgpu::Buffer,
+
+    /// Frames accumulated so far - each frame reads one of them and writes
+    /// into the other one, swapping them for the next frame.
+    history: [wgpu::TextureView; 2],
+    history_size: UVec2,
+
+    /// Which of [`Self::history`] has been written by the previous frame, if
+    /// the previous frame has been anti-aliased at all.
+    latest: Option<usize>,
+}
+
+impl TemporalAa {
+    pub fn new(gpu: &Gpu) -> Self {
+        let device = gpu.device();
+
+        let module = device.create_shader_module(
+            wgpu::ShaderModuleDescriptor {
+                label: Some("temporal_aa_shader"),
+                source: wgpu::ShaderSource::Wgsl(
+                    include_str!("temporal_aa.wgsl").into(),
+                ),
+            },
+        );
+
+        let buffer =
+            device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some("temporal_aa_buffer"),
+                size: 10 * 4 * 4,
+                usage: wgpu::BufferUsages::UNIFORM
+                    | wgpu::BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            });
+
+        let texture_entry =
+            |binding| wgpu::BindGroupLayoutEntry {
+                binding,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Texture {
+                    sample_type:
+                        wgpu::TextureSampleType::Float {
+                            filterable: false,
+                        },
+                    view_dimension:
+                        wgpu::TextureViewDimension::D2,
+                    multisampled: false,
+                },
+                count: None,
+            };
+
+        let bind_group_layout = device
+            .create_bind_group_layout(
+                &wgpu::BindGroupLayoutDescriptor {
+                    label: Some(
+                        "temporal_aa_bind_group_layout",
+                    ),
+                    entries: &[
+                        texture_entry(0),
+                        texture_entry(1),
+                        texture_entry(2),
+                        texture_entry(3),
+                        wgpu::BindGroupLayoutEntry {
+                            binding: 4,
+                            visibility:
+                                wgpu::ShaderStages::FRAGMENT,
+                            ty: wgpu::BindingType::Buffer {
+                                ty: wgpu::BufferBindingType::Uniform,
+                                has_dynamic_offset: false,
+                                min_binding_size: None,
+                            },
+                            count: None,
+                        },
+                    ],
+                },
+            );
+
+        let pipeline_layout = device
+            .create_pipeline_layout(
+                &wgpu::PipelineLayoutDescriptor {
+                    label: Some(
+                        "temporal_aa_pipeline_layout",
+                    ),
+                    bind_group_layouts: &[
+                        &bind_group_layout,
+                    ],
+                    push_constant_ranges: &[],
+                },
+            );
+
+        let pipeline = device.create_render_pipeline(
+            &wgpu::RenderPipelineDescriptor {
+                label: Some("temporal_aa_pipeline"),
+                layout: Some(&pipeline_layout),
+                vertex: wgpu::VertexState {
+                    module: &module,
+                    entry_point: "main_vs",
+                    buffers: &[],
+                },
+                primitive: wgpu::PrimitiveState::default(),
+                depth_stencil: None,
+                multisample:
+                    wgpu::MultisampleState::default(),
+                fragment: Some(wgpu::FragmentState {
+                    module: &module,
+                    entry_point: "main_fs",
+                    targets: &[
+                        Some(wgpu::ColorTargetState {
+                            format: gpu.format(),
+                            blend: None,
+                            write_mask:
+                                wgpu::ColorWrites::ALL,
+                        }),
+                        Some(wgpu::ColorTargetState {
+                            format:
+                                renderer::HISTORY_FORMAT,
+                            blend: None,
+                            write_mask:
+                                wgpu::ColorWrites::ALL,
+                        }),
+                    ],
+                }),
+                multiview: None,
+            },
+        );
+
+        let history_size = UVec2::ONE;
+        let history = create_history(gpu, history_size);
+
+        Self {
+            pipeline,
+            bind_group_layout,
+            buffer,
+            history,
+            history_size,
+            latest: None,
+        }
+    }
+
+    /// Recreates the pipeline on given GPU - used after the previous GPU has
+    /// been lost.
+    pub fn recreate(&mut self, gpu: &Gpu) {
+        *self = Self::new(gpu);
+    }
+
+    /// Forgets the accumulated frames - used when the frame couldn't be
+    /// anti-aliased, so that the next one doesn't blend with a stale history.
+    pub fn reset(&mut self) {
+        self.latest = None;
+    }
+
+    /// Draws the renderer's most recent frame, anti-aliased, into the target.
+    pub fn render(
+        &mut self,
+        gpu: &Gpu,
+        encoder: &mut wgpu::CommandEncoder,
+        target: &wgpu::TextureView,
+        renderer: &Renderer,
+        params: &Params,
+    ) {
+        let size = uvec2(params.width, params.height)
+            .max(UVec2::ONE);
+
+        if size != self.history_size {
+            self.history_size = size;
+            self.history = create_history(gpu, size);
+            self.latest = None;
+        }
+
+        let origin = camera(params);
+        let orientation =
+            look_at(origin, params.target(), Vec3::Y);
+
+        let prev_params = params.prev();
+        let prev_origin = camera(&prev_params);
+        let prev_orientation = look_at(
+            prev_origin,
+            prev_params.target(),
+            Vec3::Y,
+        );
+
+        let columns = |orientation: Mat3| {
+            [
+                orientation.x_axis.extend(0.0),
+                orientation.y_axis.extend(0.0),
+                orientation.z_axis.extend(0.0),
+            ]
+        };
+
+        let [x, y, z] = columns(orientation);
+        let [prev_x, prev_y, prev_z] =
+            columns(prev_orientation);
+
+        // (the renderer zeroes `prev_camera.y` when its own history - which
+        // includes the previous AOV - isn't valid, e.g. after a resize)
+        let has_history = self.latest.is_some()
+            && params.prev_camera.y != 0.0;
+
+        let settings: [Vec4; 10] = [
+            origin.extend(params.far),
+            x,
+            y,
+            z,
+            prev_origin.extend(params.pixel_footprint()),
+            prev_x,
+            prev_y,
+            prev_z,
+            params.frustum,
+            vec4(
+                params.taa.x,
+                params.taa.y,
+                params.taa.z,
+                has_history as u32 as f32,
+            ),
+        ];
+
+        gpu.queue().write_buffer(
+            &self.buffer,
+            0,
+            bytemuck::bytes_of(&settings),
+        );
+
+        let read = self.latest.unwrap_or(0);
+        let write = 1 - read;
+
+        let bind_group = gpu.device().create_bind_group(
+            &wgpu::BindGroupDescriptor {
+                label: Some("temporal_aa_bind_group"),
+                layout: &self.bind_group_layout,
+                entries: &[
+                    wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource:
+                            wgpu::BindingResource::TextureView(
+                                renderer.output(),
+                            ),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 1,
+                        resource:
+                            wgpu::BindingResource::TextureView(
+                                renderer.aov(),
+                            ),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 2,
+                        resource:
+                            wgpu::BindingResource::TextureView(
+                                renderer.prev_aov(),
+                            ),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 3,
+                        resource:
+                            wgpu::BindingResource::TextureView(
+                                &self.history[read],
+                            ),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 4,
+                        resource: self.buffer.as_entire_binding(),
+                    },
+                ],
+            },
+        );
+
+        let mut pass = encoder.begin_render_pass(
+            &wgpu::RenderPassDescriptor {
+                label: Some("temporal_aa_render_pass"),
+                color_attachments: &[
+                    Some(wgpu::RenderPassColorAttachment {
+                        view: target,
+                        resolve_target: None,
+                        ops: wgpu::Operations {
+                            load: wgpu::LoadOp::Load,
+                            store: true,
+                        },
+                    }),
+                    Some(wgpu::RenderPassColorAttachment {
+                        view: &self.history[write],
+                        resolve_target: None,
+                        ops: wgpu::Operations {
+                            load: wgpu::LoadOp::Load,
+                            store: true,
+                        },
+                    }),
+                ],
+                depth_stencil_attachment: None,
+            },
+        );
+
+        pass.set_pipeline(&self.pipeline);
+        pass.set_bind_group(0, &bind_group, &[]);
+        pass.draw(0..3, 0..1);
+        drop(pass);
+
+        self.latest = Some(write);
+    }
+}
+
+fn create_history(
+    gpu: &Gpu,
+    size: UVec2,
+) -> [wgpu::TextureView; 2] {
+    [(); 2].map(|_| {
+        gpu.device()
+            .create_texture(&wgpu::TextureDescriptor {
+                label: Some("temporal_aa_history"),
+                size: wgpu::Extent3d {
+                    width: size.x,
+                    height: size.y,
+                    depth_or_array_layers: 1,
+                },
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: wgpu::TextureDimension::D2,
+                format: renderer::HISTORY_FORMAT,
+                usage: wgpu::TextureUsages::RENDER_ATTACHMENT
+                    | wgpu::TextureUsages::TEXTURE_BINDING,
+                view_formats: &[],
+            })
+            .create_view(&Default::default())
+    })
+}
@@ -0,0 +1,169 @@
+// Resolves temporal anti-aliasing, see `temporal_aa.rs`.
+
+struct Settings {
+    // Where the camera is located (`xyz`) and the far plane (`w`)
+    camera: vec4<f32>,
+
+    // Orientation of the camera, same as `look_at()`
+    orientation: mat3x3<f32>,
+
+    // Where the camera was located during the previous frame (`xyz`) and the
+    // pixel's footprint (`w`, same as `Params::pixel_footprint()`)
+    prev_camera: vec4<f32>,
+
+    // Orientation of the camera during the previous frame
+    prev_orientation: mat3x3<f32>,
+
+    // Same as `Params::frustum`
+    frustum: vec4<f32>,
+
+    // Same as `Params::taa`, except for `w`, which is non-zero when there's
+    // any history to blend with
+    taa: vec4<f32>,
+}
+
+struct Output {
+    @location(0) color: vec4<f32>,
+    @location(1) history: vec4<f32>,
+}
+
+// Current frame, rendered with jitter
+@group(0) @binding(0)
+var frame: texture_2d<f32>;
+
+// Current frame's AOV
+@group(0) @binding(1)
+var aov: texture_2d<f32>;
+
+// Previous frame's AOV
+@group(0) @binding(2)
+var prev_aov: texture_2d<f32>;
+
+// Frames accumulated so far
+@group(0) @binding(3)
+var history: texture_2d<f32>;
+
+@group(0) @binding(4)
+var<uniform> settings: Settings;
+
+// Full-screen triangle, same as in the main shader
+@vertex
+fn main_vs(@builtin(vertex_index) id: u32) -> @builtin(position) vec4<f32> {
+    let uv = vec2<f32>(f32((id << 1u) & 2u), f32(id & 2u));
+
+    return vec4<f32>(uv.x * 2.0 - 1.0, 1.0 - uv.y * 2.0, 0.0, 1.0);
+}
+
+fn fetch_frame(pos: vec2<i32>) -> vec4<f32> {
+    let size = vec2<i32>(textureDimensions(frame));
+
+    return textureLoad(frame, clamp(pos, vec2<i32>(0), size - 1), 0);
+}
+
+fn fetch_history(pos: vec2<i32>) -> vec4<f32> {
+    let size = vec2<i32>(textureDimensions(history));
+
+    return textureLoad(history, clamp(pos, vec2<i32>(0), size - 1), 0);
+}
+
+// Reads the history at given (fractional) position, interpolating between the
+// pixels - the texture isn't filterable, so it's done by hand
+fn sample_history(pos: vec2<f32>) -> vec4<f32> {
+    let pos = pos - 0.5;
+    let p0 = vec2<i32>(floor(pos));
+    let t = fract(pos);
+
+    let top = mix(fetch_history(p0), fetch_history(p0 + vec2<i32>(1, 0)), t.x);
+    let bottom = mix(fetch_history(p0 + vec2<i32>(0, 1)), fetch_history(p0 + vec2<i32>(1, 1)), t.x);
+
+    return mix(top, bottom, t.y);
+}
+
+// Returns where the previous frame has seen the surface rendered at given
+// pixel (in pixels), or `-1` if it hasn't - same as `project_prev()` and
+// `seen_previously()` in the main shader.
+fn reproject(pos: vec2<f32>) -> vec2<f32> {
+    let NOT_SEEN = vec2<f32>(-1.0);
+
+    let size = vec2<f32>(textureDimensions(frame));
+    let distance = textureLoad(aov, vec2<i32>(pos), 0).w;
+
+    // (not rendered during this frame)
+    if distance < 0.0 {
+        return NOT_SEEN;
+    }
+
+    // Same as `Params::frustum_uv()` and `ray_direction()`
+    let frustum_min = settings.frustum.xy;
+    let frustum_size = settings.frustum.zw - settings.frustum.xy;
+    let screen = frustum_min + (pos + settings.taa.yz) / size * frustum_size;
+    let direction = normalize(settings.orientation * vec3<f32>(screen.x, -screen.y, 1.0));
+    let point = settings.camera.xyz + direction * distance;
+
+    let local = transpose(settings.prev_orientation) * (point - settings.prev_camera.xyz);
+
+    if local.z <= 0.0 {
+        return NOT_SEEN;
+    }
+
+    let prev_screen = vec2<f32>(local.x, -local.y) / local.z;
+    let prev_pos = (prev_screen - frustum_min) / frustum_size * size;
+
+    if any(prev_pos < vec2<f32>(0.0)) || any(prev_pos >= size) {
+        return NOT_SEEN;
+    }
+
+    let far = settings.camera.w;
+    let prev_distance = textureLoad(prev_aov, vec2<i32>(prev_pos), 0).w;
+
+    // (background past the far plane)
+    if distance >= far {
+        return select(NOT_SEEN, prev_pos, prev_distance >= far);
+    }
+
+    let expected = length(point - settings.prev_camera.xyz);
+
+    // (AOV is stored in half-precision, hence the tolerance)
+    let seen = prev_distance >= 0.0
+        && abs(prev_distance - expected) < expected * (0.01 + 2.0 * settings.prev_camera.w);
+
+    return select(NOT_SEEN, prev_pos, seen);
+}
+
+@fragment
+fn main_fs(@builtin(position) pos: vec4<f32>) -> Output {
+    let pixel = vec2<i32>(pos.xy);
+    let color = fetch_frame(pixel);
+
+    var resolved = color;
+
+    if settings.taa.w != 0.0 {
+        let prev_pos = reproject(pos.xy);
+
+        if prev_pos.x >= 0.0 {
+            // Besides moving on the screen, the surface might've changed on
+            // its own (e.g. due to animated lighting), which the depth check
+            // doesn't catch - so let's keep the history within the range of
+            // colors the pixel's neighbourhood has in the current frame; that
+            // rejects whatever's no longer there, while still letting the
+            // edges (whose neighbourhood contains both sides) get smoothed
+            var lo = color;
+            var hi = color;
+
+            for (var y = -1; y <= 1; y++) {
+                for (var x = -1; x <= 1; x++) {
+                    let neighbour = fetch_frame(pixel + vec2<i32>(x, y));
+
+                    lo = min(lo, neighbour);
+                    hi = max(hi, neighbour);
+                }
+            }
+
+            let prev_color = clamp(sample_history(prev_pos), lo, hi);
+
+            resolved = mix(prev_color, color, settings.taa.x);
+        }
+    }
+
+    return Output(resolved, resolved);
+}
@@ -0,0 +1,374 @@
+use crate::gpu::Gpu;
+
+/// Size of a single character, in pixels, including the spacing between
+/// characters and lines.
+const CELL_WIDTH: u32 = 6;
+const CELL_HEIGHT: u32 = 9;
+
+/// Space between the text and the edges of its background, in pixels.
+const MARGIN: u32 = 4;
+
+/// How many times the text is magnified on the screen, if the window is
+/// large enough (otherwise it's shown as-is).
+const SCALE: u32 = 2;
+
+const FORMAT: wgpu::TextureFormat =
+    wgpu::TextureFormat::Rgba8UnormSrgb;
+
+/// Block of text drawn in the top-left corner of the window - used by the help
+/// overlay and the command palette.
+///
+/// Text is rasterized on the CPU, using a tiny built-in font, and uploaded as a
+/// texture - but only when it changes, so showing it is pretty much free.
+#[derive(Debug)]
+pub struct Text {
+    pipeline: wgpu::RenderPipeline,
+    bind_group_layout: wgpu::BindGroupLayout,
+
+    /// Texture with the rasterized text, sized to fit it.
+    texture: wgpu::Texture,
+    bind_group: wgpu::BindGroup,
+
+    /// Text that's currently in `texture`.
+    text: Vec<String>,
+}
+
+impl Text {
+    pub fn new(gpu: &Gpu) -> Self {
+        let device = gpu.device();
+
+        let module = device.create_shader_module(
+            wgpu::ShaderModuleDescriptor {
+                label: Some("text_shader"),
+                source: wgpu::ShaderSource::Wgsl(
+                    include_str!("text.wgsl").into(),
+                ),
+            },
+        );
+
+        let bind_group_layout = device
+            .create_bind_group_layout(
+            &wgpu::BindGroupLayoutDescriptor {
+                label: Some("text_bind_group_layout"),
+                entries: &[wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility:
+                        wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type:
+                            wgpu::TextureSampleType::Float {
+                                filterable: false,
+                            },
+                        view_dimension:
+                            wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                }],
+            },
+        );
+
+        let pipeline_layout = device
+            .create_pipeline_layout(
+                &wgpu::PipelineLayoutDescriptor {
+                    label: Some("text_pipeline_layout"),
+                    bind_group_layouts: &[
+                        &bind_group_layout,
+                    ],
+                    push_constant_ranges: &[],
+                },
+            );
+
+        let pipeline = device.create_render_pipeline(
+            &wgpu::RenderPipelineDescriptor {
+                label: Some("text_pipeline"),
+                layout: Some(&pipeline_layout),
+                vertex: wgpu::VertexState {
+                    module: &module,
+                    entry_point: "main_vs",
+                    buffers: &[],
+                },
+                primitive: wgpu::PrimitiveState::default(),
+                depth_stencil: None,
+                multisample: wgpu::MultisampleState::default(),
+                fragment: Some(wgpu::FragmentState {
+                    module: &module,
+                    entry_point: "main_fs",
+                    targets: &[Some(wgpu::ColorTargetState {
+                        format: gpu.format(),
+                        blend: Some(
+                            wgpu::BlendState::ALPHA_BLENDING,
+                        ),
+                        write_mask: wgpu::ColorWrites::ALL,
+                    })],
+                }),
+                multiview: None,
+            },
+        );
+
+        let (texture, bind_group) =
+            create_texture(gpu, &bind_group_layout, 1, 1);
+
+        Self {
+            pipeline,
+            bind_group_layout,
+            texture,
+            bind_group,
+            text: Vec::new(),
+        }
+    }
+
+    /// Recreates the text on given GPU - used after the previous GPU has
+    /// been lost.
+    pub fn recreate(&mut self, gpu: &Gpu) {
+        *self = Self::new(gpu);
+    }
+
+    /// Draws given lines over whatever the target already contains.
+    pub fn render(
+        &mut self,
+        gpu: &Gpu,
+        encoder: &mut wgpu::CommandEncoder,
+        target: &wgpu::TextureView,
+        text: Vec<String>,
+        width: u32,
+        height: u32,
+    ) {
+        if text != self.text {
+            self.upload(gpu, &text);
+            self.text = text;
+        }
+
+        let size = self.texture.size();
+
+        let scale = if size.width * SCALE <= width
+            && size.height * SCALE <= height
+        {
+            SCALE
+        } else {
+            1
+        };
+
+        let mut pass = encoder.begin_render_pass(
+            &wgpu::RenderPassDescriptor {
+                label: Some("text_render_pass"),
+                color_attachments: &[Some(
+                    wgpu::RenderPassColorAttachment {
+                        view: target,
+                        resolve_target: None,
+                        ops: wgpu::Operations {
+                            load: wgpu::LoadOp::Load,
+                            store: true,
+                        },
+                    },
+                )],
+                depth_stencil_attachment: None,
+            },
+        );
+
+        pass.set_pipeline(&self.pipeline);
+        pass.set_bind_group(0, &self.bind_group, &[]);
+
+        pass.set_viewport(
+            0.0,
+            0.0,
+            (size.width * scale).min(width) as f32,
+            (size.height * scale).min(height) as f32,
+            0.0,
+            1.0,
+        );
+
+        pass.draw(0..3, 0..1);
+    }
+
+    /// Rasterizes given text and uploads it into `texture`, resizing the
+    /// texture if needed.
+    fn upload(&mut self, gpu: &Gpu, text: &[String]) {
+        let columns = text
+            .iter()
+            .map(|line| line.chars().count())
+            .max()
+            .unwrap_or(0) as u32;
+
+        let width = columns * CELL_WIDTH + 2 * MARGIN;
+        let height =
+            text.len() as u32 * CELL_HEIGHT + 2 * MARGIN;
+        let size = self.texture.size();
+
+        if (size.width, size.height) != (width, height) {
+            (self.texture, self.bind_group) =
+                create_texture(
+                    gpu,
+                    &self.bind_group_layout,
+                    width,
+                    height,
+                );
+        }
+
+        gpu.queue().write_texture(
+            self.texture.as_image_copy(),
+            &rasterize(text, width, height),
+            wgpu::ImageDataLayout {
+                offset: 0,
+                bytes_per_row: Some(4 * width),
+                rows_per_image: Some(height),
+            },
+            self.texture.size(),
+        );
+    }
+}
+
+fn create_texture(
+    gpu: &Gpu,
+    bind_group_layout: &wgpu::BindGroupLayout,
+    width: u32,
+    height: u32,
+) -> (wgpu::Texture, wgpu::BindGroup) {
+    let texture = gpu.device().create_texture(
+        &wgpu::TextureDescriptor {
+            label: Some("text_texture"),
+            size: wgpu::Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: FORMAT,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING
+                | wgpu::TextureUsages::COPY_DST,
+            view_formats: &[],
+        },
+    );
+
+    let bind_group = gpu.device().create_bind_group(
+        &wgpu::BindGroupDescriptor {
+            label: Some("text_bind_group"),
+            layout: bind_group_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource:
+                    wgpu::BindingResource::TextureView(
+                        &texture.create_view(
+                            &Default::default(),
+                        ),
+                    ),
+            }],
+        },
+    );
+
+    (texture, bind_group)
+}
+
+/// Rasterizes given text into an RGBA image of given size - white letters on a
+/// translucent black background.
+fn rasterize(
+    text: &[String],
+    width: u32,
+    height: u32,
+) -> Vec<u8> {
+    let mut pixels =
+        [0, 0, 0, 160].repeat((width * height) as usize);
+
+    for (row, line) in text.iter().enumerate() {
+        for (column, ch) in line.chars().enumerate() {
+            let glyph = glyph(ch);
+            let x0 = MARGIN + column as u32 * CELL_WIDTH;
+            let y0 = MARGIN + row as u32 * CELL_HEIGHT + 1;
+
+            for (y, bits) in glyph.iter().enumerate() {
+                for x in 0..5 {
+                    if bits & (0b10000 >> x) == 0 {
+                        continue;
+                    }
+
+                    let offset = 4
+                        * ((y0 + y as u32) * width + x0 + x)
+                            as usize;
+
+                    pixels[offset..offset + 4]
+                        .copy_from_slice(&[255; 4]);
+                }
+            }
+        }
+    }
+
+    pixels
+}
+
+/// Returns rows of given character (5x7, the most significant bit being the
+/// leftmost pixel); the font has no lowercase letters, so those get shown as
+/// uppercase ones, and characters missing from it are shown as `?`.
+pub fn glyph(ch: char) -> [u8; 7] {
+    let ch = ch.to_ascii_uppercase();
+
+    let find = |ch| FONT.iter().find(|(c, _)| *c == ch);
+
+    find(ch)
+        .or_else(|| find('?'))
+        .map(|(_, glyph)| *glyph)
+        .unwrap_or_default()
+}
+
+#[rustfmt::skip]
+const FONT: &[(char, [u8; 7])] = &[
+    (' ', [0b00000, 0b00000, 0b00000, 0b00000, 0b00000, 0b00000, 0b00000]),
+    ('A', [0b01110, 0b10001, 0b10001, 0b11111, 0b10001, 0b10001, 0b10001]),
+    ('B', [0b11110, 0b10001, 0b10001, 0b11110, 0b10001, 0b10001, 0b11110]),
+    ('C', [0b01110, 0b10001, 0b10000, 0b10000, 0b10000, 0b10001, 0b01110]),
+    ('D', [0b11100, 0b10010, 0b10001, 0b10001, 0b10001, 0b10010, 0b11100]),
+    ('E', [0b11111, 0b10000, 0b10000, 0b11110, 0b10000, 0b10000, 0b11111]),
+    ('F', [0b11111, 0b10000, 0b10000, 0b11110, 0b10000, 0b10000, 0b10000]),
+    ('G', [0b01110, 0b10001, 0b10000, 0b10111, 0b10001, 0b10001, 0b01111]),
+    ('H', [0b10001, 0b10001, 0b10001, 0b11111, 0b10001, 0b10001, 0b10001]),
+    ('I', [0b01110, 0b00100, 0b00100, 0b00100, 0b00100, 0b00100, 0b01110]),
+    ('J', [0b00111, 0b00010, 0b00010, 0b00010, 0b00010, 0b10010, 0b01100]),
+    ('K', [0b10001, 0b10010, 0b10100, 0b11000, 0b10100, 0b10010, 0b10001]),
+    ('L', [0b10000, 0b10000, 0b10000, 0b10000, 0b10000, 0b10000, 0b11111]),
+    ('M', [0b10001, 0b11011, 0b10101, 0b10101, 0b10001, 0b10001, 0b10001]),
+    ('N', [0b10001, 0b10001, 0b11001, 0b10101, 0b10011, 0b10001, 0b10001]),
+    ('O', [0b01110, 0b10001, 0b10001, 0b10001, 0b10001, 0b10001, 0b01110]),
+    ('P', [0b11110, 0b10001, 0b10001, 0b11110, 0b10000, 0b10000, 0b10000]),
+    ('Q', [0b01110, 0b10001, 0b10001, 0b10001, 0b10101, 0b10010, 0b01101]),
+    ('R', [0b11110, 0b10001, 0b10001, 0b11110, 0b10100, 0b10010, 0b10001]),
+    ('S', [0b01111, 0b10000, 0b10000, 0b01110, 0b00001, 0b00001, 0b11110]),
+    ('T', [0b11111, 0b00100, 0b00100, 0b00100, 0b00100, 0b00100, 0b00100]),
+    ('U', [0b10001, 0b10001, 0b10001, 0b10001, 0b10001, 0b10001, 0b01110]),
+    ('V', [0b10001, 0b10001, 0b10001, 0b10001, 0b10001, 0b01010, 0b00100]),
+    ('W', [0b10001, 0b10001, 0b10001, 0b10101, 0b10101, 0b10101, 0b01010]),
+    ('X', [0b10001, 0b10001, 0b01010, 0b00100, 0b01010, 0b10001, 0b10001]),
+    ('Y', [0b10001, 0b10001, 0b10001, 0b01010, 0b00100, 0b00100, 0b00100]),
+    ('Z', [0b11111, 0b00001, 0b00010, 0b00100, 0b01000, 0b10000, 0b11111]),
+    ('0', [0b01110, 0b10001, 0b10011, 0b10101, 0b11001, 0b10001, 0b01110]),
+    ('1', [0b00100, 0b01100, 0b00100, 0b00100, 0b00100, 0b00100, 0b01110]),
+    ('2', [0b01110, 0b10001, 0b00001, 0b00010, 0b00100, 0b01000, 0b11111]),
+    ('3', [0b11111, 0b00010, 0b00100, 0b00010, 0b00001, 0b10001, 0b01110]),
+    ('4', [0b00010, 0b00110, 0b01010, 0b10010, 0b11111, 0b00010, 0b00010]),
+    ('5', [0b11111, 0b10000, 0b11110, 0b00001, 0b00001, 0b10001, 0b01110]),
+    ('6', [0b00110, 0b01000, 0b10000, 0b11110, 0b10001, 0b10001, 0b01110]),
+    ('7', [0b11111, 0b00001, 0b00010, 0b00100, 0b01000, 0b01000, 0b01000]),
+    ('8', [0b01110, 0b10001, 0b10001, 0b01110, 0b10001, 0b10001, 0b01110]),
+    ('9', [0b01110, 0b10001, 0b10001, 0b01111, 0b00001, 0b00010, 0b01100]),
+    ('.', [0b00000, 0b00000, 0b00000, 0b00000, 0b00000, 0b01100, 0b01100]),
+    (',', [0b00000, 0b00000, 0b00000, 0b00000, 0b01100, 0b00100, 0b01000]),
+    (':', [0b00000, 0b01100, 0b01100, 0b00000, 0b01100, 0b01100, 0b00000]),
+    (';', [0b00000, 0b01100, 0b01100, 0b00000, 0b01100, 0b00100, 0b01000]),
+    ('-', [0b00000, 0b00000, 0b00000, 0b11111, 0b00000, 0b00000, 0b00000]),
+    ('_', [0b00000, 0b00000, 0b00000, 0b00000, 0b00000, 0b00000, 0b11111]),
+    ('=', [0b00000, 0b00000, 0b11111, 0b00000, 0b11111, 0b00000, 0b00000]),
+    ('+', [0b00000, 0b00100, 0b00100, 0b11111, 0b00100, 0b00100, 0b00000]),
+    ('*', [0b00000, 0b00100, 0b10101, 0b01110, 0b10101, 0b00100, 0b00000]),
+    ('/', [0b00000, 0b00001, 0b00010, 0b00100, 0b01000, 0b10000, 0b00000]),
+    ('\\', [0b00000, 0b10000, 0b01000, 0b00100, 0b00010, 0b00001, 0b00000]),
+    ('[', [0b01110, 0b01000, 0b01000, 0b01000, 0b01000, 0b01000, 0b01110]),
+    (']', [0b01110, 0b00010, 0b00010, 0b00010, 0b00010, 0b00010, 0b01110]),
+    ('(', [0b00010, 0b00100, 0b01000, 0b01000, 0b01000, 0b00100, 0b00010]),
+    (')', [0b01000, 0b00100, 0b00010, 0b00010, 0b00010, 0b00100, 0b01000]),
+    ('<', [0b00010, 0b00100, 0b01000, 0b10000, 0b01000, 0b00100, 0b00010]),
+    ('>', [0b01000, 0b00100, 0b00010, 0b00001, 0b00010, 0b00100, 0b01000]),
+    ('%', [0b11000, 0b11001, 0b00010, 0b00100, 0b01000, 0b10011, 0b00011]),
+    ('\'', [0b01100, 0b00100, 0b01000, 0b00000, 0b00000, 0b00000, 0b00000]),
+    ('`', [0b01000, 0b00100, 0b00010, 0b00000, 0b00000, 0b00000, 0b00000]),
+    ('?', [0b01110, 0b10001, 0b00001, 0b00010, 0b00100, 0b00000, 0b00100]),
+];
@@ -0,0 +1,30 @@
+// Draws a block of text, see `text.rs`.
+
+struct VertexOutput {
+    @builtin(position) pos: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+}
+
+@group(0) @binding(0)
+var text: texture_2d<f32>;
+
+// Full-screen triangle, same as in the main shader - the viewport takes care of
+// putting it in the corner
+@vertex
+fn main_vs(@builtin(vertex_index) id: u32) -> VertexOutput {
+    let uv = vec2<f32>(f32((id << 1u) & 2u), f32(id & 2u));
+
+    var out: VertexOutput;
+    out.pos = vec4<f32>(uv.x * 2.0 - 1.0, 1.0 - uv.y * 2.0, 0.0, 1.0);
+    out.uv = uv;
+    return out;
+}
+
+// Nearest-neighbor, so that the letters stay crisp when magnified
+@fragment
+fn main_fs(in: VertexOutput) -> @location(0) vec4<f32> {
+    let size = textureDimensions(text);
+    let pos = min(vec2<u32>(in.uv * vec2<f32>(size)), size - 1u);
+
+    return textureLoad(text, pos, 0);
+}
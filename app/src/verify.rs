@@ -0,0 +1,256 @@
+//! Verification mode (`--verify`) - renders each scene on the CPU (using the
+//! shader's `reference()` renderer, so no GPU is needed), hashes the images and
+//! compares the hashes with the ones stored in `app/verify.hashes`.
+//!
+//! This gives regression coverage that doesn't depend on GPU drivers - handy
+//! for CI; when a scene is supposed to change, run `--verify --update` to
+//! store the new hashes.
+//!
+//! Note that functions such as `sin()` can round differently on different
+//! platforms, so the hashes are best compared on the same platform they have
+//! been stored on.
+//!
+//! `--verify --f16` is a related experiment - instead of hashing, it compares
+//! each scene rendered in full and in (emulated) half precision, to see how
+//! much quality marching in `f16` would cost.
+
+use sdf_playground_common::glam::{
+    uvec2, vec2, Vec2, Vec4,
+};
+use sdf_playground_common::quality;
+use sdf_playground_common::{Params, FRUSTUM, SCENES};
+use std::collections::BTreeMap;
+use std::hash::Hasher;
+use std::path::{Path, PathBuf};
+use std::{fs, process, thread};
+
+pub const WIDTH: u32 = 160;
+pub const HEIGHT: u32 = 120;
+
+/// Moment at which all scenes are rendered - fixed, so that the images (and
+/// thus their hashes) are always the same.
+const TIME: f32 = 2.0;
+
+pub fn run(update: bool) {
+    let hashes: BTreeMap<u32, u64> = (1..=SCENES)
+        .map(|scene| {
+            let pixels = render(
+                scene,
+                sdf_playground_shader::reference,
+            );
+
+            (scene, hash(&pixels))
+        })
+        .collect();
+
+    if update {
+        let hashes: String = hashes
+            .iter()
+            .map(|(scene, hash)| {
+                format!("{scene} {hash:016x}\n")
+            })
+            .collect();
+
+        fs::write(path(), hashes).unwrap_or_else(|err| {
+            eprintln!(
+                "Couldn't write {}: {err}",
+                path().display()
+            );
+            process::exit(1);
+        });
+
+        println!("Hashes stored in {}", path().display());
+        return;
+    }
+
+    let Ok(expected) = fs::read_to_string(path()) else {
+        eprintln!(
+            "Couldn't read {} - run with `--verify --update` to create it",
+            path().display()
+        );
+        process::exit(1);
+    };
+
+    let expected: BTreeMap<u32, u64> = expected
+        .lines()
+        .filter_map(|line| {
+            let (scene, hash) = line.split_once(' ')?;
+
+            Some((
+                scene.parse().ok()?,
+                u64::from_str_radix(hash, 16).ok()?,
+            ))
+        })
+        .collect();
+
+    let mut failed = false;
+
+    for (scene, hash) in &hashes {
+        let status = match expected.get(scene) {
+            Some(expected) if expected == hash => "ok",
+            Some(_) => "MISMATCH",
+            None => "MISSING",
+        };
+
+        println!("scene {scene}: {hash:016x} ... {status}");
+
+        failed |= status != "ok";
+    }
+
+    if failed {
+        eprintln!(
+            "Some scenes don't look as they used to - if that's expected, \
+             run with `--verify --update`"
+        );
+        process::exit(1);
+    }
+}
+
+/// Renders each scene in both full and half precision (see
+/// `reference_half()` in the shader) and prints how much the images differ.
+pub fn run_half() {
+    println!(
+        "scene  mean diff  max diff  differing pixels"
+    );
+
+    for scene in 1..=SCENES {
+        let full =
+            render(scene, sdf_playground_shader::reference);
+
+        let half = render(
+            scene,
+            sdf_playground_shader::reference_half,
+        );
+
+        let diffs: Vec<_> = full
+            .iter()
+            .zip(&half)
+            .map(|(full, half)| full.abs_diff(*half))
+            .collect();
+
+        let mean = diffs
+            .iter()
+            .map(|&diff| diff as f32)
+            .sum::<f32>()
+            / diffs.len() as f32;
+
+        let max = diffs.iter().copied().max().unwrap_or(0);
+
+        let differing = full
+            .chunks_exact(4)
+            .zip(half.chunks_exact(4))
+            .filter(|(full, half)| full != half)
+            .count();
+
+        println!(
+            "{scene:>5}  {mean:>9.3}  {max:>8}  {:>15.1}%",
+            100.0 * differing as f32
+                / (WIDTH * HEIGHT) as f32
+        );
+    }
+}
+
+/// Path of the file with expected hashes.
+fn path() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("verify.hashes")
+}
+
+/// Renders given scene with given renderer (see `reference()` in the shader),
+/// returning its pixels as RGBA.
+pub fn render(
+    scene: u32,
+    renderer: fn(&Params, Vec2) -> Vec4,
+) -> Vec<u8> {
+    let mut params = Params {
+        width: WIDTH,
+        height: HEIGHT,
+        time: TIME,
+        scene,
+        far: 100.0,
+        frustum: FRUSTUM,
+        ..Default::default()
+    };
+
+    params.set_quality(quality::HIGH);
+
+    let mut pixels = vec![0; (4 * WIDTH * HEIGHT) as usize];
+
+    // Rendering on the CPU is rather slow, so let's use all the cores we've
+    // got - each thread gets a couple of rows
+    let threads = thread::available_parallelism()
+        .map_or(1, |threads| threads.get());
+
+    let rows = (HEIGHT as usize + threads - 1) / threads;
+
+    thread::scope(|scope| {
+        for (chunk_idx, chunk) in pixels
+            .chunks_mut(4 * WIDTH as usize * rows)
+            .enumerate()
+        {
+            scope.spawn(move || {
+                for (idx, pixel) in
+                    chunk.chunks_exact_mut(4).enumerate()
+                {
+                    let idx =
+                        chunk_idx * WIDTH as usize * rows
+                            + idx;
+
+                    let pos = uvec2(
+                        idx as u32 % WIDTH,
+                        idx as u32 / WIDTH,
+                    );
+
+                    let color = renderer(
+                        &params,
+                        pos.as_vec2() + vec2(0.5, 0.5),
+                    );
+
+                    for (channel, value) in pixel
+                        .iter_mut()
+                        .zip(color.to_array())
+                    {
+                        *channel = (value.clamp(0.0, 1.0)
+                            * 255.0)
+                            .round()
+                            as u8;
+                    }
+                }
+            });
+        }
+    });
+
+    pixels
+}
+
+fn hash(bytes: &[u8]) -> u64 {
+    let mut hasher = Fnv1a::default();
+
+    hasher.write(bytes);
+    hasher.finish()
+}
+
+/// FNV-1a - unlike `DefaultHasher`, it's guaranteed to return the same hash
+/// on every platform and with every version of Rust (as long as it's fed with
+/// [`Hasher::write()`] - the other methods use the platform's endianness).
+#[derive(Clone, Copy, Debug)]
+pub struct Fnv1a(u64);
+
+impl Default for Fnv1a {
+    fn default() -> Self {
+        Self(0xcbf29ce484222325)
+    }
+}
+
+impl Hasher for Fnv1a {
+    fn write(&mut self, bytes: &[u8]) {
+        self.0 = bytes.iter().fold(self.0, |hash, byte| {
+            (hash ^ *byte as u64)
+                .wrapping_mul(0x100000001b3)
+        });
+    }
+
+    fn finish(&self) -> u64 {
+        self.0
+    }
+}
@@ -0,0 +1,64 @@
+//! Video wall mode (`--span`) - a single borderless window spanning all the
+//! monitors, with the image plane stretched over the entire wall so that the
+//! scene continues seamlessly from one monitor into another.
+
+use sdf_playground_common::glam::{vec4, Vec4};
+use winit::dpi::{PhysicalPosition, PhysicalSize};
+use winit::event_loop::EventLoop;
+use winit::window::{Fullscreen, WindowBuilder};
+
+/// Returns a borderless window covering all the monitors.
+pub fn window(event_loop: &EventLoop<()>) -> WindowBuilder {
+    let mut min = PhysicalPosition::new(i32::MAX, i32::MAX);
+    let mut max = PhysicalPosition::new(i32::MIN, i32::MIN);
+
+    for monitor in event_loop.available_monitors() {
+        let position = monitor.position();
+        let size = monitor.size();
+
+        min.x = min.x.min(position.x);
+        min.y = min.y.min(position.y);
+        max.x = max.x.max(position.x + size.width as i32);
+        max.y = max.y.max(position.y + size.height as i32);
+    }
+
+    let builder = WindowBuilder::new()
+        .with_title("sdf-playground")
+        .with_decorations(false);
+
+    if min.x < max.x && min.y < max.y {
+        // (if the monitors don't form a rectangle, the parts of the window
+        // that don't land on any monitor simply don't get shown, so the scene
+        // stays aligned with how the monitors are physically arranged)
+        builder.with_position(min).with_inner_size(
+            PhysicalSize::new(
+                (max.x - min.x) as u32,
+                (max.y - min.y) as u32,
+            ),
+        )
+    } else {
+        // No monitors reported (e.g. on Wayland, which doesn't tell us where
+        // they are) - let's at least cover the current one
+        builder.with_fullscreen(Some(
+            Fullscreen::Borderless(None),
+        ))
+    }
+}
+
+/// Returns frustum (see `Params::frustum`) for a wall of given size - it's
+/// the default, square image plane extended along the longer side, so that
+/// the scene keeps its proportions no matter how many monitors there are.
+pub fn frustum(width: u32, height: u32) -> Vec4 {
+    let width = width.max(1) as f32;
+    let height = height.max(1) as f32;
+
+    if width > height {
+        let aspect = width / height;
+
+        vec4(-aspect, -1.0, aspect, 1.0)
+    } else {
+        let aspect = height / width;
+
+        vec4(-1.0, -aspect, 1.0, aspect)
+    }
+}
@@ -0,0 +1,62 @@
+//! Wallpaper mode (`--wallpaper`) - a window spanning all the monitors that
+//! stays below all the other windows, turning the scene into a live wallpaper.
+//!
+//! Only X11 is supported - there the window is marked as the desktop, so
+//! window managers treat it just like a regular wallpaper. Wayland has no way
+//! for clients to do that, and on Windows a real wallpaper has to be parented
+//! to the desktop's `WorkerW` window (which winit doesn't support), so on both
+//! the window is just kept at the bottom - covering the desktop's icons.
+
+use crate::wall;
+use log::warn;
+use std::time::Duration;
+use winit::event_loop::EventLoop;
+use winit::window::{WindowBuilder, WindowLevel};
+
+/// How often the wallpaper gets rendered - there's no point in keeping the
+/// GPU busy at full speed for something that's mostly covered by other
+/// windows.
+pub const FRAME_TIME: Duration = Duration::from_millis(33);
+
+/// Returns a window spanning all the monitors, staying below other windows.
+pub fn window(event_loop: &EventLoop<()>) -> WindowBuilder {
+    let builder = wall::window(event_loop)
+        .with_window_level(WindowLevel::AlwaysOnBottom)
+        .with_active(false);
+
+    #[cfg(target_os = "linux")]
+    let builder = {
+        use winit::platform::wayland::EventLoopWindowTargetExtWayland;
+        use winit::platform::x11::{
+            WindowBuilderExtX11, XWindowType,
+        };
+
+        if event_loop.is_wayland() {
+            unsupported();
+        }
+
+        builder.with_x11_window_type(vec![
+            XWindowType::Desktop,
+        ])
+    };
+
+    #[cfg(not(target_os = "linux"))]
+    unsupported();
+
+    #[cfg(windows)]
+    let builder = {
+        use winit::platform::windows::WindowBuilderExtWindows;
+
+        builder.with_skip_taskbar(true)
+    };
+
+    builder
+}
+
+/// Warns that the window won't be a real wallpaper on this platform.
+fn unsupported() {
+    warn!(
+        "Wallpaper mode is supported only on X11 - here the window will just \
+         stay below the other ones, covering the desktop's icons"
+    );
+}
@@ -3,30 +3,917 @@
 use bytemuck::*;
 use glam::*;
 
+pub use glam;
+
 #[repr(C)]
 #[derive(Clone, Copy, Default, Pod, Zeroable)]
+#[cfg_attr(not(target_arch = "spirv"), derive(Debug))]
 pub struct Params {
     pub width: u32,
     pub height: u32,
     pub time: f32,
+    pub debug_view: u32,
+    pub stereo: u32,
+
+    /// Distance between eyes, for [`Self::stereo`].
+    pub eye_separation: f32,
+
+    /// For how long (in seconds) the virtual camera's shutter stays open -
+    /// when non-zero, the shader averages a couple of samples from the
+    /// `time - shutter ..= time` range, producing motion blur.
+    pub shutter: f32,
+
+    /// Which scene to render, `1..=SCENES`.
+    pub scene: u32,
+
+    /// When non-zero, the scene is first marched at `1 / coarse` of the
+    /// resolution and the full-resolution rays start from where the coarse
+    /// ones have stopped, which saves lots of steps on large, smooth surfaces.
+    ///
+    /// Only used when [`Self::coarse_enabled()`].
+    pub coarse: u32,
+
+    /// When non-zero, each frame renders only half of the pixels (in a
+    /// checkerboard pattern of [`CHECKERBOARD_TILE`]-sized tiles, alternating
+    /// between frames) and reuses the previous frame for the other half.
+    pub checkerboard: u32,
+
+    /// Number of the current frame; wraps around on overflow.
+    pub frame: u32,
+
+    /// How far from the camera rays start marching - everything closer is
+    /// clipped away.
+    pub near: f32,
+
+    /// How far from the camera rays stop marching - everything farther is
+    /// considered background.
+    ///
+    /// Large scenes (terrains, planets) need this to be large, tiny ones can
+    /// make it small to avoid wasting steps on the empty space around them.
+    pub far: f32,
+
+    /// When non-zero, instead of the current scene a grid of thumbnails of
+    /// all scenes is rendered (see [`Self::gallery_scene_at()`]).
+    pub gallery: u32,
+
+    /// Angle (in radians) by which the camera is rotated around the Y axis.
+    pub orbit: f32,
+
+    /// Scene that's being faded out, see [`Self::crossfade`].
+    pub prev_scene: u32,
+
+    /// How much of [`Self::prev_scene`] is still visible (`0..=1`) - when
+    /// non-zero, both scenes are rendered and blended together.
+    pub crossfade: f32,
+
+    /// When non-zero, the scene is rendered over a ground plane that's
+    /// invisible except for the shadows cast onto it - for compositing
+    /// renders onto other images.
+    pub shadow_catcher: u32,
+
+    /// Color shown where rays don't hit anything, as linear `0xRRGGBBAA`.
+    ///
+    /// Alpha below `0xff` makes the background see-through, but only when
+    /// the window is transparent (see `--transparent`).
+    pub background: u32,
+
+    /// Strength of the subsurface scattering approximation - when non-zero,
+    /// light shines through thin parts of objects, giving them a wax- or
+    /// jade-like look.
+    pub subsurface: f32,
+
+    /// How surfaces are shaded, see [`shading`].
+    pub shading: u32,
+
+    /// Number of diffuse bands used by [`shading::TOON`].
+    pub toon_bands: u32,
+
+    /// Strength of the rim light used by [`shading::TOON`] - a bright outline
+    /// along the edges of objects, separating them from the background.
+    pub rim: f32,
+
+    /// Width of the rim light, `0..1` - the larger, the further it reaches
+    /// into the object.
+    pub rim_width: f32,
+
+    /// When positive, rays stop marching once they get closer than this to a
+    /// surface - the classic, fixed-epsilon approach, for comparison.
+    ///
+    /// When zero (the default), rays stop once they get closer than the width
+    /// of a pixel at given distance (see [`Self::pixel_footprint()`]) - i.e.
+    /// once the remaining error wouldn't be visible on the screen anyway; that
+    /// takes fewer steps for far-away surfaces and keeps nearby ones crisp.
+    pub epsilon: f32,
+
+    /// Maximum number of steps taken along a single ray before giving up.
+    ///
+    /// See [`quality`] for the presets.
+    pub steps: u32,
+
+    /// Number of steps taken by shadow rays; zero disables shadows.
+    pub shadow_steps: u32,
+
+    /// Anti-aliasing - each pixel is rendered `samples * samples` times, at
+    /// positions spread evenly over its area, and averaged; one disables it.
+    pub samples: u32,
+
+    /// Cutting plane used by [`debug_view::SLICE`]: `xyz` is the plane's
+    /// normal, `w` is its distance from the origin.
+    pub slice: Vec4,
+
+    /// Region of interest, `[x1, y1, x2, y2]` in pixels - when non-empty, only
+    /// pixels inside of it get rendered and the rest of the screen reuses
+    /// whatever was rendered there before.
+    pub roi: Vec4,
+
+    /// Part of the camera's image plane shown in the window, `[x1, y1, x2,
+    /// y2]`, with `x` growing right and `y` growing down - [`FRUSTUM`] shows
+    /// the entire (square) image plane, stretched to the window.
+    ///
+    /// Windows spanning several monitors use it to keep the aspect ratio,
+    /// and a window showing just a part of a larger display would pick the
+    /// corresponding part of the image plane.
+    pub frustum: Vec4,
+
+    /// Pixel probed by the shader (`xy`, in pixels), see [`Probe`] - probing
+    /// is enabled when `z` is non-zero.
+    pub probe: Vec4,
+
+    /// Camera of the previous frame - `x` is its [`Self::orbit`]; when `y` is
+    /// non-zero and the camera has moved since then, pixels skipped by
+    /// [`Self::checkerboard`] get reprojected from where the previous frame
+    /// has seen them, instead of being reused as-is (`zw` are unused).
+    pub prev_camera: Vec4,
+
+    /// Framing of the previous frame's camera, same as [`Self::framing`] -
+    /// together with [`Self::prev_camera`], it tells where the camera was.
+    pub prev_framing: Vec4,
+
+    /// Foveated rendering - `xy` is the point of focus (in pixels), `z` is the
+    /// radius of the circle around it rendered at full resolution; the farther
+    /// away from it, the fewer pixels get rendered (see
+    /// [`Self::fovea_block()`]) and the rest gets filled in by blurring the
+    /// rendered ones. Zero radius disables it.
+    ///
+    /// The lower-resolution parts get rendered by a separate pass, into a
+    /// smaller texture (see [`Self::fovea_atlas_size()`]), so that the
+    /// full-resolution pass can skip their tiles entirely.
+    pub fovea: Vec4,
+
+    /// How [`Self::time`] maps into the time seen by the scene (see
+    /// [`Self::scene_time()`]) - `x` is the curve (see [`time_curve`]), `y` is
+    /// its period in seconds (`zw` are unused).
+    ///
+    /// [`time_curve::PING_PONG`] and [`time_curve::SAWTOOTH`] loop every
+    /// period, which makes it easy to author looping animations (e.g. for
+    /// GIFs) without touching the shader's math; [`time_curve::EASE_IN_OUT`]
+    /// keeps going forward, only slowing down around the ends of each
+    /// period.
+    pub time_curve: Vec4,
+
+    /// Framing of the camera - `xyz` is the point it orbits around and looks
+    /// at, `w` is its distance from that point; zero `w` means the default
+    /// framing, looking at the center of the world.
+    pub framing: Vec4,
+
+    /// Glow around the scene - rays that miss it get lit up depending on how
+    /// close they've got to it, which makes halos around thin structures and
+    /// near misses; `xyz` is the (linear) color, `w` is how far the glow
+    /// reaches, in world units (zero disables it).
+    pub glow: Vec4,
+
+    /// Distance fog, colored by the sky - `x` is its density (zero disables
+    /// it), `y` says how quickly it thins out with height (zero makes it
+    /// uniform), `zw` are unused.
+    pub fog: Vec4,
+
+    /// Post effects, applied by the application once the frame is rendered -
+    /// `x` is the strength of chromatic aberration (how far the colors get
+    /// split apart at the screen's edges, as a fraction of the distance from
+    /// its center), `y` is the strength of film grain (`zw` are unused); zero
+    /// disables given effect.
+    pub post_effects: Vec4,
+
+    /// Temporal anti-aliasing - `x` is how much the current frame contributes
+    /// to the pixel (the rest comes from the history; zero disables TAA), `yz`
+    /// is this frame's jitter (sub-pixel offset of the rays, in pixels, set by
+    /// the application), `w` is unused.
+    ///
+    /// The shader only applies the jitter - blending with the history happens
+    /// in the application's post pass, which can see the pixel's neighbours.
+    pub taa: Vec4,
+
+    /// Relief mode - when `x` is non-zero, the scene's distances get negated,
+    /// so that the empty space becomes solid and vice versa; this turns the
+    /// insides of objects (e.g. caves in a fractal or holes carved out by CSG
+    /// subtractions) into something that can be looked at and flown through.
+    /// `yzw` are unused.
+    pub relief: Vec4,
+
+    /// Ambient lighting - light reaching surfaces from the whole sky rather
+    /// than straight from the sun, which keeps the shadowed areas from going
+    /// pitch black; `x` is its strength (zero disables it), `yzw` are unused.
+    pub ambient: Vec4,
+
+    /// Values of hot variables (see [`Hot`]) tweaked in the application.
+    pub hot: Hot,
+}
+
+/// Hot variables - literals in the scene's code wrapped in `hot!("name",
+/// default)` (a macro available in the shader's scene functions), which the
+/// application finds in the sources and lets you tweak live, without
+/// recompiling anything.
+///
+/// Only the tweaked variables are stored here, as pairs of their keys (see
+/// [`Self::key()`]) and values - the rest keeps its default, so the shader
+/// works the same when rendered without the application (e.g. in tests).
+#[repr(C)]
+#[derive(Clone, Copy, Default, Pod, Zeroable)]
+#[cfg_attr(not(target_arch = "spirv"), derive(Debug))]
+pub struct Hot {
+    /// Each entry holds two variables: `x` and `z` are the keys (bit-cast
+    /// into floats), `y` and `w` are the values; zero keys mark empty slots.
+    entries: [Vec4; 4],
+}
+
+impl Hot {
+    /// How many variables can be tweaked at once.
+    pub const CAPACITY: usize = 8;
+
+    /// Returns key of variable with given name - a hash of it, arranged so
+    /// that (when bit-cast into a float) it's never zero nor `NaN`, which
+    /// could otherwise get mangled on its way to the GPU.
+    pub const fn key(name: &str) -> u32 {
+        let name = name.as_bytes();
+        let mut hash: u32 = 0x811c9dc5;
+        let mut idx = 0;
+
+        // (FNV-1a - `for` loops aren't allowed in `const fn`s)
+        while idx < name.len() {
+            hash ^= name[idx] as u32;
+            hash = hash.wrapping_mul(0x01000193);
+            idx += 1;
+        }
+
+        // Exponent of `1.0`, i.e. a float from `1.0..2.0`
+        (hash & 0x007f_ffff) | 0x3f80_0000
+    }
+
+    /// Returns value of given variable, or `default` if it's not been
+    /// tweaked.
+    pub fn get(&self, key: u32, default: f32) -> f32 {
+        let key = f32::from_bits(key);
+        let mut idx = 0;
+
+        while idx < self.entries.len() {
+            let entry = self.entries[idx];
+
+            if entry.x == key {
+                return entry.y;
+            }
+
+            if entry.z == key {
+                return entry.w;
+            }
+
+            idx += 1;
+        }
+
+        default
+    }
+
+    /// Sets value of given variable, returning `false` if there's no room
+    /// left for it.
+    pub fn set(&mut self, key: u32, value: f32) -> bool {
+        let key = f32::from_bits(key);
+
+        for entry in &mut self.entries {
+            if entry.x == key || entry.x == 0.0 {
+                entry.x = key;
+                entry.y = value;
+                return true;
+            }
+
+            if entry.z == key || entry.z == 0.0 {
+                entry.z = key;
+                entry.w = value;
+                return true;
+            }
+        }
+
+        false
+    }
 }
 
-pub fn direction(origin: Vec3, uv: Vec2) -> Vec3 {
-    let camera = {
-        let up = vec3(0.0, 1.0, 0.0);
-        let f = -origin.normalize();
-        let s = f.cross(up).normalize();
-        let u = s.cross(f);
+/// Default value for [`Params::frustum`].
+pub const FRUSTUM: Vec4 = Vec4::new(-1.0, -1.0, 1.0, 1.0);
+
+/// Bit set in the scene id passed to the scene's distance field in relief mode,
+/// see [`Params::field()`].
+pub const RELIEF: u32 = 1 << 31;
+
+/// Size of the checkerboard's fields (in pixels), see [`Params::checkerboard`].
+pub const CHECKERBOARD_TILE: u32 = 8;
+
+/// Size of tiles (in pixels) for which [`Params::fovea_block()`] gets decided -
+/// also the largest block it returns, so that the corners of all tiles always
+/// get rendered.
+pub const FOVEA_TILE: u32 = 8;
 
-        Mat3 {
-            x_axis: s,
-            y_axis: u,
-            z_axis: f,
+impl Params {
+    /// Returns parameters the application starts with - shared by the window,
+    /// the benchmark and the loop exporter, so that they all render scenes
+    /// the same way (unlike [`Self::default()`], which zeroes everything,
+    /// including e.g. the far plane).
+    pub fn new(width: u32, height: u32) -> Self {
+        Self {
+            width,
+            height,
+            time: 0.0,
+            debug_view: debug_view::NONE,
+            stereo: stereo::OFF,
+            eye_separation: 0.2,
+            shutter: 0.0,
+            scene: 1,
+            coarse: 0,
+            checkerboard: 0,
+            frame: 0,
+            near: 0.0,
+            far: 100.0,
+            gallery: 0,
+            orbit: 0.0,
+            prev_scene: 0,
+            crossfade: 0.0,
+            shadow_catcher: 0,
+            background: 0x000000ff,
+            subsurface: 0.0,
+            shading: shading::REGULAR,
+            toon_bands: 3,
+            rim: 0.5,
+            rim_width: 0.3,
+            epsilon: 0.0,
+            steps: 64,
+            shadow_steps: 32,
+            samples: 1,
+            slice: vec4(0.0, 0.0, 1.0, 0.0),
+            roi: Vec4::ZERO,
+            frustum: FRUSTUM,
+            probe: Vec4::ZERO,
+            prev_camera: Vec4::ZERO,
+            prev_framing: Vec4::ZERO,
+            fovea: Vec4::ZERO,
+            time_curve: Vec4::ZERO,
+            framing: Vec4::ZERO,
+            glow: vec4(0.3, 0.8, 1.0, 0.0),
+            fog: Vec4::ZERO,
+            post_effects: Vec4::ZERO,
+            taa: Vec4::ZERO,
+            relief: Vec4::ZERO,
+            ambient: vec4(0.3, 0.0, 0.0, 0.0),
+            hot: Hot::default(),
         }
+    }
+
+    /// Whether the coarse pre-pass should be used - it's supported only for
+    /// the regular, single-camera rendering without motion blur.
+    pub fn coarse_enabled(&self) -> bool {
+        self.coarse > 0
+            && self.stereo == stereo::OFF
+            && self.shutter <= 0.0
+            && self.gallery == 0
+    }
+
+    /// Returns how sparsely pixels of the tile containing given pixel get
+    /// rendered with foveated rendering (see [`Self::fovea`]) - only pixels
+    /// lying on a grid with cells this large are rendered, so one means all of
+    /// them, two means every other pixel in each direction etc.
+    ///
+    /// Each ring of [`Self::fovea`]'s radius around the point of focus halves
+    /// the resolution, down to `1 / FOVEA_TILE`.
+    pub fn fovea_block(&self, pixel: UVec2) -> u32 {
+        let radius = self.fovea.z;
+
+        if radius <= 0.0 {
+            return 1;
+        }
+
+        let tile = (pixel / FOVEA_TILE * FOVEA_TILE)
+            .as_vec2()
+            + FOVEA_TILE as f32 * 0.5;
+
+        let level = (tile.distance(self.fovea.xy())
+            / radius) as u32;
+
+        // (up to `FOVEA_TILE`)
+        1 << level.min(3)
+    }
+
+    /// Whether temporal anti-aliasing should be used (see [`Self::taa`]) - it
+    /// needs the previous frame to line up with the current one (modulo the
+    /// camera's movement), so it stays out of the way of effects that render
+    /// anything else.
+    pub fn taa_enabled(&self) -> bool {
+        self.taa.x > 0.0
+            && self.stereo == stereo::OFF
+            && self.crossfade <= 0.0
+            && self.shutter <= 0.0
+            && self.gallery == 0
+    }
+
+    /// Whether foveated rendering should be used - it's not supported in the
+    /// gallery, which lays pixels out its own way.
+    pub fn fovea_enabled(&self) -> bool {
+        self.fovea.z > 0.0 && self.gallery == 0
+    }
+
+    /// Size of the texture foveated rendering renders its lower-resolution
+    /// parts into - it's got a region for each block larger than one (see
+    /// [`Self::fovea_region()`]), laid out like a mipmap chain: the half
+    /// resolution on the left, the quarter and the eighth stacked on its
+    /// right.
+    pub fn fovea_atlas_size(&self) -> UVec2 {
+        let size = |block: u32| {
+            uvec2(
+                (self.width + block - 1) / block,
+                (self.height + block - 1) / block,
+            )
+        };
+
+        uvec2(
+            size(2).x + size(4).x,
+            size(2).y.max(size(4).y + size(8).y),
+        )
+    }
+
+    /// Returns where the region of [`Self::fovea_atlas_size()`] containing
+    /// pixels rendered with given block (two, four or eight) starts - the
+    /// region's texel `xy` is the pixel `xy * block`.
+    pub fn fovea_region(&self, block: u32) -> UVec2 {
+        let half_width = (self.width + 1) / 2;
+        let quarter_height = (self.height + 3) / 4;
+
+        match block {
+            2 => UVec2::ZERO,
+            4 => uvec2(half_width, 0),
+            _ => uvec2(half_width, quarter_height),
+        }
+    }
+
+    /// Returns whether given pixel (lying on the grid of given block) gets
+    /// rendered into [`Self::fovea_region()`] - that's the case when it's a
+    /// corner of any of the grid's cells in tiles with this block, which
+    /// includes the corners shared with the neighbouring tiles.
+    pub fn fovea_renders(
+        &self,
+        pixel: UVec2,
+        block: u32,
+    ) -> bool {
+        let prev = pixel.max(UVec2::ONE) - UVec2::ONE;
+
+        self.fovea_block(pixel) == block
+            || self.fovea_block(uvec2(prev.x, pixel.y))
+                == block
+            || self.fovea_block(uvec2(pixel.x, prev.y))
+                == block
+            || self.fovea_block(prev) == block
+    }
+
+    /// Resolution of the coarse pre-pass.
+    pub fn coarse_size(&self) -> UVec2 {
+        let coarse = self.coarse.max(1);
+
+        uvec2(
+            (self.width + coarse - 1) / coarse,
+            (self.height + coarse - 1) / coarse,
+        )
+    }
+
+    /// Returns [`Self::background`] as a vector, premultiplied by its alpha.
+    ///
+    /// The shader outputs premultiplied colors, since that's what blending
+    /// them (e.g. fog or the shadow catcher over a see-through background)
+    /// and the transparent window's compositor expect.
+    pub fn background_color(&self) -> Vec4 {
+        let channel = |shift: u32| {
+            ((self.background >> shift) & 0xff) as f32
+                / 255.0
+        };
+
+        let alpha = channel(0);
+
+        vec4(
+            channel(24) * alpha,
+            channel(16) * alpha,
+            channel(8) * alpha,
+            alpha,
+        )
+    }
+
+    /// Maps given screen position (`0..1`) into the part of the image plane
+    /// selected by [`Self::frustum`], returning it as a screen position
+    /// understood by [`direction()`].
+    pub fn frustum_uv(&self, uv: Vec2) -> Vec2 {
+        let min = self.frustum.xy();
+        let max = self.frustum.zw();
+
+        (min + uv * (max - min) + 1.0) * 0.5
+    }
+
+    /// Sets [`Self::steps`], [`Self::shadow_steps`] and [`Self::samples`]
+    /// according to given preset (see [`quality`]).
+    pub fn set_quality(&mut self, quality: u32) {
+        let (steps, shadow_steps, samples) = match quality {
+            quality::LOW => (32, 0, 1),
+            quality::MEDIUM => (48, 16, 1),
+            quality::HIGH => (64, 32, 1),
+            _ => (128, 64, 2),
+        };
+
+        self.steps = steps;
+        self.shadow_steps = shadow_steps;
+        self.samples = samples;
+    }
+
+    /// Returns the parameters as they were during the previous frame, as far
+    /// as the camera goes (see [`Self::prev_camera`]) - everything else stays
+    /// as it is.
+    pub fn prev(&self) -> Self {
+        let mut prev = *self;
+
+        prev.orbit = self.prev_camera.x;
+        prev.framing = self.prev_framing;
+        prev
+    }
+
+    /// Returns whether the camera has moved since the previous frame (see
+    /// [`Self::prev_camera`]).
+    pub fn camera_moved(&self) -> bool {
+        self.prev_camera.x != self.orbit
+            || self.prev_framing != self.framing
+    }
+
+    /// Returns the point the camera looks at, see [`Self::framing`].
+    pub fn target(&self) -> Vec3 {
+        if self.framing.w > 0.0 {
+            self.framing.xyz()
+        } else {
+            Vec3::ZERO
+        }
+    }
+
+    /// Returns the scene id to evaluate the distance field with - that's
+    /// [`Self::scene`], with [`RELIEF`] set in relief mode (see
+    /// [`Self::relief`]).
+    ///
+    /// Everything else (materials, backgrounds etc.) should keep using the
+    /// plain [`Self::scene`].
+    pub fn field(&self) -> u32 {
+        if self.relief.x != 0.0 {
+            self.scene | RELIEF
+        } else {
+            self.scene
+        }
+    }
+
+    /// Returns the time seen by the scene, i.e. [`Self::time`] remapped
+    /// through [`Self::time_curve`].
+    pub fn scene_time(&self) -> f32 {
+        let time = self.time;
+        let period = self.time_curve.y;
+
+        if period <= 0.0 {
+            return time;
+        }
+
+        let cycles = time / period;
+        let cycle = floor(cycles);
+        let t = cycles - cycle;
+
+        match self.time_curve.x as u32 {
+            time_curve::EASE_IN_OUT => {
+                (cycle + t * t * (3.0 - 2.0 * t)) * period
+            }
+
+            time_curve::PING_PONG => {
+                (1.0 - (1.0 - 2.0 * t).abs()) * period * 0.5
+            }
+
+            time_curve::SAWTOOTH => t * period,
+
+            _ => time,
+        }
+    }
+
+    /// How wide a single pixel is at the distance of 1.0 from the camera - the
+    /// farther we go, the larger area a pixel covers, forming a cone.
+    pub fn pixel_footprint(&self) -> f32 {
+        let size = self.frustum.zw() - self.frustum.xy();
+
+        (size / vec2(self.width as f32, self.height as f32))
+            .max_element()
+    }
+
+    /// Size of a single thumbnail in the gallery (see [`Self::gallery`]), in
+    /// pixels.
+    pub fn gallery_thumbnail_size(&self) -> Vec2 {
+        vec2(self.width as f32, self.height as f32)
+            / gallery_grid().as_vec2()
+            * (1.0 - 2.0 * GALLERY_GAP)
+    }
+
+    /// Returns which scene's thumbnail is shown at given pixel of the gallery
+    /// (see [`Self::gallery`]) and where within that thumbnail the pixel lies.
+    ///
+    /// Position is `0..1` inside the thumbnail and slightly outside of that
+    /// range in the gap around it; scene is zero for the cells of the grid
+    /// that don't contain any scene.
+    pub fn gallery_scene_at(
+        &self,
+        pixel: Vec2,
+    ) -> (u32, Vec2) {
+        let grid = gallery_grid();
+
+        let cell = pixel * grid.as_vec2()
+            / vec2(self.width as f32, self.height as f32);
+
+        let index =
+            cell.max(Vec2::ZERO).as_uvec2().min(grid - 1);
+
+        let uv = (cell - index.as_vec2() - GALLERY_GAP)
+            / (1.0 - 2.0 * GALLERY_GAP);
+
+        let scene = index.y * grid.x + index.x + 1;
+
+        if scene > SCENES {
+            (0, uv)
+        } else {
+            (scene, uv)
+        }
+    }
+}
+
+/// Gap around each thumbnail in the gallery, relative to the size of its cell.
+const GALLERY_GAP: f32 = 0.03;
+
+/// Returns the number of columns and rows of the gallery - it's the smallest
+/// square-ish grid that fits all the scenes.
+fn gallery_grid() -> UVec2 {
+    let mut columns = 1;
+
+    while columns * columns < SCENES {
+        columns += 1;
+    }
+
+    uvec2(columns, (SCENES + columns - 1) / columns)
+}
+
+/// Statistics collected by the shader while rendering a frame.
+#[repr(C)]
+#[derive(Clone, Copy, Default, Pod, Zeroable)]
+#[cfg_attr(not(target_arch = "spirv"), derive(Debug))]
+pub struct Stats {
+    /// Number of pixels for which `march()` ran out of steps before hitting
+    /// anything or escaping the scene.
+    pub exhausted: u32,
+
+    /// Number of primary rays traced - at least one per pixel, more with
+    /// anti-aliasing, motion blur etc.
+    pub rays: u32,
+
+    /// Number of primary rays that hit a surface.
+    pub hits: u32,
+
+    /// Number of primary rays that ran out of steps.
+    pub exhausted_rays: u32,
+
+    /// Total number of steps taken by primary rays (wraps around on huge
+    /// frames with lots of samples).
+    pub steps: u32,
+
+    /// Values recorded for the pixel pointed at by [`Params::probe`].
+    pub probe: Probe,
+}
+
+/// Values recorded by the shader for a single pixel (see [`Params::probe`]) -
+/// kinda like `println!()`, but for the fragment shader.
+///
+/// Apart from the fields below, `probe()` in the shader can store anything
+/// else into [`Self::custom`] - handy when debugging a particular formula.
+#[repr(C)]
+#[derive(Clone, Copy, Default, Pod, Zeroable)]
+#[cfg_attr(not(target_arch = "spirv"), derive(Debug))]
+pub struct Probe {
+    /// Non-zero if the shader has probed anything during the frame.
+    pub written: u32,
+
+    /// How many steps it took `march()` to hit the surface or escape the scene.
+    pub steps: u32,
+
+    /// Non-zero if `march()` ran out of steps.
+    pub exhausted: u32,
+
+    /// Shading model the hit got shaded with, see [`shading`].
+    pub shading: u32,
+
+    /// Distance from the camera to the hit point; infinity if the ray didn't
+    /// hit anything.
+    pub distance: f32,
+
+    pub normal: [f32; 3],
+    pub albedo: [f32; 3],
+
+    /// Color of the pixel, without anti-aliasing, motion blur and cross-fading
+    /// between scenes.
+    pub color: [f32; 3],
+
+    /// Whatever `probe()` wants to store there.
+    pub custom: [f32; 4],
+}
+
+/// Number of scenes implemented by the shader, see [`Params::scene`].
+pub const SCENES: u32 = 6;
+
+/// Names of the scenes, shown in the window's title.
+#[cfg(not(target_arch = "spirv"))]
+pub const SCENE_NAMES: [&str; SCENES as usize] = [
+    "sphere",
+    "rect",
+    "sphere and rect",
+    "heart",
+    "ocean",
+    "mandelbulb",
+];
+
+/// Returns name of given scene, or `"?"` for scenes the shader doesn't have.
+#[cfg(not(target_arch = "spirv"))]
+pub fn scene_name(scene: u32) -> &'static str {
+    match scene {
+        1..=SCENES => SCENE_NAMES[scene as usize - 1],
+        _ => "?",
+    }
+}
+
+/// Values for [`Params::time_curve`]'s `x`.
+pub mod time_curve {
+    /// Time flows as-is, the period is ignored.
+    pub const LINEAR: u32 = 0;
+
+    /// Time flows as-is, but slows down around the start and the end of each
+    /// period - for animations that should ease into and out of each loop.
+    pub const EASE_IN_OUT: u32 = 1;
+
+    /// Time goes forward for the first half of each period and then backward
+    /// for the second one - for animations that don't loop on their own.
+    pub const PING_PONG: u32 = 2;
+
+    /// Time goes forward and jumps back to zero at the end of each period.
+    pub const SAWTOOTH: u32 = 3;
+
+    pub const COUNT: u32 = 4;
+}
+
+/// Values for [`Params::debug_view`].
+pub mod debug_view {
+    /// Regular, shaded rendering.
+    pub const NONE: u32 = 0;
+
+    /// Cross-section of the distance field, rendered as a color map with
+    /// isolines on top of the scene.
+    pub const SLICE: u32 = 1;
+
+    /// Regular rendering, but pixels for which the scene turned out not to be
+    /// 1-Lipschitz (e.g. due to twisting or displacement) are painted magenta.
+    pub const LIPSCHITZ: u32 = 2;
+
+    /// Number of steps it took to march each pixel, as a heatmap (black =
+    /// few, white = all of them) - shows where the marcher spends its time.
+    pub const STEPS: u32 = 3;
+
+    /// Image in grayscale, with pixels brighter than what the display can
+    /// show (luminance above 1.0) striped in false color, from red (a bit too
+    /// bright) to white (many times too bright).
+    pub const EXPOSURE: u32 = 4;
+
+    pub const COUNT: u32 = 5;
+}
+
+/// Returns direction of the ray going through given screen position (`0..1`)
+/// of a camera located at `origin` and looking at `target`.
+pub fn direction(
+    origin: Vec3,
+    target: Vec3,
+    uv: Vec2,
+) -> Vec3 {
+    ray_direction(look_at(origin, target, Vec3::Y), uv)
+}
+
+/// Returns orientation of a camera located at `origin` and looking at
+/// `target`, as a matrix of its right (`x_axis`), up (`y_axis`) and forward
+/// (`z_axis`) vectors.
+///
+/// `up` doesn't have to be perpendicular to the viewing direction, it just
+/// says which way is "up-ish"; when it's parallel to the viewing direction
+/// (e.g. in a top-down shot with `up` = `Vec3::Y`), some other vector is used
+/// instead, so that we don't end up with NaNs.
+pub fn look_at(
+    origin: Vec3,
+    target: Vec3,
+    up: Vec3,
+) -> Mat3 {
+    let f = (target - origin).normalize_or_zero();
+
+    // Camera located right at the target doesn't look anywhere in particular,
+    // so let's pick something
+    let f = if f == Vec3::ZERO { Vec3::Z } else { f };
+
+    let s = f.cross(up);
+
+    let s = if s.length_squared() > 1e-8 {
+        s
+    } else if f.x.abs() < 0.9 {
+        f.cross(Vec3::X)
+    } else {
+        f.cross(Vec3::Z)
     };
 
+    let s = s.normalize();
+    let u = s.cross(f);
+
+    Mat3 {
+        x_axis: s,
+        y_axis: u,
+        z_axis: f,
+    }
+}
+
+/// Returns direction of the ray going through given screen position (`0..1`)
+/// of a camera with given orientation (see [`look_at()`]).
+pub fn ray_direction(camera: Mat3, uv: Vec2) -> Vec3 {
     let uv = uv.xy() * 2.0 - 1.0;
     let uv = vec2(uv.x, -uv.y);
 
     (camera * uv.extend(1.0)).normalize()
 }
+
+/// Width and height of the matcap image, see [`shading::MATCAP`].
+pub const MATCAP_SIZE: u32 = 256;
+
+/// Values for [`Params::shading`].
+pub mod shading {
+    /// Regular, (very) roughly physically-based shading.
+    pub const REGULAR: u32 = 0;
+
+    /// Non-photorealistic, cartoon-like shading - diffuse lighting quantized
+    /// into a couple of flat bands, a hard-edged specular highlight and a rim
+    /// light.
+    pub const TOON: u32 = 1;
+
+    /// Shading looked up from a matcap - an image of a lit sphere - using the
+    /// surface's normal as seen from the camera; ignores the scene's lighting,
+    /// making it a neutral preview material for modeling shapes.
+    pub const MATCAP: u32 = 2;
+
+    pub const COUNT: u32 = 3;
+}
+
+/// Quality presets, see [`Params::set_quality()`] - so that the same scene can
+/// be shown both on weak laptops and beefy desktops.
+pub mod quality {
+    /// Short rays, no shadows.
+    pub const LOW: u32 = 0;
+
+    /// Shorter rays, rough shadows.
+    pub const MEDIUM: u32 = 1;
+
+    /// The default.
+    pub const HIGH: u32 = 2;
+
+    /// Long rays, detailed shadows and 4x anti-aliasing.
+    pub const ULTRA: u32 = 3;
+}
+
+/// Values for [`Params::stereo`].
+pub mod stereo {
+    /// Regular, single-camera rendering.
+    pub const OFF: u32 = 0;
+
+    /// Red-cyan anaglyph, for the cheap paper glasses.
+    pub const ANAGLYPH: u32 = 1;
+
+    /// Left eye on the left half of the screen, right eye on the right half.
+    pub const SIDE_BY_SIDE: u32 = 2;
+
+    pub const COUNT: u32 = 3;
+}
+
+/// Rounds given number down - `f32::floor()` requires `std`, which isn't
+/// available in the shader.
+fn floor(x: f32) -> f32 {
+    let y = x as i32 as f32;
+
+    if y > x {
+        y - 1.0
+    } else {
+        y
+    }
+}
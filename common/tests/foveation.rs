@@ -0,0 +1,104 @@
+//! Foveated rendering (see `Params::fovea`) - the lower-resolution parts of the
+//! frame get rendered into an atlas, which the upscaling pass later reads from;
+//! each texel it reads has to be rendered, and no two of them can overlap.
+//!
+//! Run with `cargo test -p sdf-playground-common`.
+
+use sdf_playground_common::glam::*;
+use sdf_playground_common::Params;
+
+const SIZES: [(u32, u32); 4] =
+    [(1, 1), (97, 61), (320, 200), (333, 555)];
+
+fn params(width: u32, height: u32) -> Params {
+    Params {
+        width,
+        height,
+        fovea: vec4(
+            width as f32 * 0.3,
+            height as f32 * 0.6,
+            width.min(height) as f32 * 0.15 + 1.0,
+            0.0,
+        ),
+        ..Default::default()
+    }
+}
+
+/// Returns the region's size, i.e. how many pixels of given block fit on the
+/// screen.
+fn region_size(params: &Params, block: u32) -> UVec2 {
+    (uvec2(params.width, params.height) + block - 1) / block
+}
+
+#[test]
+fn regions_fit_in_atlas() {
+    for (width, height) in SIZES {
+        let params = params(width, height);
+        let atlas = params.fovea_atlas_size();
+
+        let regions = [2, 4, 8].map(|block| {
+            let min = params.fovea_region(block);
+            let max = min + region_size(&params, block);
+
+            assert!(
+                max.cmple(atlas).all(),
+                "{width}x{height}: region of block {block} ({min}..{max}) doesn't fit in {atlas}"
+            );
+
+            (min, max)
+        });
+
+        for (a, (a_min, a_max)) in
+            regions.iter().enumerate()
+        {
+            for (b_min, b_max) in &regions[a + 1..] {
+                let overlap = a_min.cmplt(*b_max).all()
+                    && b_min.cmplt(*a_max).all();
+
+                assert!(
+                    !overlap,
+                    "{width}x{height}: region #{a} overlaps with a later one"
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn corners_get_rendered() {
+    for (width, height) in SIZES {
+        let params = params(width, height);
+        let size = uvec2(width, height);
+
+        for y in 0..height {
+            for x in 0..width {
+                let pixel = uvec2(x, y);
+                let block = params.fovea_block(pixel);
+
+                if block == 1 {
+                    continue;
+                }
+
+                // (same as `main_fs()` in `foveation.wgsl`)
+                let p0 = pixel / block * block;
+                let p1 = UVec2::select(
+                    (p0 + block).cmplt(size),
+                    p0 + block,
+                    p0,
+                );
+
+                for corner in [
+                    p0,
+                    uvec2(p1.x, p0.y),
+                    uvec2(p0.x, p1.y),
+                    p1,
+                ] {
+                    assert!(
+                        params.fovea_renders(corner, block),
+                        "{width}x{height}: corner {corner} of {pixel} (block {block}) isn't rendered"
+                    );
+                }
+            }
+        }
+    }
+}
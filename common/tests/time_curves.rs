@@ -0,0 +1,70 @@
+//! Time curves (see `Params::time_curve`) - looping animations rely on them
+//! starting and ending each period at the same moment.
+//!
+//! Run with `cargo test -p sdf-playground-common`.
+
+use sdf_playground_common::glam::*;
+use sdf_playground_common::{time_curve, Params};
+
+#[track_caller]
+fn assert_time(
+    curve: u32,
+    period: f32,
+    time: f32,
+    expected: f32,
+) {
+    let params = Params {
+        time,
+        time_curve: vec4(curve as f32, period, 0.0, 0.0),
+        ..Default::default()
+    };
+
+    let actual = params.scene_time();
+
+    assert!(
+        (actual - expected).abs() < 0.001,
+        "curve {curve} (period = {period}) at {time}: expected {expected}, got {actual}"
+    );
+}
+
+#[test]
+fn linear() {
+    assert_time(time_curve::LINEAR, 0.0, 12.5, 12.5);
+    assert_time(time_curve::LINEAR, 4.0, 12.5, 12.5);
+}
+
+#[test]
+fn ease_in_out() {
+    assert_time(time_curve::EASE_IN_OUT, 4.0, 0.0, 0.0);
+    assert_time(time_curve::EASE_IN_OUT, 4.0, 1.0, 0.625);
+    assert_time(time_curve::EASE_IN_OUT, 4.0, 2.0, 2.0);
+    assert_time(time_curve::EASE_IN_OUT, 4.0, 3.0, 3.375);
+    assert_time(time_curve::EASE_IN_OUT, 4.0, 8.0, 8.0);
+}
+
+#[test]
+fn ping_pong() {
+    assert_time(time_curve::PING_PONG, 4.0, 0.0, 0.0);
+    assert_time(time_curve::PING_PONG, 4.0, 1.0, 1.0);
+    assert_time(time_curve::PING_PONG, 4.0, 2.0, 2.0);
+    assert_time(time_curve::PING_PONG, 4.0, 3.0, 1.0);
+    assert_time(time_curve::PING_PONG, 4.0, 4.0, 0.0);
+    assert_time(time_curve::PING_PONG, 4.0, 5.0, 1.0);
+}
+
+#[test]
+fn sawtooth() {
+    assert_time(time_curve::SAWTOOTH, 4.0, 0.0, 0.0);
+    assert_time(time_curve::SAWTOOTH, 4.0, 3.0, 3.0);
+    assert_time(time_curve::SAWTOOTH, 4.0, 5.0, 1.0);
+
+    // Motion blur can look slightly into the past
+    assert_time(time_curve::SAWTOOTH, 4.0, -1.0, 3.0);
+}
+
+#[test]
+fn no_period() {
+    for curve in 0..time_curve::COUNT {
+        assert_time(curve, 0.0, 12.5, 12.5);
+    }
+}
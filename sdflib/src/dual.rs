@@ -0,0 +1,237 @@
+//! Dual numbers, i.e. forward-mode automatic differentiation.
+//!
+//! The idea is that instead of computing just `f(x)`, we compute `f(x)` and
+//! `f'(x)` at the same time - every operation (addition, multiplication, sine
+//! etc.) transforms both the value and the derivative, using the usual rules
+//! from calculus:
+//!
+//! ```text
+//! (a + b)' = a' + b'
+//! (a * b)' = a' * b + a * b'
+//! sin(a)'  = cos(a) * a'
+//! ...
+//! ```
+//!
+//! Since our functions take 3D points, our derivative is actually a gradient -
+//! which, for an SDF, is precisely the normal we're after.
+
+#![allow(unused)]
+
+use core::ops::{Add, Mul, Neg, Sub};
+use spirv_std::glam::*;
+#[cfg(target_arch = "spirv")]
+use spirv_std::num_traits::*;
+
+/// Number together with its gradient.
+#[derive(Clone, Copy)]
+pub struct Dual {
+    pub val: f32,
+    pub grad: Vec3,
+}
+
+impl Dual {
+    /// Creates a number that doesn't depend on the point (i.e. a constant).
+    pub fn constant(val: f32) -> Self {
+        Self {
+            val,
+            grad: Vec3::ZERO,
+        }
+    }
+
+    pub fn sin(self) -> Self {
+        Self {
+            val: self.val.sin(),
+            grad: self.grad * self.val.cos(),
+        }
+    }
+
+    pub fn cos(self) -> Self {
+        Self {
+            val: self.val.cos(),
+            grad: self.grad * -self.val.sin(),
+        }
+    }
+
+    pub fn abs(self) -> Self {
+        if self.val < 0.0 {
+            -self
+        } else {
+            self
+        }
+    }
+
+    pub fn sqrt(self) -> Self {
+        let val = self.val.sqrt();
+
+        // sqrt() isn't differentiable at zero; we pretend the gradient is zero
+        // there, which lets `normal()` fall back to finite differences
+        let grad = if val > 0.0 {
+            self.grad / (2.0 * val)
+        } else {
+            Vec3::ZERO
+        };
+
+        Self { val, grad }
+    }
+
+    pub fn min(self, rhs: Self) -> Self {
+        if self.val < rhs.val {
+            self
+        } else {
+            rhs
+        }
+    }
+
+    pub fn max(self, rhs: Self) -> Self {
+        if self.val > rhs.val {
+            self
+        } else {
+            rhs
+        }
+    }
+}
+
+impl Add for Dual {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self {
+        Self {
+            val: self.val + rhs.val,
+            grad: self.grad + rhs.grad,
+        }
+    }
+}
+
+impl Add<f32> for Dual {
+    type Output = Self;
+
+    fn add(self, rhs: f32) -> Self {
+        Self {
+            val: self.val + rhs,
+            grad: self.grad,
+        }
+    }
+}
+
+impl Sub for Dual {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self {
+        self + -rhs
+    }
+}
+
+impl Sub<f32> for Dual {
+    type Output = Self;
+
+    fn sub(self, rhs: f32) -> Self {
+        self + -rhs
+    }
+}
+
+impl Mul for Dual {
+    type Output = Self;
+
+    fn mul(self, rhs: Self) -> Self {
+        Self {
+            val: self.val * rhs.val,
+            grad: self.grad * rhs.val + rhs.grad * self.val,
+        }
+    }
+}
+
+impl Mul<f32> for Dual {
+    type Output = Self;
+
+    fn mul(self, rhs: f32) -> Self {
+        Self {
+            val: self.val * rhs,
+            grad: self.grad * rhs,
+        }
+    }
+}
+
+impl Neg for Dual {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        Self {
+            val: -self.val,
+            grad: -self.grad,
+        }
+    }
+}
+
+/// Three-dimensional vector of [`Dual`]s.
+#[derive(Clone, Copy)]
+pub struct DualVec3 {
+    pub x: Dual,
+    pub y: Dual,
+    pub z: Dual,
+}
+
+impl DualVec3 {
+    /// Creates a vector representing the point at which we're differentiating
+    /// (i.e. the variable, so to say).
+    pub fn point(p: Vec3) -> Self {
+        Self {
+            x: Dual {
+                val: p.x,
+                grad: Vec3::X,
+            },
+            y: Dual {
+                val: p.y,
+                grad: Vec3::Y,
+            },
+            z: Dual {
+                val: p.z,
+                grad: Vec3::Z,
+            },
+        }
+    }
+
+    pub fn val(self) -> Vec3 {
+        vec3(self.x.val, self.y.val, self.z.val)
+    }
+
+    pub fn length(self) -> Dual {
+        (self.x * self.x
+            + self.y * self.y
+            + self.z * self.z)
+            .sqrt()
+    }
+
+    pub fn abs(self) -> Self {
+        Self {
+            x: self.x.abs(),
+            y: self.y.abs(),
+            z: self.z.abs(),
+        }
+    }
+
+    pub fn max(self, rhs: f32) -> Self {
+        let rhs = Dual::constant(rhs);
+
+        Self {
+            x: self.x.max(rhs),
+            y: self.y.max(rhs),
+            z: self.z.max(rhs),
+        }
+    }
+
+    pub fn max_element(self) -> Dual {
+        self.x.max(self.y).max(self.z)
+    }
+}
+
+impl Sub<Vec3> for DualVec3 {
+    type Output = Self;
+
+    fn sub(self, rhs: Vec3) -> Self {
+        Self {
+            x: self.x - rhs.x,
+            y: self.y - rhs.y,
+            z: self.z - rhs.z,
+        }
+    }
+}
@@ -0,0 +1,15 @@
+//! Math shared by shader crates - signed distance functions of 2D and 3D
+//! shapes (together with operators combining them), dual numbers for
+//! computing their gradients, color palettes and a procedural starfield.
+//!
+//! It's a separate crate so that different sketches can depend on the same,
+//! tested functions instead of copy-pasting them around; everything here works
+//! both on the GPU (through rust-gpu) and on the CPU.
+
+#![cfg_attr(target_arch = "spirv", no_std)]
+
+pub mod dual;
+pub mod palette;
+pub mod sdf;
+pub mod sdf2d;
+pub mod starfield;
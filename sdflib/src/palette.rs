@@ -0,0 +1,76 @@
+//! Palettes and gradients, mapping numbers (usually `0..1`) into colors - used
+//! to color fractals and to visualize values in debug views.
+
+#![allow(unused)]
+
+use core::f32::consts::PI;
+use spirv_std::glam::*;
+#[cfg(target_arch = "spirv")]
+use spirv_std::num_traits::*;
+
+/// Cosine palette - `a + b * cos(2π * (c * t + d))`.
+///
+/// `a` is the palette's average color and `b` says how far it swings from it,
+/// while `c` and `d` control how fast (and with what offset) each channel
+/// oscillates; see <https://iquilezles.org/articles/palettes/> for a couple of
+/// good-looking combinations.
+pub fn palette(
+    t: f32,
+    a: Vec3,
+    b: Vec3,
+    c: Vec3,
+    d: Vec3,
+) -> Vec3 {
+    let phase = 2.0 * PI * (c * t + d);
+
+    a + b * vec3(
+        phase.x.cos(),
+        phase.y.cos(),
+        phase.z.cos(),
+    )
+}
+
+/// Red, green, blue, red again - repeats every 1.0.
+pub fn rainbow(t: f32) -> Vec3 {
+    palette(
+        t,
+        vec3(0.5, 0.5, 0.5),
+        vec3(0.5, 0.5, 0.5),
+        vec3(1.0, 1.0, 1.0),
+        vec3(0.0, 0.33, 0.67),
+    )
+}
+
+/// Browns, greens and blues - repeats every 1.0.
+pub fn earth(t: f32) -> Vec3 {
+    palette(
+        t,
+        vec3(0.5, 0.5, 0.5),
+        vec3(0.5, 0.5, 0.5),
+        vec3(1.0, 1.0, 1.0),
+        vec3(0.0, 0.1, 0.2),
+    )
+}
+
+/// Purples, oranges and yellows - repeats every 2.0.
+pub fn sunset(t: f32) -> Vec3 {
+    palette(
+        t,
+        vec3(0.5, 0.5, 0.5),
+        vec3(0.5, 0.5, 0.5),
+        vec3(1.0, 1.0, 0.5),
+        vec3(0.8, 0.9, 0.3),
+    )
+}
+
+/// Black, red, yellow, white - for visualizing "how much" of something there
+/// is; `t` is clamped to `0..1`.
+pub fn heat(t: f32) -> Vec3 {
+    let t = t.clamp(0.0, 1.0) * 3.0;
+
+    vec3(
+        t.clamp(0.0, 1.0),
+        (t - 1.0).clamp(0.0, 1.0),
+        (t - 2.0).clamp(0.0, 1.0),
+    )
+}
@@ -0,0 +1,182 @@
+//! Signed distance functions of 3D shapes, along with operators combining
+//! them.
+
+#![allow(unused)]
+
+use core::f32::consts::PI;
+use spirv_std::glam::*;
+#[cfg(target_arch = "spirv")]
+use spirv_std::num_traits::*;
+
+pub fn union(f1: f32, f2: f32) -> f32 {
+    f1.min(f2)
+}
+
+pub fn subtraction(f1: f32, f2: f32) -> f32 {
+    f1.max(-f2)
+}
+
+pub fn intersection(f1: f32, f2: f32) -> f32 {
+    f1.max(f2)
+}
+
+pub fn repeat(p: Vec3, s: Vec3) -> Vec3 {
+    p - s * (p / s).round()
+}
+
+pub fn sphere(p: Vec3, r: f32) -> f32 {
+    p.length() - r
+}
+
+pub fn rect(p: Vec3, b: Vec3) -> f32 {
+    let q = p.abs() - b;
+
+    q.max(Vec3::ZERO).length() + q.max_element().min(0.0)
+}
+
+/// Result of a fractal's distance estimator - apart from the distance, it
+/// says how the point's orbit behaved, which is what fractals are usually
+/// colored by.
+pub struct Fractal {
+    pub distance: f32,
+
+    /// Orbit traps - the smallest distances from the orbit to the YZ, XZ
+    /// and XY planes (`xyz`) and the smallest squared distance from the
+    /// orbit to the origin (`w`).
+    pub trap: Vec4,
+
+    /// How many iterations it took for the orbit to escape.
+    pub iterations: u32,
+}
+
+/// Mandelbulb (power 8), roughly 1.2 units in radius.
+pub fn mandelbulb(p: Vec3) -> Fractal {
+    const POWER: f32 = 8.0;
+    const ITERATIONS: u32 = 8;
+    const BAILOUT: f32 = 2.0;
+
+    let mut z = p;
+    let mut dr = 1.0;
+    let mut r = 0.0;
+    let mut trap = Vec4::splat(f32::MAX);
+    let mut iterations = 0;
+
+    while iterations < ITERATIONS {
+        r = z.length();
+
+        if r > BAILOUT {
+            break;
+        }
+
+        trap = trap.min(z.abs().extend(r * r));
+
+        // Raise `z` to the power, in spherical coordinates
+        let theta = (z.z / r).acos() * POWER;
+        let phi = z.y.atan2(z.x) * POWER;
+
+        dr = r.powf(POWER - 1.0) * POWER * dr + 1.0;
+
+        z = r.powf(POWER)
+            * vec3(
+                theta.sin() * phi.cos(),
+                phi.sin() * theta.sin(),
+                theta.cos(),
+            )
+            + p;
+
+        iterations += 1;
+    }
+
+    Fractal {
+        distance: 0.5 * r.ln() * r / dr,
+        trap,
+        iterations,
+    }
+}
+
+/// Versions of the functions above operating on dual numbers.
+pub mod dual {
+    use crate::dual::{Dual, DualVec3};
+    use spirv_std::glam::*;
+
+    pub fn intersection(f1: Dual, f2: Dual) -> Dual {
+        f1.max(f2)
+    }
+
+    pub fn sphere(p: DualVec3, r: f32) -> Dual {
+        p.length() - r
+    }
+
+    pub fn rect(p: DualVec3, b: Vec3) -> Dual {
+        let q = p.abs() - b;
+
+        q.max(0.0).length()
+            + q.max_element().min(Dual::constant(0.0))
+    }
+}
+
+/// Thanks to: https://www.shadertoy.com/view/MdXyzX.
+///
+/// `pixel_size` says how large (in world units) a single pixel is at `point` -
+/// waves smaller than a pixel are skipped; zero means all the waves.
+pub fn ocean(
+    time: f32,
+    point: Vec3,
+    pixel_size: f32,
+) -> f32 {
+    point.y - ocean_height(time, point.xz(), pixel_size)
+}
+
+/// Height of the ocean's surface at given point of the XZ plane - the
+/// ocean is a heightfield, see [`ocean()`].
+pub fn ocean_height(
+    time: f32,
+    point: Vec2,
+    pixel_size: f32,
+) -> f32 {
+    // Origin (the point at (0,0)) contains a ripple-artifact that doesn't
+    // look great - to avoid it, let's offset the ocean
+    let point = point + 128.0;
+
+    // Also, the default animation speed is kinda slow, so let's speed it up
+    let time = 2.0 * time;
+
+    // ---
+
+    let mut h_sum = 0.0;
+    let mut h_weight = 0.0;
+
+    let mut wave_pos = point;
+    let mut wave_freq = 1.0;
+    let mut wave_weight = 1.0;
+
+    let mut noise = 0.0f32;
+
+    for _ in 0..15 {
+        // Each next wave is shorter than the previous one, so once we've
+        // reached the size of a pixel, there's no point in continuing
+        if 2.0 * PI / wave_freq < pixel_size {
+            break;
+        }
+
+        let wave_dir = vec2(noise.cos(), noise.sin());
+
+        let wave =
+            wave_dir.dot(wave_pos) * wave_freq + time;
+
+        let wave_h = (wave.sin() - 1.0).exp();
+        let wave_dh = wave_h * wave.cos();
+
+        h_sum += wave_h * wave_weight;
+        h_weight += wave_weight;
+
+        wave_pos -= 0.25 * wave_dh * wave_dir * wave_weight;
+
+        wave_freq *= 1.18;
+        wave_weight *= 0.82;
+
+        noise += 1234.4321;
+    }
+
+    h_sum / h_weight
+}
@@ -0,0 +1,53 @@
+//! Signed distance functions of 2D shapes - see the shader's `main_fs_2d()`
+//! for a way of looking at them, and [`extrude()`] for a way of turning them
+//! into 3D objects.
+
+#![allow(unused)]
+
+use spirv_std::glam::*;
+#[cfg(target_arch = "spirv")]
+use spirv_std::num_traits::*;
+
+pub fn union(f1: f32, f2: f32) -> f32 {
+    f1.min(f2)
+}
+
+pub fn subtraction(f1: f32, f2: f32) -> f32 {
+    f1.max(-f2)
+}
+
+pub fn intersection(f1: f32, f2: f32) -> f32 {
+    f1.max(f2)
+}
+
+pub fn circle(p: Vec2, r: f32) -> f32 {
+    p.length() - r
+}
+
+pub fn rect(p: Vec2, b: Vec2) -> f32 {
+    let q = p.abs() - b;
+
+    q.max(Vec2::ZERO).length() + q.max_element().min(0.0)
+}
+
+/// Line segment going from `a` to `b`.
+pub fn segment(p: Vec2, a: Vec2, b: Vec2) -> f32 {
+    let pa = p - a;
+    let ba = b - a;
+    let h = (pa.dot(ba) / ba.dot(ba)).clamp(0.0, 1.0);
+
+    (pa - ba * h).length()
+}
+
+/// Rounds the shape's corners (and makes it `r` units larger).
+pub fn round(d: f32, r: f32) -> f32 {
+    d - r
+}
+
+/// Turns a 2D shape into a 3D one by extruding it along the Y axis, `h` units
+/// in both directions - `d` is the 2D shape's distance at `p.xz()`.
+pub fn extrude(p: Vec3, d: f32, h: f32) -> f32 {
+    let w = vec2(d, p.y.abs() - h);
+
+    w.max_element().min(0.0) + w.max(Vec2::ZERO).length()
+}
@@ -0,0 +1,121 @@
+//! Procedural night sky - twinkling stars and an (optional) milky way band,
+//! for backgrounds of scenes set in space.
+//!
+//! Everything's a function of the viewing direction, so the sky is infinitely
+//! far away - it doesn't move when the camera does, only when it turns.
+
+use core::f32::consts::TAU;
+use spirv_std::glam::*;
+#[cfg(target_arch = "spirv")]
+use spirv_std::num_traits::*;
+
+/// How many cells (each holding at most one star) the sky is divided into,
+/// along each axis of the cube enclosing it - the more, the more stars (and
+/// the smaller they get).
+const CELLS: f32 = 120.0;
+
+/// Chance of a cell containing a star.
+const DENSITY: f32 = 0.15;
+
+/// Normal of the plane the milky way lies in - tilted, so that it crosses the
+/// sky diagonally.
+const MILKY_WAY: Vec3 = Vec3::new(0.3, 0.8, 0.52);
+
+/// Returns color of the night sky in given (normalized) direction - stars,
+/// twinkling as the time passes, plus the milky way if requested.
+pub fn night_sky(
+    direction: Vec3,
+    time: f32,
+    milky_way: bool,
+) -> Vec3 {
+    let mut color = stars(direction, time);
+
+    if milky_way {
+        color += band(direction);
+    }
+
+    color
+}
+
+/// Returns color of the stars seen in given direction.
+///
+/// The direction is scaled into a grid of cells, each of which has a chance of
+/// holding a single star, placed randomly (but away from the cell's walls, so
+/// that it doesn't get cut off by them) - that way each pixel has to check
+/// just a single cell.
+pub fn stars(direction: Vec3, time: f32) -> Vec3 {
+    let point = direction * CELLS;
+    let cell = vec3(
+        point.x.floor(),
+        point.y.floor(),
+        point.z.floor(),
+    );
+    let seed = cell.as_ivec3();
+
+    if random(seed, 0) > DENSITY {
+        return Vec3::ZERO;
+    }
+
+    let star = cell
+        + vec3(
+            random(seed, 1),
+            random(seed, 2),
+            random(seed, 3),
+        ) * 0.5
+        + 0.25;
+
+    // Most stars are faint, a couple are bright
+    let brightness = random(seed, 4).powf(4.0) * 4.0 + 0.2;
+
+    // Each star twinkles at its own pace
+    let twinkle = 0.75
+        + 0.25
+            * (time * (1.0 + 3.0 * random(seed, 5))
+                + TAU * random(seed, 6))
+            .sin();
+
+    // Somewhere between bluish-white and orange-ish
+    let tint = vec3(0.7, 0.8, 1.0)
+        .lerp(vec3(1.0, 0.8, 0.6), random(seed, 7));
+
+    let distance = point.distance(star);
+    let glow = (-distance * distance * 40.0).exp();
+
+    tint * brightness * twinkle * glow
+}
+
+/// Returns color of the milky way seen in given direction - a faint, blotchy
+/// band around the sky.
+fn band(direction: Vec3) -> Vec3 {
+    let height = direction.dot(MILKY_WAY.normalize());
+    let band = (-height * height * 40.0).exp();
+
+    // A couple of sines, so that the band isn't perfectly uniform
+    let blotches = (direction.x * 13.0).sin()
+        * (direction.y * 17.0).sin()
+        * (direction.z * 11.0).sin();
+
+    let blotches = 0.6 + 0.4 * blotches;
+
+    vec3(0.25, 0.22, 0.3) * band * blotches * 0.4
+}
+
+/// Returns a pseudo-random number in `0..1`, the same each time it's called
+/// with the same cell and channel.
+fn random(cell: IVec3, channel: u32) -> f32 {
+    let mut hash = (cell.x as u32)
+        .wrapping_mul(0x8da6_b343)
+        ^ (cell.y as u32).wrapping_mul(0xd816_3841)
+        ^ (cell.z as u32).wrapping_mul(0xcb1a_b31f)
+        ^ channel.wrapping_mul(0x9e37_79b9);
+
+    // (a couple of xorshift-multiply rounds, so that neighbouring cells and
+    // channels don't end up correlated)
+    hash ^= hash >> 16;
+    hash = hash.wrapping_mul(0x7feb_352d);
+    hash ^= hash >> 15;
+    hash = hash.wrapping_mul(0x846c_a68b);
+    hash ^= hash >> 16;
+
+    (hash >> 8) as f32 / (1 << 24) as f32
+}
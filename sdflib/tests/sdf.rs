@@ -0,0 +1,104 @@
+//! Distances returned by the primitives at a couple of hand-picked points, and
+//! gradients of their dual versions - so that sketches depending on the
+//! library can trust it.
+//!
+//! Run with `cargo test -p sdf-playground-sdflib`.
+
+use sdf_playground_sdflib::dual::DualVec3;
+use sdf_playground_sdflib::{sdf, sdf2d};
+use spirv_std::glam::*;
+
+#[track_caller]
+fn assert_approx(actual: f32, expected: f32) {
+    assert!(
+        (actual - expected).abs() < 0.001,
+        "expected {expected}, got {actual}"
+    );
+}
+
+#[test]
+fn primitives() {
+    assert_approx(sdf::sphere(Vec3::ZERO, 2.0), -2.0);
+    assert_approx(
+        sdf::sphere(vec3(0.0, 3.0, 4.0), 2.0),
+        3.0,
+    );
+
+    assert_approx(
+        sdf::rect(Vec3::ZERO, vec3(1.0, 2.0, 3.0)),
+        -1.0,
+    );
+    assert_approx(
+        sdf::rect(vec3(2.0, 0.0, 0.0), Vec3::ONE),
+        1.0,
+    );
+
+    // Past the corner, the closest point is the corner itself
+    assert_approx(
+        sdf::rect(vec3(2.0, 2.0, 2.0), Vec3::ONE),
+        3f32.sqrt(),
+    );
+}
+
+#[test]
+fn operators() {
+    assert_approx(sdf::union(1.0, -2.0), -2.0);
+    assert_approx(sdf::intersection(1.0, -2.0), 1.0);
+    assert_approx(sdf::subtraction(1.0, -2.0), 2.0);
+
+    assert_eq!(
+        sdf::repeat(vec3(4.5, -0.5, 0.0), Vec3::splat(2.0)),
+        vec3(0.5, -0.5, 0.0),
+    );
+}
+
+#[test]
+fn primitives_2d() {
+    assert_approx(sdf2d::circle(vec2(3.0, 4.0), 1.0), 4.0);
+    assert_approx(
+        sdf2d::rect(Vec2::ZERO, vec2(1.0, 2.0)),
+        -1.0,
+    );
+
+    assert_approx(
+        sdf2d::segment(
+            vec2(1.0, 1.0),
+            vec2(0.0, 0.0),
+            vec2(2.0, 0.0),
+        ),
+        1.0,
+    );
+
+    // Point inside of the 2D shape, but above the extruded one
+    assert_approx(
+        sdf2d::extrude(vec3(0.0, 3.0, 0.0), -1.0, 1.0),
+        2.0,
+    );
+}
+
+#[test]
+fn dual() {
+    for point in [
+        vec3(3.0, 0.0, 0.0),
+        vec3(1.0, -2.0, 0.5),
+        vec3(0.0, 0.0, -7.0),
+    ] {
+        let sphere =
+            sdf::dual::sphere(DualVec3::point(point), 1.0);
+
+        assert_approx(sphere.val, sdf::sphere(point, 1.0));
+        assert!(sphere
+            .grad
+            .abs_diff_eq(point.normalize(), 0.001));
+
+        let rect = sdf::dual::rect(
+            DualVec3::point(point),
+            Vec3::ONE,
+        );
+
+        assert_approx(
+            rect.val,
+            sdf::rect(point, Vec3::ONE),
+        );
+    }
+}
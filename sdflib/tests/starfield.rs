@@ -0,0 +1,72 @@
+//! Sanity checks of the starfield - it's random, so there's not much to
+//! compare against, but at least it should look like a night sky: mostly
+//! dark, with a sprinkle of stars.
+//!
+//! Run with `cargo test -p sdf-playground-sdflib`.
+
+use sdf_playground_sdflib::starfield;
+use spirv_std::glam::*;
+
+/// Directions spread (roughly) evenly over the whole sky.
+fn directions() -> impl Iterator<Item = Vec3> {
+    const COUNT: u32 = 200_000;
+
+    // Fibonacci sphere
+    (0..COUNT).map(|idx| {
+        let y =
+            1.0 - 2.0 * (idx as f32 + 0.5) / COUNT as f32;
+        let radius = (1.0 - y * y).sqrt();
+        let angle = idx as f32 * 2.399_963;
+
+        vec3(radius * angle.cos(), y, radius * angle.sin())
+    })
+}
+
+#[test]
+fn stars_are_sparse() {
+    let (lit, total) =
+        directions().fold((0, 0), |(lit, total), dir| {
+            let star = starfield::stars(dir, 0.0);
+
+            assert!(
+                star.is_finite()
+                    && star.min_element() >= 0.0,
+                "invalid color at {dir}: {star}"
+            );
+
+            (
+                lit + (star.max_element() > 0.1) as u32,
+                total + 1,
+            )
+        });
+
+    let lit = lit as f32 / total as f32;
+
+    assert!(
+        lit > 0.0001 && lit < 0.05,
+        "expected a sprinkle of stars, got {:.2}% of the sky lit",
+        lit * 100.0
+    );
+}
+
+#[test]
+fn stars_twinkle() {
+    let twinkled = directions().any(|dir| {
+        let a = starfield::stars(dir, 0.0);
+        let b = starfield::stars(dir, 1.0);
+
+        (a - b).abs().max_element() > 0.01
+    });
+
+    assert!(twinkled);
+}
+
+#[test]
+fn milky_way_only_adds_light() {
+    for dir in directions().step_by(97) {
+        let without = starfield::night_sky(dir, 0.0, false);
+        let with = starfield::night_sky(dir, 0.0, true);
+
+        assert!(with.cmpge(without).all());
+    }
+}
@@ -0,0 +1,148 @@
+//! Benchmarks of the SDFs, running on the CPU.
+//!
+//! Numbers here won't match what the GPU does, of course, but they are stable
+//! (no driver, no vsync, no other apps fighting for the GPU), which makes them
+//! good at catching regressions in the math itself.
+//!
+//! Run with `cargo bench -p sdf-playground-shader`.
+
+use criterion::{
+    black_box, criterion_group, criterion_main, Criterion,
+};
+use sdf_playground_common::glam::*;
+use sdf_playground_common::Hot;
+use sdf_playground_shader::{march, normal, scene, sdf};
+
+fn ocean(c: &mut Criterion) {
+    let point = vec3(1.0, 0.5, -2.0);
+
+    c.bench_function("ocean (all details)", |b| {
+        b.iter(|| {
+            sdf::ocean(
+                black_box(1.0),
+                black_box(point),
+                0.0,
+            )
+        })
+    });
+
+    // Far-away point, for which most of the waves get skipped
+    c.bench_function("ocean (far away)", |b| {
+        b.iter(|| {
+            sdf::ocean(
+                black_box(1.0),
+                black_box(point),
+                0.5,
+            )
+        })
+    });
+}
+
+// (all of the functions below use scene 5, i.e. the ocean, which is the
+// heaviest one)
+
+fn scene_and_normal(c: &mut Criterion) {
+    let point = vec3(1.0, 0.5, -2.0);
+
+    c.bench_function("scene", |b| {
+        b.iter(|| {
+            scene(
+                5,
+                black_box(1.0),
+                &Hot::default(),
+                black_box(point),
+                0.0,
+            )
+        })
+    });
+
+    c.bench_function("normal", |b| {
+        b.iter(|| {
+            normal(
+                5,
+                black_box(1.0),
+                &Hot::default(),
+                black_box(point),
+                0.001,
+            )
+        })
+    });
+}
+
+fn marching(c: &mut Criterion) {
+    let origin = vec3(7.0, 4.0, 7.0);
+
+    // Ray going through the center of the screen (i.e. hitting the scene) and
+    // one going through its corner (i.e. most likely missing it)
+    let hit = sdf_playground_common::direction(
+        origin,
+        Vec3::ZERO,
+        vec2(0.5, 0.5),
+    );
+    let miss = sdf_playground_common::direction(
+        origin,
+        Vec3::ZERO,
+        vec2(0.0, 0.0),
+    );
+
+    c.bench_function("march (hit)", |b| {
+        b.iter(|| {
+            march(
+                5,
+                black_box(1.0),
+                &Hot::default(),
+                origin,
+                black_box(hit),
+                0.002,
+                0.0,
+                64,
+                0.0,
+                100.0,
+            )
+        })
+    });
+
+    // Same as above, but stopping at a fixed epsilon instead of the pixel's
+    // footprint (see `Params::epsilon`)
+    c.bench_function("march (hit, fixed epsilon)", |b| {
+        b.iter(|| {
+            march(
+                5,
+                black_box(1.0),
+                &Hot::default(),
+                origin,
+                black_box(hit),
+                0.002,
+                0.001,
+                64,
+                0.0,
+                100.0,
+            )
+        })
+    });
+
+    c.bench_function("march (miss)", |b| {
+        b.iter(|| {
+            march(
+                5,
+                black_box(1.0),
+                &Hot::default(),
+                origin,
+                black_box(miss),
+                0.002,
+                0.0,
+                64,
+                0.0,
+                100.0,
+            )
+        })
+    });
+}
+
+criterion_group!(
+    benches,
+    ocean,
+    scene_and_normal,
+    marching
+);
+criterion_main!(benches);
@@ -1,20 +1,56 @@
 #![cfg_attr(target_arch = "spirv", no_std)]
 
-use sdf_playground_common::Params;
+pub use sdf_playground_sdflib::{
+    palette, sdf, sdf2d, starfield,
+};
+
+use core::f32::consts::PI;
+use sdf_playground_common::{
+    debug_view, shading, stereo, Hot, Params, Probe, Stats,
+    CHECKERBOARD_TILE, FRUSTUM, MATCAP_SIZE, RELIEF,
+};
+use sdf_playground_sdflib::dual::{Dual, DualVec3};
 use spirv_std::glam::*;
 #[cfg(target_arch = "spirv")]
 use spirv_std::num_traits::*;
-use spirv_std::spirv;
+use spirv_std::{spirv, Image};
 
 /// Signed distance function composing the entire scene.
 ///
 /// As all SDFs do, it returns the closest distance to any object at given
 /// coordinates.
-fn scene(time: f32, point: Vec3) -> f32 {
-    /// Choose which scene to show:
-    const SCENE: u8 = 5;
+///
+/// `pixel_size` says how large (in world units) a single pixel is at `point` -
+/// expensive objects can use it to skip details that wouldn't be visible
+/// anyway (e.g. the ocean computes fewer waves far away from the camera). Zero
+/// means "give me all the details".
+///
+/// `scene_id` says which scene to render (see [`Params::scene`]) - when adding
+/// a new scene, remember to bump [`sdf_playground_common::SCENES`] as well (and
+/// maybe give the scene a background of its own, see [`background()`]).
+///
+/// Any literal can be wrapped in `hot!("name", literal)` to make it tweakable
+/// from the application (see [`Hot`]) - `hot` holds the tweaked values.
+pub fn scene(
+    scene_id: u32,
+    time: f32,
+    hot: &Hot,
+    point: Vec3,
+    pixel_size: f32,
+) -> f32 {
+    // Lets literals be tweaked live, see `Hot`
+    #[allow(unused_macros)]
+    macro_rules! hot {
+        ($name:literal, $default:expr) => {{
+            const KEY: u32 = Hot::key($name);
+            hot.get(KEY, $default)
+        }};
+    }
+
+    // Relief mode, see `Params::relief`
+    let (scene_id, sign) = relief(scene_id);
 
-    match SCENE {
+    let distance = match scene_id {
         1 => {
             // Scene 1: Just a sphere
             sdf::sphere(point, 5.0)
@@ -29,7 +65,8 @@ fn scene(time: f32, point: Vec3) -> f32 {
             // Scene 3: Intersection of sphere & rectangle
             let a = sdf::sphere(
                 point,
-                4.0 + (time * 3.0).sin(),
+                4.0 + (time * hot!("pulse_speed", 3.0))
+                    .sin(),
             );
 
             let b = sdf::rect(point, vec3(3.0, 3.0, 3.0));
@@ -51,7 +88,7 @@ fn scene(time: f32, point: Vec3) -> f32 {
         5 => {
             // Scene 5: Ocean in a sphere
             if point.length() <= 15.0 {
-                let a = sdf::ocean(time, point);
+                let a = sdf::ocean(time, point, pixel_size);
                 let b = sdf::sphere(point, 7.0);
 
                 sdf::intersection(a, b)
@@ -62,7 +99,241 @@ fn scene(time: f32, point: Vec3) -> f32 {
             }
         }
 
+        6 => {
+            // Scene 6: Mandelbulb
+            mandelbulb(point).distance
+        }
+
         _ => f32::MAX,
+    };
+
+    sign * distance
+}
+
+/// Shape enclosing a scene, see [`bounds()`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Bounds {
+    Sphere { radius: f32 },
+    Cuboid { half_size: Vec3 },
+}
+
+/// Returns a shape enclosing given scene (at any moment), so that the
+/// application can draw it as a gizmo - when changing [`scene()`], remember to
+/// update it as well.
+pub fn bounds(scene_id: u32) -> Option<Bounds> {
+    match scene_id {
+        1 => Some(Bounds::Sphere { radius: 5.0 }),
+        2 | 3 => Some(Bounds::Cuboid {
+            half_size: vec3(3.0, 3.0, 3.0),
+        }),
+
+        // (the beating can push the surface at most 1.0 outwards)
+        4 => Some(Bounds::Sphere { radius: 4.0 }),
+
+        5 => Some(Bounds::Sphere { radius: 7.0 }),
+        6 => Some(Bounds::Sphere { radius: 4.5 }),
+        _ => None,
+    }
+}
+
+/// 2D signed distance function shown in the 2D mode (see [`main_fs_2d()`]).
+pub fn scene_2d(time: f32, point: Vec2) -> f32 {
+    let body = sdf2d::round(
+        sdf2d::rect(point, vec2(3.0, 1.5)),
+        0.5,
+    );
+
+    let hole = sdf2d::circle(
+        point - vec2(2.0 * time.cos(), 0.0),
+        1.0,
+    );
+
+    let handle = sdf2d::segment(
+        point,
+        vec2(-4.0, -3.0),
+        vec2(4.0, -3.0),
+    ) - 0.25;
+
+    sdf2d::union(sdf2d::subtraction(body, hole), handle)
+}
+
+/// Mandelbulb used by scene 6, scaled up to match the size of other scenes.
+fn mandelbulb(point: Vec3) -> sdf::Fractal {
+    const SCALE: f32 = 4.0;
+
+    let mut fractal = sdf::mandelbulb(point / SCALE);
+
+    fractal.distance *= SCALE;
+    fractal
+}
+
+/// Returns the base color of surface at given point.
+///
+/// Most scenes are just plain blue, but fractals get colored according to
+/// their orbit traps (see [`sdf::Fractal`]).
+fn albedo(scene_id: u32, point: Vec3) -> Vec3 {
+    match scene_id {
+        6 => trap_color(&mandelbulb(point)),
+        _ => vec3(0.02, 0.19, 0.58),
+    }
+}
+
+/// Returns color of the light scattered inside of the object at given point
+/// (see [`subsurface()`]) - by default it's a brighter version of the
+/// object's albedo, since that's the color the light picks up while bouncing
+/// around inside of it.
+fn subsurface_color(scene_id: u32, point: Vec3) -> Vec3 {
+    albedo(scene_id, point).powf(0.5)
+}
+
+/// Returns color of the background seen in given direction, where rays don't
+/// hit anything.
+///
+/// Scenes can have backgrounds of their own - the rest uses
+/// [`Params::background`]; so does everything when the background is see-
+/// through (see `--transparent`), so that compositing keeps working.
+fn background(
+    params: &Params,
+    scene_id: u32,
+    direction: Vec3,
+) -> Vec4 {
+    if params.background & 0xff != 0xff {
+        return params.background_color();
+    }
+
+    match scene_id {
+        2 => grid_background(direction).extend(1.0),
+        5 => sky(direction, SUN.normalize()).extend(1.0),
+        6 => {
+            let stars = starfield::night_sky(
+                direction,
+                params.time,
+                true,
+            );
+
+            (gradient_background(direction) + stars)
+                .extend(1.0)
+        }
+        _ => params.background_color(),
+    }
+}
+
+/// Dark vertical gradient, slightly brighter towards the horizon.
+fn gradient_background(direction: Vec3) -> Vec3 {
+    let top = vec3(0.01, 0.01, 0.03);
+    let horizon = vec3(0.08, 0.06, 0.12);
+
+    horizon.lerp(top, direction.y.abs().sqrt())
+}
+
+/// Blueprint-like background - lines of latitude and longitude every 15°.
+fn grid_background(direction: Vec3) -> Vec3 {
+    const SPACING: f32 = PI / 12.0;
+
+    // Angular width of the lines, in radians
+    const WIDTH: f32 = 0.004;
+
+    let longitude = direction.z.atan2(direction.x);
+    let latitude = direction.y.clamp(-1.0, 1.0).asin();
+
+    // Angle to the nearest line
+    let line = |angle: f32| {
+        let t = angle / SPACING;
+
+        (t - t.round()).abs() * SPACING
+    };
+
+    // (meridians converge at the poles, so the distance to them shrinks
+    // there)
+    let distance = line(latitude)
+        .min(line(longitude) * latitude.cos());
+
+    let line = 1.0 - (distance / WIDTH).min(1.0);
+
+    vec3(0.02, 0.05, 0.12).lerp(vec3(0.2, 0.4, 0.8), line)
+}
+
+/// Maps orbit traps of a fractal into a color.
+fn trap_color(fractal: &sdf::Fractal) -> Vec3 {
+    // How close the orbit got to the origin, nudged by how many iterations it
+    // took to escape - this picks the color from a palette...
+    let t = fractal.trap.w.sqrt()
+        + 0.05 * fractal.iterations as f32;
+
+    let color = palette::earth(t);
+
+    // ... while closeness to the XZ plane darkens the crevices
+    color * (fractal.trap.y * 4.0).clamp(0.3, 1.0)
+}
+
+/// Same as [`scene()`], but computes the gradient together with the distance -
+/// used by [`normal()`].
+///
+/// Not all scenes are implemented here (differentiating the ocean by hand is
+/// rather tedious) - for those this function returns a zero gradient, which
+/// makes `normal()` fall back to finite differences.
+///
+/// Hot variables used in `scene()` should be used here as well, under the same
+/// names - otherwise normals won't follow the tweaks.
+fn scene_dual(
+    scene_id: u32,
+    time: f32,
+    hot: &Hot,
+    point: DualVec3,
+) -> Dual {
+    // Lets literals be tweaked live, see `Hot`
+    #[allow(unused_macros)]
+    macro_rules! hot {
+        ($name:literal, $default:expr) => {{
+            const KEY: u32 = Hot::key($name);
+            hot.get(KEY, $default)
+        }};
+    }
+
+    // Relief mode, see `Params::relief`
+    let (scene_id, sign) = relief(scene_id);
+
+    let distance = match scene_id {
+        1 => sdf::dual::sphere(point, 5.0),
+        2 => sdf::dual::rect(point, vec3(3.0, 3.0, 3.0)),
+
+        3 => {
+            let a = sdf::dual::sphere(
+                point,
+                4.0 + (time * hot!("pulse_speed", 3.0))
+                    .sin(),
+            );
+
+            let b =
+                sdf::dual::rect(point, vec3(3.0, 3.0, 3.0));
+
+            sdf::dual::intersection(a, b)
+        }
+
+        4 => {
+            let d = (time * 3.0).sin().abs().powf(3.0);
+
+            let d = (point.x * d).sin()
+                * (point.y * d).sin()
+                * (point.z * d).sin();
+
+            sdf::dual::sphere(point, 3.0) + d
+        }
+
+        _ => Dual::constant(f32::MAX),
+    };
+
+    distance * sign
+}
+
+/// Splits scene id passed to [`scene()`] into the actual scene and the sign
+/// its distances get multiplied by - negative in relief mode (see
+/// [`Params::relief`]), which swaps the scene's inside with its outside.
+fn relief(scene_id: u32) -> (u32, f32) {
+    if scene_id & RELIEF != 0 {
+        (scene_id & !RELIEF, -1.0)
+    } else {
+        (scene_id, 1.0)
     }
 }
 
@@ -85,277 +356,2393 @@ pub fn main_vs(
     };
 }
 
+/// Where the camera is located (arbitrary, can be modified) - unless the
+/// scene's been framed (see [`Params::framing`]), in which case only its
+/// direction is kept.
+pub const CAMERA: Vec3 = Vec3::new(7.0, 4.0, 7.0);
+
+/// Height of the ground plane used by [`Params::shadow_catcher`] - just below
+/// the largest of our scenes.
+const GROUND: f32 = -7.0;
+
+/// AOV (see [`main_fs()`]) of pixels that weren't rendered during this frame
+/// (e.g. due to checkerboard rendering) - it has a negative distance, so that
+/// post-processing can tell them apart.
+const UNKNOWN_AOV: Vec4 = Vec4::new(0.0, 0.0, 0.0, -1.0);
+
+/// Where the sun is located (arbitrary, can be modified).
+pub const SUN: Vec3 = Vec3::new(50.0, 100.0, 50.0);
+
+/// Returns where the camera is located, after orbiting it (see
+/// [`Params::orbit`]) and framing the scene (see [`Params::framing`]).
+pub fn camera(params: &Params) -> Vec3 {
+    let offset = if params.framing.w > 0.0 {
+        CAMERA.normalize() * params.framing.w
+    } else {
+        CAMERA
+    };
+
+    params.target()
+        + Mat3::from_rotation_y(params.orbit) * offset
+}
+
 /// Fragment shader, generates color for each pixel on the screen¹.
 ///
+/// Apart from the color, it also outputs the pixel's AOV (*arbitrary output
+/// variable*) - normal of the surface (`xyz`) and its distance from the camera
+/// (`w`), for post-processing effects such as outlines.
+///
 /// ¹ technically for each pixel on the triangle, but since our triangle takes
 ///   the entire screen...
 #[spirv(fragment)]
+#[allow(clippy::too_many_arguments)]
 pub fn main_fs(
     #[spirv(frag_coord)] pos: Vec4,
     #[spirv(descriptor_set = 0, binding = 0, uniform)]
     params: &Params,
+    #[spirv(
+        descriptor_set = 0,
+        binding = 1,
+        storage_buffer
+    )]
+    stats: &mut Stats,
+    #[spirv(descriptor_set = 0, binding = 2)] coarse: &Image!(
+         2D,
+         type = f32,
+         sampled
+     ),
+    #[spirv(descriptor_set = 0, binding = 3)] history: &Image!(
+         2D,
+         type = f32,
+         sampled
+     ),
+    #[spirv(descriptor_set = 0, binding = 4)] matcap: &Image!(
+         2D,
+         type = f32,
+         sampled
+     ),
+    #[spirv(descriptor_set = 0, binding = 5)] prev_aov: &Image!(
+         2D,
+         type = f32,
+         sampled
+     ),
     out_color: &mut Vec4,
+    out_history: &mut Vec4,
+    out_aov: &mut Vec4,
 ) {
-    // Time elapsed since the application started, in seconds
-    let time = params.time;
-
-    // Screen position, remapped to 0..1
-    let uv = pos.xy()
-        / vec2(params.width as f32, params.height as f32);
-
-    // Where the sun is located (arbitrary, can be modified)
-    let sun_pos = vec3(50.0, 100.0, 50.0);
-
-    // Where the camera is located (arbitrary, can be modified)
-    let ray_origin = vec3(7.0, 4.0, 7.0);
-
-    // Where the camera is looking towards; it varies for each pixel, simulating
-    // a perspective projection
-    let ray_direction =
-        sdf_playground_common::direction(ray_origin, uv);
-
-    // -----
-    //
-    // Having everything ready, let's perform the ray-marching!
-    //
-    // This function returns a 3D point of the place in the world "we see" from
-    // our pixel.
-    //
-    // If we see nothing, `march()` will return a point that's infinitely far
-    // away (which we detect below).
-    //
-    let hit_point = march(time, ray_origin, ray_direction);
+    fragment(
+        pos,
+        params,
+        stats,
+        coarse,
+        history,
+        matcap,
+        prev_aov,
+        out_color,
+        out_history,
+        out_aov,
+    );
+}
 
-    *out_color = if hit_point.is_finite() {
-        // We hit something - let's compute normal and perform shading!
-        let hit_normal = normal(time, hit_point);
+/// Fragment shader for the 2D mode - instead of the scene, it shows
+/// [`scene_2d()`] from the top, colored by distance: orange outside, blue
+/// inside, with an isoline every half a unit and the shape's edge in white.
+#[spirv(fragment)]
+pub fn main_fs_2d(
+    #[spirv(frag_coord)] pos: Vec4,
+    #[spirv(descriptor_set = 0, binding = 0, uniform)]
+    params: &Params,
+    out_color: &mut Vec4,
+    out_history: &mut Vec4,
+    out_aov: &mut Vec4,
+) {
+    *out_color = render_2d(pos, params);
+    *out_history = *out_color;
+    *out_aov = UNKNOWN_AOV;
+}
 
-        // Direction from the hit-point to our sun
-        let sun_dir = (sun_pos - hit_point).normalize();
+/// Actual implementation of the 2D mode.
+fn render_2d(pos: Vec4, params: &Params) -> Vec4 {
+    // How many units fit on the shorter side of the window
+    const EXTENT: f32 = 16.0;
 
-        // Cosine of the angle between the hit-point and sun - intuitively:
-        //
-        // - when the angle is 1.0, the surface is pointing straight at the sun:
-        //
-        //     sun
-        //      |
-        //      |
-        //     hit
-        //
-        // - when the angle is between 0.0 and 1.0, the surface is pointing
-        //   *roughly* in the direction of the sun:
-        //
-        //      sun
-        //      /
-        //     /
-        //   hit
-        //
-        // - otherwise, the surface doesn't receive any lightning from the sun:
-        //
-        //   hit -- sun
-        //
-        // tl;dr dot product of two normal vectors is like a similarity metric
-        //       of them - when it's > 0.0, the normals are pointing in a
-        //       similar direction
-        let sun_cosine =
-            hit_normal.dot(sun_dir).clamp(0.0, 1.0);
+    let size =
+        vec2(params.width as f32, params.height as f32);
 
-        // Diffuse lightning - it determines the "base" color of our object
-        let diffuse = vec3(0.02, 0.19, 0.58) * sun_cosine;
+    let point = (pos.xy() - size * 0.5)
+        / size.min_element()
+        * EXTENT;
 
-        // Specular lightning - it shows a nice specular highlight on the place
-        // where the sun shines the most.
-        //
-        // Note that this is a very rough approximation - in principle, we
-        // should, at the very least, compute something called a *half-vector*,
-        // but ain't nobody got time for that.
-        let specular =
-            vec3(1.0, 1.0, 1.0) * sun_cosine.powf(50.0);
+    let point = vec2(point.x, -point.y);
 
-        // Now, let's simply blend both colors together.
-        //
-        // As before, this is kind of an approximation - in principle, we should
-        // use a *tone-mapping operator* here, so that very bright colors (with
-        // R,G,B above > 1.0) can be properly displayed on typical displays.
-        //
-        // Our current approach (of not using any tone-mapping whatsoever) is
-        // alright~ish, it's just that the colors will look a bit washed out.
-        (diffuse + specular).extend(1.0)
-    } else {
-        // We hit nothing - let's output the background color
-        vec4(0.0, 0.0, 0.0, 1.0)
-    };
+    slice_color(scene_2d(params.scene_time(), point))
+        .extend(1.0)
 }
 
-/// Follows a ray from origin through direction and returns the closest surface
-/// hit by that ray.
-///
-/// Intuitively, in two dimensions, if `*` marked the origin and `->` marked the
-/// direction, given a scene such as:
-///
-/// ```text
-/// A          B
-///
-/// F    * ->  C  G
-///
-/// E          D
-/// ```
-///
-/// ... `march()` would return the position of `C`.
-fn march(time: f32, origin: Vec3, direction: Vec3) -> Vec3 {
-    const STEPS: u32 = 64;
+/// Fragment shader for the coarse pre-pass (see [`Params::coarse`]) - marches
+/// the scene at a lower resolution and returns how far each ray got, so that
+/// [`main_fs()`] can start marching from there instead of from the camera.
+#[spirv(fragment)]
+pub fn main_coarse(
+    #[spirv(frag_coord)] pos: Vec4,
+    #[spirv(descriptor_set = 0, binding = 0, uniform)]
+    params: &Params,
+    out_distance: &mut f32,
+) {
+    *out_distance = coarse(pos, params);
+}
 
-    let mut distance = 0.0;
+/// Actual implementation of the coarse pre-pass.
+fn coarse(pos: Vec4, params: &Params) -> f32 {
+    let scale = params.coarse as f32;
 
-    for _ in 0..STEPS {
-        let point = origin + direction * distance;
-        let step = scene(time, point);
+    // (`pos` is the center of a coarse pixel, so scaling it gives us the
+    //  center of the block of pixels it covers)
+    let uv = params.frustum_uv(
+        pos.xy() * scale
+            / vec2(
+                params.width as f32,
+                params.height as f32,
+            ),
+    );
 
-        if step < 0.01 {
-            return point;
-        }
+    // Marching with a wider pixel footprint makes the ray stop as soon as the
+    // entire cone covered by the coarse pixel gets close to a surface, which
+    // is what makes the result a safe starting point for all rays within it
+    //
+    // (that's also why we always use the adaptive threshold here, ignoring
+    // `params.epsilon`)
+    let pixel_footprint = scale * params.pixel_footprint();
 
-        distance += step;
+    let hit = march(
+        params.field(),
+        params.scene_time(),
+        &params.hot,
+        camera(params),
+        sdf_playground_common::direction(
+            camera(params),
+            params.target(),
+            uv,
+        ),
+        pixel_footprint,
+        0.0,
+        params.steps,
+        params.near,
+        params.far,
+    );
 
-        if distance > 100.0 {
-            break;
-        }
+    if hit.exhausted {
+        // We don't know how far it's safe to go, so let's not skip anything
+        0.0
+    } else {
+        hit.distance
     }
+}
 
-    Vec3::INFINITY
+/// Fragment shader for the lower-resolution parts of foveated rendering (see
+/// [`Params::fovea`]) - renders them into a smaller texture, laid out as
+/// described in [`Params::fovea_atlas_size()`], so that the GPU doesn't waste
+/// any time on the skipped pixels.
+#[spirv(fragment)]
+pub fn main_fovea(
+    #[spirv(frag_coord)] pos: Vec4,
+    #[spirv(descriptor_set = 0, binding = 0, uniform)]
+    params: &Params,
+    #[spirv(
+        descriptor_set = 0,
+        binding = 1,
+        storage_buffer
+    )]
+    stats: &mut Stats,
+    #[spirv(descriptor_set = 0, binding = 2)] coarse: &Image!(
+         2D,
+         type = f32,
+         sampled
+     ),
+    #[spirv(descriptor_set = 0, binding = 4)] matcap: &Image!(
+         2D,
+         type = f32,
+         sampled
+     ),
+    out_color: &mut Vec4,
+) {
+    *out_color = fovea(pos, params, stats, coarse, matcap);
 }
 
-/// Returns the normal of surface at given point.
-///
-/// Intuitively, normal describes the orientation ("rotation") of surface at
-/// given point:
-///
-/// ```text
-///     /-----------/
-///    /    ^      /
-///   /     |     /   (so this boi is "looking up")
-///  /           /
-/// /-----------/
-/// ```
-///
-/// Later we calculate the angle between the surface's normal and the sun, which
-/// allows us to compute how bright that surface should be:
-///
-/// ```text
-///         *  (the sun)
-///
-///
-///     /-----------/
-///    /           /|
-///   /     A     / |
-///  /           /  |
-/// /-----------/ C |
-/// |           |   /
-/// |     B     |  /
-/// |           | /
-/// |           |/
-/// \-----------/
-///
-/// (in this case we'd imagine that `A` is bright, while `B` and `C` are black,
-///  since their normals point totally outside "of" the sun)
-/// ```
-fn normal(time: f32, point: Vec3) -> Vec3 {
-    let d = 0.001;
-    let dx = vec3(d, 0.0, 0.0);
-    let dy = vec3(0.0, d, 0.0);
-    let dz = vec3(0.0, 0.0, d);
+/// Actual implementation of the foveated rendering's pass.
+fn fovea(
+    pos: Vec4,
+    params: &Params,
+    stats: &mut Stats,
+    coarse: &Image!(2D, type = f32, sampled),
+    matcap: &Image!(2D, type = f32, sampled),
+) -> Vec4 {
+    let texel = pos.xy().as_uvec2();
 
-    // Calculating normal is as simple taking the derivative of `scene`, but
-    // since (for our purposes here) that function is closed-form, we do the
-    // next best thing:
-    //
-    // Calculate the gradient and use it to estimate the derivative.
+    // Regions are checked from the smallest one, since that's where the larger
+    // ones end
+    let block = if texel.cmpge(params.fovea_region(8)).all()
+    {
+        8
+    } else if texel.cmpge(params.fovea_region(4)).all() {
+        4
+    } else {
+        2
+    };
 
-    let gx =
-        scene(time, point + dx) - scene(time, point - dx);
+    let pixel =
+        (texel - params.fovea_region(block)) * block;
 
-    let gy =
-        scene(time, point + dy) - scene(time, point - dy);
+    // Texels outside of the screen or of the tiles with this block don't get
+    // read, so there's no need to render them
+    if pixel.x >= params.width
+        || pixel.y >= params.height
+        || !params.fovea_renders(pixel, block)
+    {
+        return Vec4::ZERO;
+    }
 
-    let gz =
-        scene(time, point + dz) - scene(time, point - dz);
+    let pos =
+        (pixel.as_vec2() + 0.5).extend(0.0).extend(1.0);
+    let mut rays = Rays::default();
+    let mut aov = UNKNOWN_AOV;
 
-    vec3(gx, gy, gz).normalize()
-}
+    let color = shade(
+        pos,
+        params,
+        matcap,
+        coarse_start(pos, params, coarse),
+        &mut aov,
+        &mut rays,
+    );
 
-mod sdf {
-    #![allow(unused)]
+    rays.store(stats);
+    color
+}
 
-    use super::*;
+/// Actual implementation of the fragment shader.
+#[allow(clippy::too_many_arguments)]
+fn fragment(
+    pos: Vec4,
+    params: &Params,
+    stats: &mut Stats,
+    coarse: &Image!(2D, type = f32, sampled),
+    history: &Image!(2D, type = f32, sampled),
+    matcap: &Image!(2D, type = f32, sampled),
+    prev_aov: &Image!(2D, type = f32, sampled),
+    out_color: &mut Vec4,
+    out_history: &mut Vec4,
+    out_aov: &mut Vec4,
+) {
+    // Rays we've traced for this pixel, added into `stats` at the end
+    let mut rays = Rays::default();
 
-    pub fn union(f1: f32, f2: f32) -> f32 {
-        f1.min(f2)
-    }
+    *out_aov = UNKNOWN_AOV;
 
-    pub fn subtraction(f1: f32, f2: f32) -> f32 {
-        f1.max(-f2)
+    if params.gallery != 0 {
+        *out_color =
+            gallery(pos, params, matcap, &mut rays);
+        *out_history = *out_color;
+        rays.store(stats);
+        return;
     }
 
-    pub fn intersection(f1: f32, f2: f32) -> f32 {
-        f1.max(f2)
-    }
+    let pixel = pos.xy().as_uvec2();
 
-    pub fn repeat(p: Vec3, s: Vec3) -> Vec3 {
-        p - s * (p / s).round()
+    if params.probe.z != 0.0
+        && pixel == params.probe.xy().as_uvec2()
+    {
+        probe(pos, params, matcap, &mut stats.probe);
     }
 
-    pub fn sphere(p: Vec3, r: f32) -> f32 {
-        p.length() - r
+    // Whatever we render gets also stored in the history, so that the next
+    // frame can reuse it instead of rendering given pixel again:
+    //
+    // - checkerboard rendering renders only half of the tiles each frame
+    //   (alternating, like black and white fields of a checkerboard) and fills
+    //   the other half with what was rendered there in the previous frame; on
+    //   static scenes that's indistinguishable from the real thing, on animated
+    //   ones the skipped tiles lag a frame behind.
+    //
+    //   (skipping single pixels wouldn't save anything, since the GPU shades
+    //   pixels in groups - a group with even one pixel left to render takes
+    //   as long as if it rendered all of them)
+    let tile = pixel / CHECKERBOARD_TILE;
+
+    let skipped_by_checkerboard = params.checkerboard != 0
+        && (tile.x + tile.y + params.frame) % 2 == 1;
+
+    // - region of interest renders only the pixels inside of it, keeping the
+    //   rest of the screen frozen,
+    let roi = params.roi;
+
+    let skipped_by_roi = roi.x < roi.z
+        && roi.y < roi.w
+        && (pos.x < roi.x
+            || pos.x > roi.z
+            || pos.y < roi.y
+            || pos.y > roi.w);
+
+    // - foveated rendering renders tiles away from the point of focus at a
+    //   lower resolution, in a separate pass (see `main_fovea()`); they get
+    //   upscaled into the history afterwards, so here we skip them entirely.
+    let skipped_by_fovea = params.fovea_block(pixel) > 1;
+
+    // When the camera moves, the previous frame doesn't line up with the
+    // current one anymore - so instead of reusing the same pixel, checkerboard
+    // looks up where the pixel's surface has been seen previously
+    let camera_moved = params.prev_camera.y != 0.0
+        && params.camera_moved()
+        && params.stereo == stereo::OFF;
+
+    if skipped_by_roi
+        || skipped_by_fovea
+        || (skipped_by_checkerboard && !camera_moved)
+    {
+        *out_color = history.fetch(pixel.as_ivec2());
+        *out_history = *out_color;
+
+        // (as long as the camera hasn't moved, what's been seen there
+        // previously is still there)
+        if !camera_moved && !skipped_by_fovea {
+            *out_aov = prev_aov.fetch(pixel.as_ivec2());
+        }
+
+        return;
+    }
+
+    if skipped_by_checkerboard
+        && reproject(
+            pos, params, history, prev_aov, out_color,
+            out_aov,
+        )
+    {
+        *out_history = *out_color;
+        return;
+    }
+
+    let start = coarse_start(pos, params, coarse);
+
+    // (with TAA, each frame looks at a slightly different spot within the
+    // pixel, so that the accumulated history covers all of it)
+    let jittered = if params.taa_enabled() {
+        pos + params.taa.yz().extend(0.0).extend(0.0)
+    } else {
+        pos
+    };
+
+    *out_color = shade(
+        jittered, params, matcap, start, out_aov, &mut rays,
+    );
+
+    *out_history = *out_color;
+    rays.store(stats);
+}
+
+/// Renders the scene at given pixel - shared by the full-resolution pass and
+/// the lower-resolution one of foveated rendering (see [`main_fovea()`]).
+fn shade(
+    pos: Vec4,
+    params: &Params,
+    matcap: &Image!(2D, type = f32, sampled),
+    start: f32,
+    out_aov: &mut Vec4,
+    rays: &mut Rays,
+) -> Vec4 {
+    let color = if params.shutter <= 0.0 {
+        render_antialiased(
+            pos, params, matcap, start, out_aov, rays,
+        )
+    } else {
+        render_blurred(pos, params, matcap, out_aov, rays)
+    };
+
+    if params.crossfade <= 0.0 {
+        return color;
+    }
+
+    // We're in the middle of switching scenes - render the previous one as
+    // well and blend it into the current one (the coarse pre-pass marched only
+    // the current scene, so the previous one starts from scratch)
+    let mut prev_params = *params;
+    let mut prev_aov = UNKNOWN_AOV;
+
+    prev_params.scene = params.prev_scene;
+
+    let prev_color = if params.shutter <= 0.0 {
+        render_antialiased(
+            pos,
+            &prev_params,
+            matcap,
+            0.0,
+            &mut prev_aov,
+            rays,
+        )
+    } else {
+        render_blurred(
+            pos,
+            &prev_params,
+            matcap,
+            &mut prev_aov,
+            rays,
+        )
+    };
+
+    color.lerp(prev_color, params.crossfade)
+}
+
+/// Statistics of the primary rays traced for a single pixel - gathered
+/// locally and added into [`Stats`] once the pixel is done, so that there's
+/// just a couple of atomic operations per pixel instead of one per step.
+#[derive(Clone, Copy, Default)]
+struct Rays {
+    count: u32,
+    hits: u32,
+    exhausted: u32,
+    steps: u32,
+}
+
+impl Rays {
+    fn record(&mut self, hit: &Hit) {
+        self.count += 1;
+        self.steps += hit.steps;
+
+        if hit.point.is_finite() {
+            self.hits += 1;
+        }
+
+        if hit.exhausted {
+            self.exhausted += 1;
+        }
+    }
+
+    fn store(&self, stats: &mut Stats) {
+        if self.count == 0 {
+            return;
+        }
+
+        atomic_add(&mut stats.rays, self.count);
+        atomic_add(&mut stats.hits, self.hits);
+        atomic_add(&mut stats.steps, self.steps);
+
+        if self.exhausted > 0 {
+            atomic_add(
+                &mut stats.exhausted_rays,
+                self.exhausted,
+            );
+            atomic_add(&mut stats.exhausted, 1);
+        }
+    }
+}
+
+/// Atomically adds `value` to `counter`, which lives in a storage buffer
+/// shared by all the pixels.
+fn atomic_add(counter: &mut u32, value: u32) {
+    unsafe {
+        spirv_std::arch::atomic_i_add::<
+            u32,
+            { spirv_std::memory::Scope::Device as u32 },
+            { spirv_std::memory::Semantics::NONE.bits() },
+        >(counter, value);
+    }
+}
+
+/// Fills given pixel with what the previous frame has rendered at the same
+/// point of the scene, even though the camera has moved since then (see
+/// [`Params::prev_camera`]).
+///
+/// Only the primary ray gets marched, which is way cheaper than shading it -
+/// its hit point is projected onto the previous camera's screen and looked up
+/// in the history, as long as the previous frame has seen the same surface
+/// there (i.e. its distance, stored in the previous AOV, matches).
+///
+/// Returns `false` when that's not the case (e.g. the surface has been hidden
+/// behind something), in which case the pixel has to be rendered from scratch.
+fn reproject(
+    pos: Vec4,
+    params: &Params,
+    history: &Image!(2D, type = f32, sampled),
+    prev_aov: &Image!(2D, type = f32, sampled),
+    out_color: &mut Vec4,
+    out_aov: &mut Vec4,
+) -> bool {
+    let size =
+        vec2(params.width as f32, params.height as f32);
+    let ray_origin = camera(params);
+
+    let ray_direction = sdf_playground_common::direction(
+        ray_origin,
+        params.target(),
+        params.frustum_uv(pos.xy() / size),
+    );
+
+    let pixel_footprint = params.pixel_footprint();
+
+    let hit = march(
+        params.field(),
+        params.scene_time(),
+        &params.hot,
+        ray_origin,
+        ray_direction,
+        pixel_footprint,
+        params.epsilon,
+        params.steps,
+        params.near,
+        params.far,
+    );
+
+    if hit.exhausted {
+        return false;
+    }
+
+    let hit_point = if hit.point.is_finite() {
+        hit.point
+    } else {
+        ray_origin + ray_direction * params.far
+    };
+
+    let Some(prev_pos) = project_prev(params, hit_point)
+    else {
+        return false;
+    };
+
+    let prev_pixel = prev_pos.floor().as_ivec2();
+
+    if !seen_previously(
+        params,
+        prev_aov,
+        prev_pixel,
+        hit_point,
+        hit.point.is_finite(),
+    ) {
+        return false;
+    }
+
+    *out_color = history.fetch(prev_pixel);
+
+    *out_aov = if hit.point.is_finite() {
+        normal(
+            params.field(),
+            params.scene_time(),
+            &params.hot,
+            hit.point,
+            hit.distance * pixel_footprint,
+        )
+        .extend(hit.distance)
+    } else {
+        vec4(0.0, 0.0, 0.0, params.far)
+    };
+
+    true
+}
+
+/// Returns where given point of the scene was on the previous frame's screen
+/// (see [`Params::prev_camera`]), in pixels - or `None` if it was off-screen.
+fn project_prev(
+    params: &Params,
+    point: Vec3,
+) -> Option<Vec2> {
+    let size =
+        vec2(params.width as f32, params.height as f32);
+
+    let prev_params = params.prev();
+    let prev_origin = camera(&prev_params);
+
+    let prev_camera = sdf_playground_common::look_at(
+        prev_origin,
+        prev_params.target(),
+        Vec3::Y,
+    );
+
+    let local =
+        prev_camera.transpose() * (point - prev_origin);
+
+    if local.z <= 0.0 {
+        return None;
+    }
+
+    // Inverse of `ray_direction()` and `Params::frustum_uv()`
+    let screen = vec2(local.x, -local.y) / local.z;
+    let min = params.frustum.xy();
+    let max = params.frustum.zw();
+    let prev_pos = (screen - min) / (max - min) * size;
+
+    if prev_pos.cmplt(Vec2::ZERO).any()
+        || prev_pos.cmpge(size).any()
+    {
+        return None;
+    }
+
+    Some(prev_pos)
+}
+
+/// Returns whether the previous frame has seen given point (a surface, if
+/// `is_surface`, or the background past the far plane otherwise) at given
+/// pixel - i.e. whether the distance stored there in the previous AOV matches.
+fn seen_previously(
+    params: &Params,
+    prev_aov: &Image!(2D, type = f32, sampled),
+    prev_pixel: IVec2,
+    point: Vec3,
+    is_surface: bool,
+) -> bool {
+    let prev_distance = prev_aov.fetch(prev_pixel).w;
+
+    if !is_surface {
+        return prev_distance >= params.far;
+    }
+
+    let distance =
+        (point - camera(&params.prev())).length();
+
+    // (AOV is stored in half-precision, hence the tolerance)
+    prev_distance >= 0.0
+        && (prev_distance - distance).abs()
+            < distance
+                * (0.01 + 2.0 * params.pixel_footprint())
+}
+
+/// Records values of given pixel into `probe` (see [`Params::probe`]).
+///
+/// The ray is traced again from scratch (through the pixel's center, ignoring
+/// stereo, anti-aliasing etc.), so that the probe doesn't have to be threaded
+/// through all the rendering functions - and so that whatever you're debugging
+/// can be easily copied here and stored into [`Probe::custom`].
+fn probe(
+    pos: Vec4,
+    params: &Params,
+    matcap: &Image!(2D, type = f32, sampled),
+    probe: &mut Probe,
+) {
+    let uv = pos.xy()
+        / vec2(params.width as f32, params.height as f32);
+
+    let ray_origin = camera(params);
+
+    let ray_direction = sdf_playground_common::direction(
+        ray_origin,
+        params.target(),
+        params.frustum_uv(uv),
+    );
+
+    let pixel_footprint = params.pixel_footprint();
+
+    let hit = march(
+        params.field(),
+        params.scene_time(),
+        &params.hot,
+        ray_origin,
+        ray_direction,
+        pixel_footprint,
+        params.epsilon,
+        params.steps,
+        params.near,
+        params.far,
+    );
+
+    probe.written = 1;
+    probe.steps = hit.steps;
+    probe.exhausted = if hit.exhausted { 1 } else { 0 };
+
+    if hit.point.is_finite() {
+        let normal = normal(
+            params.field(),
+            params.scene_time(),
+            &params.hot,
+            hit.point,
+            hit.distance * pixel_footprint,
+        );
+
+        probe.distance = hit.distance;
+        probe.normal = normal.to_array();
+        probe.albedo =
+            albedo(params.scene, hit.point).to_array();
+        probe.shading = params.shading;
+    } else {
+        probe.distance = f32::INFINITY;
+    }
+
+    let mut aov = UNKNOWN_AOV;
+    let mut rays = Rays::default();
+
+    probe.color = render(
+        pos, params, matcap, 0.0, &mut aov, &mut rays,
+    )
+    .truncate()
+    .to_array();
+}
+
+/// Returns color of given pixel of the gallery (see [`Params::gallery`]).
+fn gallery(
+    pos: Vec4,
+    params: &Params,
+    matcap: &Image!(2D, type = f32, sampled),
+    rays: &mut Rays,
+) -> Vec4 {
+    // Moment at which all thumbnails are rendered - fixed, so that the gallery
+    // doesn't flicker with all the scenes animating at once
+    const TIME: f32 = 2.0;
+
+    let (scene_id, uv) = params.gallery_scene_at(pos.xy());
+
+    if scene_id == 0 {
+        return vec4(0.0, 0.0, 0.0, 1.0);
+    }
+
+    if uv.cmplt(Vec2::ZERO).any()
+        || uv.cmpgt(Vec2::ONE).any()
+    {
+        // We're in the gap around a thumbnail - let's use it to highlight the
+        // scene that's currently selected
+        return if scene_id == params.scene {
+            vec4(1.0, 1.0, 1.0, 1.0)
+        } else {
+            vec4(0.0, 0.0, 0.0, 1.0)
+        };
+    }
+
+    let size = params.gallery_thumbnail_size();
+    let mut params = *params;
+    let mut aov = UNKNOWN_AOV;
+
+    params.scene = scene_id;
+    params.time = TIME;
+    params.width = size.x as u32;
+    params.height = size.y as u32;
+    params.debug_view = debug_view::NONE;
+    params.stereo = stereo::OFF;
+    params.frustum = FRUSTUM;
+    params.framing = Vec4::ZERO;
+    params.relief = Vec4::ZERO;
+
+    render(
+        (uv * size).extend(0.0).extend(0.0),
+        &params,
+        matcap,
+        0.0,
+        &mut aov,
+        rays,
+    )
+}
+
+/// Returns color of given pixel, averaged over `params.samples²` positions
+/// spread evenly over the pixel's area (see [`Params::samples`]).
+///
+/// `aov` gets set to the AOV of the last sample.
+fn render_antialiased(
+    pos: Vec4,
+    params: &Params,
+    matcap: &Image!(2D, type = f32, sampled),
+    start: f32,
+    aov: &mut Vec4,
+    rays: &mut Rays,
+) -> Vec4 {
+    let samples = params.samples.max(1);
+    let mut color = Vec4::ZERO;
+
+    for y in 0..samples {
+        for x in 0..samples {
+            let offset = (vec2(x as f32, y as f32) + 0.5)
+                / samples as f32
+                - 0.5;
+
+            color += render(
+                pos + offset.extend(0.0).extend(0.0),
+                params,
+                matcap,
+                start,
+                aov,
+                rays,
+            );
+        }
+    }
+
+    color / (samples * samples) as f32
+}
+
+/// Returns color of given pixel, averaged over the time during which the
+/// virtual camera's shutter is open.
+///
+/// `aov` gets set to the AOV of the last sample.
+fn render_blurred(
+    pos: Vec4,
+    params: &Params,
+    matcap: &Image!(2D, type = f32, sampled),
+    aov: &mut Vec4,
+    rays: &mut Rays,
+) -> Vec4 {
+    // Motion blur - instead of rendering the scene at a single moment, we
+    // render it a couple of times over the period during which the shutter is
+    // open and average the results, just like a real camera would.
+    //
+    // Samples are jittered per pixel, so that instead of a couple of distinct
+    // copies of fast-moving objects we get a (slightly noisy) smooth blur.
+    const SAMPLES: u32 = 8;
+
+    let jitter =
+        (pos.xy().dot(vec2(12.9898, 78.233)).sin()
+            * 43_758.547)
+            .fract();
+
+    let mut color = Vec4::ZERO;
+
+    for sample in 0..SAMPLES {
+        let mut params = *params;
+
+        params.time -= params.shutter
+            * (sample as f32 + jitter)
+            / SAMPLES as f32;
+
+        color +=
+            render(pos, &params, matcap, 0.0, aov, rays);
+    }
+
+    color / SAMPLES as f32
+}
+
+/// Returns how far the ray for given pixel can safely skip ahead, according
+/// to the coarse pre-pass; zero if the pre-pass is disabled.
+fn coarse_start(
+    pos: Vec4,
+    params: &Params,
+    coarse: &Image!(2D, type = f32, sampled),
+) -> f32 {
+    if !params.coarse_enabled() {
+        return 0.0;
+    }
+
+    let scale = params.coarse as f32;
+    let size = params.coarse_size().as_ivec2();
+
+    // Our pixel lies somewhere between four coarse pixels - since the surface
+    // might be closer in any of them, we take the minimum
+    let base = (pos.xy() / scale - 0.5).floor().as_ivec2();
+    let mut distance = f32::INFINITY;
+
+    for dy in 0..2 {
+        for dx in 0..2 {
+            let texel = (base + ivec2(dx, dy))
+                .clamp(IVec2::ZERO, size - 1);
+
+            distance = distance.min(coarse.fetch(texel).x);
+        }
+    }
+
+    // Coarse rays stop within a (coarse) pixel's width of the surface, so
+    // let's back off by that much, to be on the safe side
+    let pixel_footprint = scale * params.pixel_footprint();
+
+    (distance * (1.0 - pixel_footprint)).max(0.0)
+}
+
+/// Returns color of given pixel at the moment specified by `params.time`.
+///
+/// `start` says how far along the ray we can start marching (see
+/// [`coarse_start()`]); `aov` gets set to the pixel's AOV (see [`main_fs()`]).
+fn render(
+    pos: Vec4,
+    params: &Params,
+    matcap: &Image!(2D, type = f32, sampled),
+    start: f32,
+    aov: &mut Vec4,
+    rays: &mut Rays,
+) -> Vec4 {
+    // Screen position, remapped to 0..1
+    let uv = pos.xy()
+        / vec2(params.width as f32, params.height as f32);
+
+    let camera = camera(params);
+
+    let pixel_footprint = params.pixel_footprint();
+
+    // For stereo rendering, each eye is moved a bit to the side - and since we
+    // always look at the same point, both eyes converge there
+    let eye = |side: f32| {
+        let right = sdf_playground_common::look_at(
+            camera,
+            params.target(),
+            Vec3::Y,
+        )
+        .x_axis;
+
+        camera + right * side * params.eye_separation * 0.5
+    };
+
+    match params.stereo {
+        stereo::ANAGLYPH => {
+            let left = trace(
+                params,
+                matcap,
+                eye(-1.0),
+                uv,
+                pixel_footprint,
+                0.0,
+                aov,
+                rays,
+            );
+
+            let right = trace(
+                params,
+                matcap,
+                eye(1.0),
+                uv,
+                pixel_footprint,
+                0.0,
+                aov,
+                rays,
+            );
+
+            // Red-cyan glasses: red channel for the left eye, green and blue
+            // for the right one; using luminance for the red channel instead
+            // of just the red component gives less retinal rivalry
+            let left = left
+                .truncate()
+                .dot(vec3(0.299, 0.587, 0.114));
+
+            vec4(left, right.y, right.z, 1.0)
+        }
+
+        stereo::SIDE_BY_SIDE => {
+            // Left half of the screen for the left eye, right half for the
+            // right eye
+            let (side, uv) = if uv.x < 0.5 {
+                (-1.0, vec2(uv.x * 2.0, uv.y))
+            } else {
+                (1.0, vec2(uv.x * 2.0 - 1.0, uv.y))
+            };
+
+            trace(
+                params,
+                matcap,
+                eye(side),
+                uv,
+                pixel_footprint,
+                0.0,
+                aov,
+                rays,
+            )
+        }
+
+        _ => trace(
+            params,
+            matcap,
+            camera,
+            uv,
+            pixel_footprint,
+            start,
+            aov,
+            rays,
+        ),
+    }
+}
+
+/// Returns color of the scene as seen from `ray_origin` through screen position
+/// `uv`.
+///
+/// Marching starts `start` units away from the origin; the ray gets recorded
+/// into `rays`. `aov` gets set to the AOV of the pixel (see [`main_fs()`]).
+#[allow(clippy::too_many_arguments)]
+fn trace(
+    params: &Params,
+    matcap: &Image!(2D, type = f32, sampled),
+    ray_origin: Vec3,
+    uv: Vec2,
+    pixel_footprint: f32,
+    start: f32,
+    aov: &mut Vec4,
+    rays: &mut Rays,
+) -> Vec4 {
+    // Time elapsed since the application started, in seconds (remapped
+    // through `params.time_curve`)
+    let time = params.scene_time();
+
+    // Which scene we're looking at - and the id its distance field gets
+    // evaluated with (see `Params::field()`)
+    let scene_id = params.scene;
+    let field = params.field();
+
+    // Where the camera is looking towards; it varies for each pixel, simulating
+    // a perspective projection
+    let ray_direction = sdf_playground_common::direction(
+        ray_origin,
+        params.target(),
+        params.frustum_uv(uv),
+    );
+
+    // -----
+    //
+    // Having everything ready, let's perform the ray-marching!
+    //
+    // This function returns a 3D point of the place in the world "we see" from
+    // our pixel.
+    //
+    // If we see nothing, `march()` will return a point that's infinitely far
+    // away (which we detect below).
+    //
+    let hit = march(
+        field,
+        time,
+        &params.hot,
+        ray_origin,
+        ray_direction,
+        pixel_footprint,
+        params.epsilon,
+        params.steps,
+        start.max(params.near),
+        params.far,
+    );
+    let hit_point = hit.point;
+
+    rays.record(&hit);
+    *aov = vec4(0.0, 0.0, 0.0, params.far);
+
+    let mut color = if hit_point.is_finite() {
+        // We hit something - let's compute normal and perform shading!
+        let hit_normal = normal(
+            field,
+            time,
+            &params.hot,
+            hit_point,
+            hit.distance * pixel_footprint,
+        );
+
+        *aov = hit_normal.extend(hit.distance);
+
+        // Direction from the hit-point to our sun
+        let sun_dir = (SUN - hit_point).normalize();
+
+        // Cosine of the angle between the hit-point and sun - intuitively:
+        //
+        // - when the angle is 1.0, the surface is pointing straight at the sun:
+        //
+        //     sun
+        //      |
+        //      |
+        //     hit
+        //
+        // - when the angle is between 0.0 and 1.0, the surface is pointing
+        //   *roughly* in the direction of the sun:
+        //
+        //      sun
+        //      /
+        //     /
+        //   hit
+        //
+        // - otherwise, the surface doesn't receive any lightning from the sun:
+        //
+        //   hit -- sun
+        //
+        // tl;dr dot product of two normal vectors is like a similarity metric
+        //       of them - when it's > 0.0, the normals are pointing in a
+        //       similar direction
+        let sun_cosine =
+            hit_normal.dot(sun_dir).clamp(0.0, 1.0);
+
+        // Shadows - how much of the sun's light actually reaches the point
+        // (see `shadow_model()`)
+        let light = shadow(
+            field,
+            time,
+            &params.hot,
+            secondary_origin(
+                params,
+                hit_point,
+                hit_normal,
+                hit.distance,
+                pixel_footprint,
+            ),
+            sun_dir,
+            pixel_footprint,
+            params.shadow_steps,
+        );
+
+        let sun_cosine = sun_cosine * light;
+
+        // Diffuse lightning - it determines the "base" color of our object;
+        // underwater, it also includes the light focused by the waves (see
+        // `caustics()`)
+        let diffuse = albedo(scene_id, hit_point)
+            * sun_cosine
+            * (1.0
+                + caustics(
+                    scene_id, time, hit_point, sun_dir,
+                ));
+
+        // Specular lightning - it shows a nice specular highlight on the place
+        // where the sun shines the most.
+        //
+        // Note that this is a very rough approximation - in principle, we
+        // should, at the very least, compute something called a *half-vector*,
+        // but ain't nobody got time for that.
+        let specular =
+            vec3(1.0, 1.0, 1.0) * sun_cosine.powf(50.0);
+
+        // Subsurface scattering - light that entered the object somewhere
+        // else, bounced around inside of it and came out here; the thinner
+        // the object is behind this point, the more of it gets through
+        let subsurface = if params.subsurface > 0.0 {
+            subsurface_color(scene_id, hit_point)
+                * params.subsurface
+                * subsurface(
+                    field,
+                    time,
+                    &params.hot,
+                    hit_point,
+                    hit_normal,
+                    sun_dir,
+                )
+        } else {
+            Vec3::ZERO
+        };
+
+        // Now, let's simply blend both colors together.
+        //
+        // As before, this is kind of an approximation - in principle, we should
+        // use a *tone-mapping operator* here, so that very bright colors (with
+        // R,G,B above > 1.0) can be properly displayed on typical displays.
+        //
+        // Our current approach (of not using any tone-mapping whatsoever) is
+        // alright~ish, it's just that the colors will look a bit washed out.
+        if params.shading == shading::MATCAP {
+            matcap_color(
+                matcap,
+                ray_origin,
+                params.target(),
+                hit_normal,
+            )
+            .extend(1.0)
+        } else if params.shading == shading::TOON {
+            toon(
+                params,
+                albedo(scene_id, hit_point),
+                hit_normal,
+                ray_direction,
+                sun_cosine,
+            )
+            .extend(1.0)
+        } else if is_water(
+            scene_id,
+            time,
+            hit_point,
+            hit.distance * pixel_footprint,
+        ) {
+            // Water gets its own shading, see `water()`
+            water(
+                params,
+                scene_id,
+                field,
+                time,
+                hit_point,
+                hit_normal,
+                ray_direction,
+                sun_dir,
+                light,
+                hit.distance,
+                pixel_footprint,
+            )
+            .extend(1.0)
+        } else {
+            // Ambient lighting - the sky lights up what the sun can't reach
+            let ambient = albedo(scene_id, hit_point)
+                * sky_light(hit_normal)
+                * params.ambient.x;
+
+            (diffuse + specular + subsurface + ambient)
+                .extend(1.0)
+        }
+    } else {
+        // We hit nothing - let's output the background
+        background(params, scene_id, ray_direction)
+    };
+
+    if params.shadow_catcher != 0 {
+        // If the ray hits the ground plane before anything else, we show
+        // just the shadow cast there by the scene - black, with opacity
+        // saying how deep the shadow is, so that it can be composited over
+        // whatever's behind
+        let plane_distance =
+            (GROUND - ray_origin.y) / ray_direction.y;
+
+        if plane_distance > 0.0
+            && plane_distance < hit.distance
+            && plane_distance < params.far
+        {
+            let point =
+                ray_origin + ray_direction * plane_distance;
+
+            let light = soft_shadow(
+                field,
+                time,
+                &params.hot,
+                secondary_origin(
+                    params,
+                    point,
+                    Vec3::Y,
+                    plane_distance,
+                    pixel_footprint,
+                ),
+                (SUN - point).normalize(),
+                pixel_footprint,
+                params.shadow_steps,
+            );
+
+            color = params.background_color().lerp(
+                vec4(0.0, 0.0, 0.0, 1.0),
+                1.0 - light,
+            );
+        }
+    }
+
+    if params.fog.x > 0.0 {
+        // (rays that miss get fogged as if they've stopped at the far plane,
+        // so that the fog doesn't end abruptly at the horizon)
+        let amount = fog(
+            params,
+            ray_origin,
+            ray_direction,
+            hit.distance.min(params.far),
+        );
+
+        let sky = sky(
+            ray_direction,
+            (SUN - ray_origin).normalize(),
+        );
+
+        color = color
+            .truncate()
+            .lerp(sky, amount)
+            .extend(color.w.max(amount));
+    }
+
+    if params.glow.w > 0.0 && !hit_point.is_finite() {
+        // Rays that barely miss the scene get a halo, fading away with how
+        // close they've got - the closer, the brighter
+        let glow = (-hit.closest / params.glow.w).exp();
+
+        color = (color.truncate()
+            + params.glow.truncate() * glow)
+            .extend(color.w.max(glow));
+    }
+
+    if params.debug_view == debug_view::EXPOSURE {
+        let pixel = uv
+            * vec2(
+                params.width as f32,
+                params.height as f32,
+            );
+
+        color =
+            exposure_color(color.truncate(), pixel, time)
+                .extend(color.w);
+    }
+
+    if params.debug_view == debug_view::STEPS {
+        color = palette::heat(
+            hit.steps as f32 / params.steps as f32,
+        )
+        .extend(1.0);
+    }
+
+    if params.debug_view == debug_view::LIPSCHITZ
+        && hit.lipschitz_violated
+    {
+        // Paint offending pixels magenta, keeping a bit of the original color
+        // so that it's still possible to tell which object is broken
+        color = color
+            .truncate()
+            .lerp(vec3(1.0, 0.0, 1.0), 0.75)
+            .extend(1.0);
+    }
+
+    if params.debug_view == debug_view::SLICE {
+        // Where the ray intersects the cutting plane - if it does, we paint
+        // the plane over whatever the scene looks like there
+        let plane_normal = params.slice.xyz().normalize();
+
+        let plane_distance = (params.slice.w
+            - plane_normal.dot(ray_origin))
+            / plane_normal.dot(ray_direction);
+
+        if plane_distance > 0.0 {
+            let point =
+                ray_origin + ray_direction * plane_distance;
+
+            color = slice_color(scene(
+                field,
+                time,
+                &params.hot,
+                point,
+                0.0,
+            ))
+            .extend(1.0);
+        }
+    }
+
+    color
+}
+
+/// Whether we should reflect other objects in the water (see [`water()`]) -
+/// looks nicer, but requires marching another ray for each pixel.
+const WATER_REFLECTIONS: bool = true;
+
+/// Returns whether `point` lies on the water's surface, as opposed to some
+/// other object.
+fn is_water(
+    scene_id: u32,
+    time: f32,
+    point: Vec3,
+    pixel_size: f32,
+) -> bool {
+    match scene_id {
+        // (the scene is an intersection, i.e. the larger of both distances,
+        //  so whichever one is larger says where we are)
+        5 => {
+            sdf::ocean(time, point, pixel_size)
+                >= sdf::sphere(point, 7.0)
+        }
+
+        _ => false,
+    }
+}
+
+/// Returns color of the water's surface at given point, as seen from
+/// `direction` - it's a blend between the light reflected by the surface (of
+/// the sky, sun and - with [`WATER_REFLECTIONS`] - other objects) and the
+/// light scattered inside of the water, weighted according to the Fresnel
+/// effect (water at grazing angles is almost a perfect mirror).
+///
+/// `scene_id`, `field` and `time` are the ones the ray's been traced with (see
+/// [`trace()`]), so that reflections show the same scene at the same moment.
+#[allow(clippy::too_many_arguments)]
+fn water(
+    params: &Params,
+    scene_id: u32,
+    field: u32,
+    time: f32,
+    point: Vec3,
+    normal: Vec3,
+    direction: Vec3,
+    sun_dir: Vec3,
+    light: f32,
+    distance: f32,
+    pixel_footprint: f32,
+) -> Vec3 {
+    // How much light is reflected when looking straight at the surface
+    const REFLECTANCE: f32 = 0.02;
+
+    let reflected = (direction
+        - 2.0 * direction.dot(normal) * normal)
+        .normalize();
+
+    let mut reflection = sky(reflected, sun_dir);
+
+    if WATER_REFLECTIONS {
+        let hit = march(
+            field,
+            time,
+            &params.hot,
+            secondary_origin(
+                params,
+                point,
+                normal,
+                distance,
+                pixel_footprint,
+            ),
+            reflected,
+            pixel_footprint,
+            params.epsilon,
+            params.steps,
+            0.0,
+            params.far,
+        );
+
+        if hit.point.is_finite() {
+            let hit_normal = self::normal(
+                field,
+                time,
+                &params.hot,
+                hit.point,
+                hit.distance * pixel_footprint,
+            );
+
+            reflection = albedo(scene_id, hit.point)
+                * hit_normal.dot(sun_dir).clamp(0.0, 1.0);
+        }
+    }
+
+    // Schlick's approximation
+    let cosine = (-direction).dot(normal).clamp(0.0, 1.0);
+
+    let fresnel = REFLECTANCE
+        + (1.0 - REFLECTANCE) * (1.0 - cosine).powf(5.0);
+
+    // Light that went into the water and got scattered back - deep blue,
+    // slightly brighter where the surface faces the sun
+    let refraction = vec3(0.0, 0.04, 0.08)
+        + vec3(0.0, 0.09, 0.1)
+            * normal.dot(sun_dir).clamp(0.0, 1.0)
+            * light;
+
+    // Glints of the sun, sharper than on other objects
+    let half = (sun_dir - direction).normalize();
+    let specular =
+        normal.dot(half).clamp(0.0, 1.0).powf(200.0)
+            * light;
+
+    refraction.lerp(reflection, fresnel)
+        + Vec3::splat(specular)
+}
+
+/// Returns how much of the fog (see [`Params::fog`]) there is between the
+/// ray's origin and given distance along it, from zero (clear) to one.
+///
+/// With height falloff, the fog's density decays exponentially with height -
+/// integrating that along the ray has a closed form, see:
+/// https://iquilezles.org/articles/fog/
+fn fog(
+    params: &Params,
+    origin: Vec3,
+    direction: Vec3,
+    distance: f32,
+) -> f32 {
+    let density = params.fog.x;
+    let falloff = params.fog.y;
+
+    let optical_depth = if falloff > 0.0 {
+        let dy = direction.y * falloff;
+
+        // (for horizontal rays the density stays constant along the way,
+        // and the formula below would divide by zero)
+        let along = if dy.abs() > 1e-4 {
+            (1.0 - (-distance * dy).exp()) / dy
+        } else {
+            distance
+        };
+
+        density * (-origin.y * falloff).exp() * along
+    } else {
+        density * distance
+    };
+
+    1.0 - (-optical_depth).exp()
+}
+
+/// Returns color of the sky in given direction, including the sun.
+fn sky(direction: Vec3, sun_dir: Vec3) -> Vec3 {
+    let sun = direction.dot(sun_dir).max(0.0).powf(500.0);
+
+    sky_gradient(direction)
+        + vec3(1.0, 0.9, 0.7) * sun * 5.0
+}
+
+/// Returns color of the sky in given direction, without the sun.
+fn sky_gradient(direction: Vec3) -> Vec3 {
+    let horizon = vec3(0.6, 0.75, 0.9);
+    let zenith = vec3(0.15, 0.35, 0.7);
+
+    horizon.lerp(zenith, direction.y.max(0.0).sqrt())
+}
+
+/// Returns light reaching a surface with given normal from the sky (as
+/// opposed to the sun, see [`Params::ambient`]).
+///
+/// Properly, that'd be the sky integrated over the hemisphere around the
+/// normal - here it's approximated by the sky seen along the normal (lifted
+/// up to the horizon, for surfaces facing down), dimmed by how much of the
+/// hemisphere is taken by the ground, which reflects a bit of warm light back.
+fn sky_light(normal: Vec3) -> Vec3 {
+    // Color of the light bounced off the ground
+    const BOUNCE: Vec3 = Vec3::new(0.12, 0.1, 0.08);
+
+    let direction =
+        vec3(normal.x, normal.y.max(0.0), normal.z);
+
+    // (surfaces facing straight down see just the ground)
+    let direction = if direction.length_squared() > 1e-6 {
+        direction.normalize()
+    } else {
+        Vec3::Y
+    };
+
+    let facing_sky = 0.5 + 0.5 * normal.y;
+
+    sky_gradient(direction) * facing_sky
+        + BOUNCE * (1.0 - facing_sky)
+}
+
+/// Techniques of computing shadows, see [`shadow_model()`].
+mod shadows {
+    /// No shadows at all - the cheapest option.
+    pub const NONE: u32 = 0;
+
+    /// Soft shadows, marched through the entire scene (see
+    /// [`super::soft_shadow()`]).
+    pub const SOFT: u32 = 1;
+
+    /// Shadows marched over the scene's heightfield (see
+    /// [`super::heightfield_shadow()`]) - much cheaper than [`SOFT`], but
+    /// works only for terrain-like scenes.
+    pub const HEIGHTFIELD: u32 = 2;
+}
+
+/// Returns which technique of computing shadows (see [`shadows`]) given
+/// scene uses.
+fn shadow_model(scene_id: u32) -> u32 {
+    match scene_id {
+        5 => shadows::HEIGHTFIELD,
+        _ => shadows::NONE,
+    }
+}
+
+/// Returns where a secondary ray (e.g. a shadow or a reflection) leaving the
+/// surface at `point` should start, so that it doesn't hit the very surface
+/// it's leaving.
+///
+/// [`march()`] stops once it's close enough to the surface (see
+/// [`Params::epsilon`]), not exactly on it - so `point` might lie on either
+/// side of the surface, up to that threshold away; pushing the origin along
+/// the `normal` by twice the threshold gets it safely outside. Since the
+/// threshold grows with `distance` (from the camera to `point`), so does the
+/// offset.
+fn secondary_origin(
+    params: &Params,
+    point: Vec3,
+    normal: Vec3,
+    distance: f32,
+    pixel_footprint: f32,
+) -> Vec3 {
+    // Offset used for surfaces right in front of the camera, where the
+    // threshold alone would be too small to overcome numerical errors
+    const MIN_BIAS: f32 = 0.01;
+
+    let threshold = if params.epsilon > 0.0 {
+        params.epsilon
+    } else {
+        distance * pixel_footprint
+    };
+
+    point + normal * (2.0 * threshold).max(MIN_BIAS)
+}
+
+/// Returns how much of the light coming from `light_dir` reaches `point`
+/// (`0..1`), according to the scene's [`shadow_model()`].
+///
+/// `steps` says how many steps the shadow ray can take (see
+/// [`Params::shadow_steps`]) - zero disables shadows.
+fn shadow(
+    scene_id: u32,
+    time: f32,
+    hot: &Hot,
+    point: Vec3,
+    light_dir: Vec3,
+    pixel_footprint: f32,
+    steps: u32,
+) -> f32 {
+    if steps == 0 {
+        return 1.0;
+    }
+
+    // (heightfields don't know anything about relief mode, so the inverted
+    // scene gets traced in full)
+    let model = if scene_id & RELIEF != 0 {
+        shadows::SOFT
+    } else {
+        shadow_model(scene_id)
+    };
+
+    match model {
+        shadows::SOFT => soft_shadow(
+            scene_id,
+            time,
+            hot,
+            point,
+            light_dir,
+            pixel_footprint,
+            steps,
+        ),
+
+        // Heightfield's steps grow geometrically, so it needs fewer of them
+        // to cover the same distance
+        shadows::HEIGHTFIELD => heightfield_shadow(
+            scene_id,
+            time,
+            point,
+            light_dir,
+            pixel_footprint,
+            steps * 3 / 4,
+        ),
+
+        _ => 1.0,
+    }
+}
+
+/// Height of the terrain in given scene at given point of the XZ plane (see
+/// [`shadows::HEIGHTFIELD`]); negative infinity for scenes that aren't
+/// heightfields.
+fn heightfield(
+    scene_id: u32,
+    time: f32,
+    point: Vec2,
+    pixel_size: f32,
+) -> f32 {
+    match scene_id {
+        5 => sdf::ocean_height(time, point, pixel_size),
+        _ => f32::NEG_INFINITY,
+    }
+}
+
+/// Like [`soft_shadow()`], but instead of marching through the entire scene,
+/// it follows the light over the scene's [`heightfield()`] - comparing the
+/// height of the ray with the height of the terrain below it, similarly to
+/// horizon mapping.
+///
+/// It's a lot cheaper (the heightfield is usually much simpler than the
+/// scene and there's no need to find the closest surface), but it only sees
+/// the terrain.
+fn heightfield_shadow(
+    scene_id: u32,
+    time: f32,
+    point: Vec3,
+    light_dir: Vec3,
+    pixel_footprint: f32,
+    steps: u32,
+) -> f32 {
+    // The higher, the sharper the shadows
+    const SHARPNESS: f32 = 8.0;
+
+    if light_dir.y <= 0.0 {
+        // Sun is below the horizon
+        return 0.0;
+    }
+
+    // Points below the terrain (e.g. underwater ones) are lit by other means,
+    // see `caustics()`
+    let ground =
+        heightfield(scene_id, time, point.xz(), 0.0);
+
+    if point.y < ground - 0.1 {
+        return 1.0;
+    }
+
+    let mut light: f32 = 1.0;
+    let mut distance = 0.1;
+
+    for _ in 0..steps {
+        let ray = point + light_dir * distance;
+
+        let height = ray.y
+            - heightfield(
+                scene_id,
+                time,
+                ray.xz(),
+                distance * pixel_footprint,
+            );
+
+        light = light.min(SHARPNESS * height / distance);
+
+        if light < 0.001 {
+            break;
+        }
+
+        // Shadows cast by far-away terrain are blurry anyway, so we can afford
+        // taking larger and larger steps
+        distance *= 1.3;
+    }
+
+    light.clamp(0.0, 1.0)
+}
+
+/// Height of the water's surface in given scene, or negative infinity if the
+/// scene doesn't contain any water.
+fn water_level(scene_id: u32) -> f32 {
+    match scene_id {
+        5 => 0.0,
+        _ => f32::NEG_INFINITY,
+    }
+}
+
+/// Returns how much additional light reaches an underwater `point`, focused
+/// there by the waves - the shimmering pattern seen at the bottom of pools.
+///
+/// It's not derived from the actual waves (that would require tracing lots of
+/// rays through the surface), just a pattern that looks alike, projected from
+/// the surface along the direction of the light; zero above water.
+fn caustics(
+    scene_id: u32,
+    time: f32,
+    point: Vec3,
+    light_dir: Vec3,
+) -> f32 {
+    const ITERATIONS: u32 = 5;
+    const INTENSITY: f32 = 0.005;
+
+    let depth = water_level(scene_id) - point.y;
+
+    if depth <= 0.0 || light_dir.y <= 0.0 {
+        return 0.0;
+    }
+
+    // Where the light reaching this point has entered the water
+    let surface =
+        point.xz() + light_dir.xz() / light_dir.y * depth;
+
+    // Iterated domain warping, producing a web of thin, bright lines
+    let p = surface * 0.5 - 250.0;
+    let mut i = p;
+    let mut c = 1.0;
+
+    for n in 0..ITERATIONS {
+        let t = time * (1.0 - 3.5 / (n + 1) as f32);
+
+        i = p + vec2(
+            (t - i.x).cos() + (t + i.y).sin(),
+            (t - i.y).sin() + (t + i.x).cos(),
+        );
+
+        c += 1.0
+            / vec2(
+                p.x / ((i.x + t).sin() / INTENSITY),
+                p.y / ((i.y + t).cos() / INTENSITY),
+            )
+            .length();
+    }
+
+    let c = 1.17 - (c / ITERATIONS as f32).powf(1.4);
+    let c = c.abs().powf(8.0);
+
+    // Caustics fade in just below the surface (where the waves haven't
+    // focused the light yet) and fade out in the depths
+    c * smoothstep(0.0, 0.5, depth) * (-0.3 * depth).exp()
+}
+
+/// Returns how much light passes through the object at `point` (with given
+/// normal) when coming from given direction (`0..1`).
+///
+/// It's approximated by checking how thick the object is right behind the
+/// surface, using a couple of samples of the distance field (which is negative
+/// inside of objects) along the inverted normal - and by how much the surface
+/// faces away from the light, since that's where the light shining through
+/// is what stands out (the lit side gets its diffuse lighting anyway).
+fn subsurface(
+    scene_id: u32,
+    time: f32,
+    hot: &Hot,
+    point: Vec3,
+    normal: Vec3,
+    light_dir: Vec3,
+) -> f32 {
+    const SAMPLES: u32 = 5;
+
+    // How deep into the object we look
+    const DEPTH: f32 = 2.0;
+
+    // How quickly the light fades out inside of the object
+    const DENSITY: f32 = 1.5;
+
+    let mut thickness = 0.0;
+
+    for sample in 1..=SAMPLES {
+        let distance =
+            DEPTH * sample as f32 / SAMPLES as f32;
+
+        thickness += (-scene(
+            scene_id,
+            time,
+            hot,
+            point - normal * distance,
+            0.0,
+        ))
+        .max(0.0);
+    }
+
+    let back_light = 0.5 - 0.5 * normal.dot(light_dir);
+
+    back_light
+        * (-thickness * DENSITY * DEPTH / SAMPLES as f32)
+            .exp()
+}
+
+/// Returns how much light reaches `origin` from given direction - 1.0 when
+/// nothing's in the way, 0.0 when it's fully occluded, and values in between
+/// for rays passing close to a surface, which gives the shadows soft edges.
+fn soft_shadow(
+    scene_id: u32,
+    time: f32,
+    hot: &Hot,
+    origin: Vec3,
+    direction: Vec3,
+    pixel_footprint: f32,
+    steps: u32,
+) -> f32 {
+    // The higher, the sharper the shadows
+    const SHARPNESS: f32 = 8.0;
+
+    let mut light: f32 = 1.0;
+    let mut distance = 0.05;
+
+    for _ in 0..steps {
+        let step = scene(
+            scene_id,
+            time,
+            hot,
+            origin + direction * distance,
+            distance * pixel_footprint,
+        );
+
+        light = light.min(SHARPNESS * step / distance);
+
+        if light < 0.001 || distance > 50.0 {
+            break;
+        }
+
+        distance += step.clamp(0.05, 2.0);
+    }
+
+    light.clamp(0.0, 1.0)
+}
+
+/// Maps signed distance into a color, for [`debug_view::SLICE`] and the 2D
+/// mode.
+///
+/// Outside of objects is orange, inside is blue, there's an isoline every half
+/// a unit and the surface itself (i.e. where distance is zero) is white.
+fn slice_color(distance: f32) -> Vec3 {
+    let color = if distance > 0.0 {
+        vec3(0.9, 0.6, 0.3)
+    } else {
+        vec3(0.4, 0.7, 0.85)
+    };
+
+    // Make the colors darker closer to the surface, so that it's easy to tell
+    // which way the field grows
+    let color =
+        color * (1.0 - 0.8 * (-2.0 * distance.abs()).exp());
+
+    let color =
+        color * (0.8 + 0.2 * (distance * 4.0 * PI).cos());
+
+    color.lerp(
+        Vec3::ONE,
+        1.0 - smoothstep(0.0, 0.05, distance.abs()),
+    )
+}
+
+/// Returns color of surface with given normal, as seen by camera located at
+/// `camera` and looking at `target`, according to the matcap (see
+/// [`shading::MATCAP`]).
+fn matcap_color(
+    matcap: &Image!(2D, type = f32, sampled),
+    camera: Vec3,
+    target: Vec3,
+    normal: Vec3,
+) -> Vec3 {
+    let camera = sdf_playground_common::look_at(
+        camera,
+        target,
+        Vec3::Y,
+    );
+
+    // Matcap is a picture of a sphere, so its center corresponds to surfaces
+    // facing the camera and its edges - to surfaces facing sideways
+    let uv = vec2(
+        normal.dot(camera.x_axis),
+        -normal.dot(camera.y_axis),
+    ) * 0.5
+        + 0.5;
+
+    let texel = (uv * MATCAP_SIZE as f32).as_ivec2().clamp(
+        IVec2::ZERO,
+        IVec2::splat(MATCAP_SIZE as i32 - 1),
+    );
+
+    matcap.fetch(texel).xyz()
+}
+
+/// Shades surface with given albedo and normal in a cartoon-like style (see
+/// [`shading::TOON`]).
+fn toon(
+    params: &Params,
+    albedo: Vec3,
+    normal: Vec3,
+    ray_direction: Vec3,
+    sun_cosine: f32,
+) -> Vec3 {
+    // Diffuse lighting, but instead of a smooth gradient we've got a couple of
+    // flat bands - the darkest one is kept a bit above black, so that the
+    // unlit side of objects doesn't disappear
+    let bands = params.toon_bands.max(2) as f32;
+
+    let diffuse = ((sun_cosine * bands).floor()
+        / (bands - 1.0))
+        .min(1.0);
+
+    let diffuse = albedo * (0.2 + 0.8 * diffuse);
+
+    // Specular highlight - same as in the regular shading, but hard-edged
+    let specular = if sun_cosine.powf(50.0) > 0.5 {
+        Vec3::ONE
+    } else {
+        Vec3::ZERO
+    };
+
+    // Rim light - pixels where the surface turns away from the camera, i.e.
+    // close to the object's silhouette
+    let facing = normal.dot(-ray_direction).clamp(0.0, 1.0);
+
+    let rim = if facing < params.rim_width {
+        Vec3::splat(params.rim)
+    } else {
+        Vec3::ZERO
+    };
+
+    diffuse + specular + rim
+}
+
+/// Maps color into what's shown by [`debug_view::EXPOSURE`] at given pixel.
+fn exposure_color(
+    color: Vec3,
+    pixel: Vec2,
+    time: f32,
+) -> Vec3 {
+    let luminance = color.dot(vec3(0.2126, 0.7152, 0.0722));
+
+    if luminance <= 1.0 {
+        return Vec3::splat(luminance);
+    }
+
+    // Moving diagonal stripes (zebra), like in camera viewfinders - they make
+    // the overexposed areas stand out even when they're tiny
+    let stripe = (pixel.x + pixel.y) / 8.0 - time * 2.0;
+
+    if stripe - stripe.floor() < 0.5 {
+        return Vec3::ZERO;
+    }
+
+    // Dark red when just a bit too bright, white when three stops (i.e. eight
+    // times) over
+    palette::heat(0.25 + luminance.log2() / 4.0)
+}
+
+/// Simplified version of [`main_fs()`] meant for running on the CPU - it
+/// marches the same scenes and computes the same normals, but shades them with
+/// plain diffuse lighting, ignoring everything else (shadows, water, shading
+/// models, debug views etc.).
+///
+/// That makes it independent of textures and GPU drivers, so it renders the
+/// same image everywhere - which is what the application's `--verify` mode
+/// needs.
+pub fn reference(params: &Params, pos: Vec2) -> Vec4 {
+    reference_with::<false>(params, pos)
+}
+
+/// Same as [`reference()`], but with distances and shading intermediates
+/// rounded to half precision (see [`half()`]) - to check how much of the image
+/// would survive marching in `f16`, which is what mobile-class GPUs are the
+/// fastest at.
+pub fn reference_half(params: &Params, pos: Vec2) -> Vec4 {
+    reference_with::<true>(params, pos)
+}
+
+fn reference_with<const HALF: bool>(
+    params: &Params,
+    pos: Vec2,
+) -> Vec4 {
+    let q =
+        |value: f32| if HALF { half(value) } else { value };
+
+    let uv = pos
+        / vec2(params.width as f32, params.height as f32);
+    let camera = camera(params);
+    let pixel_footprint = params.pixel_footprint();
+
+    let direction = sdf_playground_common::direction(
+        camera,
+        params.target(),
+        params.frustum_uv(uv),
+    );
+
+    let hit = march_with::<HALF>(
+        params.field(),
+        params.scene_time(),
+        &params.hot,
+        camera,
+        direction,
+        pixel_footprint,
+        params.epsilon,
+        params.steps,
+        params.near,
+        params.far,
+    );
+
+    if !hit.point.is_finite() {
+        return params.background_color();
+    }
+
+    let normal = normal(
+        params.field(),
+        params.scene_time(),
+        &params.hot,
+        hit.point,
+        hit.distance * pixel_footprint,
+    );
+
+    let normal =
+        vec3(q(normal.x), q(normal.y), q(normal.z));
+
+    let sun_dir = (SUN - hit.point).normalize();
+
+    let diffuse = q(normal.dot(sun_dir)).clamp(0.0, 1.0);
+
+    (albedo(params.scene, hit.point)
+        * q(0.1 + 0.9 * diffuse))
+    .extend(1.0)
+}
+
+/// Rounds given value to the nearest one representable in half precision
+/// (`f16`: 10 bits of mantissa, 5 bits of exponent) - that's how we emulate
+/// half-precision math, since rust-gpu can't emit `f16` (yet).
+pub fn half(value: f32) -> f32 {
+    if !value.is_finite() {
+        return value;
     }
 
-    pub fn rect(p: Vec3, b: Vec3) -> f32 {
-        let q = p.abs() - b;
+    let bits = value.to_bits();
+    let exponent = ((bits >> 23) & 0xff) as i32 - 127;
 
-        q.max(Vec3::ZERO).length()
-            + q.max_element().min(0.0)
+    // Below the smallest normal `f16`, values are spaced evenly by 2^-24
+    if exponent < -14 {
+        return (value * 16_777_216.0).round()
+            / 16_777_216.0;
     }
 
-    /// Thanks to: https://www.shadertoy.com/view/MdXyzX.
-    pub fn ocean(time: f32, point: Vec3) -> f32 {
-        // Origin (the point at (0,0)) contains a ripple-artifact that doesn't
-        // look great - to avoid it, let's offset the ocean
-        let point = point + vec3(128.0, 0.0, 128.0);
+    // Round the 23-bit mantissa to 10 bits (to nearest, ties to even)
+    let bits = bits + 0xfff + ((bits >> 13) & 1);
+    let value = f32::from_bits(bits & !0x1fff);
+
+    // Largest finite `f16`
+    if value.abs() > 65_504.0 {
+        if value > 0.0 {
+            f32::INFINITY
+        } else {
+            f32::NEG_INFINITY
+        }
+    } else {
+        value
+    }
+}
 
-        // Also, the default animation speed is kinda slow, so let's speed it up
-        let time = 2.0 * time;
+/// Hermite interpolation between 0.0 and 1.0, as in GLSL.
+fn smoothstep(edge0: f32, edge1: f32, x: f32) -> f32 {
+    let t = ((x - edge0) / (edge1 - edge0)).clamp(0.0, 1.0);
 
-        // ---
+    t * t * (3.0 - 2.0 * t)
+}
 
-        let mut h_sum = 0.0;
-        let mut h_weight = 0.0;
+/// Follows a ray from origin through direction and returns the closest surface
+/// hit by that ray.
+///
+/// Intuitively, in two dimensions, if `*` marked the origin and `->` marked the
+/// direction, given a scene such as:
+///
+/// ```text
+/// A          B
+///
+/// F    * ->  C  G
+///
+/// E          D
+/// ```
+///
+/// ... `march()` would return the position of `C`.
+///
+/// We consider the surface hit once it's closer than the width of a pixel at
+/// given distance (see `pixel_footprint` in `main_fs()`) - that's as much
+/// detail as we're able to show anyway, so there's no point in marching any
+/// closer; this gives nearby surfaces crisp details while far-away surfaces
+/// don't waste steps.
+///
+/// Alternatively, when `epsilon` is positive, the surface is considered hit
+/// once it's closer than `epsilon` - that's the classic, fixed-epsilon
+/// marching, kept for comparison (see [`Params::epsilon`]).
+///
+/// Marching starts `start` units away from the origin - it's the near clip
+/// plane for most rays, except when we know (from the coarse pre-pass) that
+/// there's nothing in between - and gives up once the ray gets farther than
+/// `far`.
+///
+/// When the ray starts inside an object (e.g. because the camera has flown
+/// into it), the distances are negative and marching would stop right away,
+/// painting the entire screen with the object's interior - so instead we step
+/// through the interior (using the distance's absolute value, which tells how
+/// far the nearest way out is) until we're out of the object, and march from
+/// there as usual; this makes objects look as if they were cut open by the
+/// near clip plane.
+#[allow(clippy::too_many_arguments)]
+pub fn march(
+    scene_id: u32,
+    time: f32,
+    hot: &Hot,
+    origin: Vec3,
+    direction: Vec3,
+    pixel_footprint: f32,
+    epsilon: f32,
+    max_steps: u32,
+    start: f32,
+    far: f32,
+) -> Hit {
+    march_with::<false>(
+        scene_id,
+        time,
+        hot,
+        origin,
+        direction,
+        pixel_footprint,
+        epsilon,
+        max_steps,
+        start,
+        far,
+    )
+}
 
-        let mut wave_pos = point.xz();
-        let mut wave_freq = 1.0;
-        let mut wave_weight = 1.0;
+/// Same as [`march()`], but when `HALF` is true, the distances get rounded to
+/// half precision (see [`half()`]) - both the ones returned by the scene and
+/// the one accumulated along the ray.
+#[allow(clippy::too_many_arguments)]
+fn march_with<const HALF: bool>(
+    scene_id: u32,
+    time: f32,
+    hot: &Hot,
+    origin: Vec3,
+    direction: Vec3,
+    pixel_footprint: f32,
+    epsilon: f32,
+    max_steps: u32,
+    start: f32,
+    far: f32,
+) -> Hit {
+    let q =
+        |value: f32| if HALF { half(value) } else { value };
+    let mut distance = start;
+    let mut prev_step = f32::MAX;
+    let mut lipschitz_violated = false;
+    let mut inside = true;
+    let mut closest = f32::MAX;
 
-        let mut noise = 0.0f32;
+    for steps in 0..max_steps {
+        if distance > far {
+            return Hit {
+                point: Vec3::INFINITY,
+                distance: f32::INFINITY,
+                lipschitz_violated,
+                exhausted: false,
+                steps,
+                closest,
+            };
+        }
 
-        for _ in 0..15 {
-            let wave_dir = vec2(noise.cos(), noise.sin());
+        let point = origin + direction * distance;
+        let step = q(scene(
+            scene_id,
+            time,
+            hot,
+            point,
+            distance * pixel_footprint,
+        ));
 
-            let wave =
-                wave_dir.dot(wave_pos) * wave_freq + time;
+        let threshold = if epsilon > 0.0 {
+            epsilon
+        } else {
+            (distance * pixel_footprint).max(0.0001)
+        };
 
-            let wave_h = (wave.sin() - 1.0).exp();
-            let wave_dh = wave_h * wave.cos();
+        // Still inside the object the ray has started in - keep stepping
+        // through it, and then past its surface on the other side (so that we
+        // don't mistake it for a hit); `max()` makes sure we always move
+        // forward, even when we're right at the surface
+        if inside {
+            if step < 0.0
+                || (distance > start && step < threshold)
+            {
+                distance =
+                    q(distance + step.abs().max(threshold));
+                continue;
+            }
 
-            h_sum += wave_h * wave_weight;
-            h_weight += wave_weight;
+            inside = false;
+        }
 
-            wave_pos -=
-                0.25 * wave_dh * wave_dir * wave_weight;
+        closest = closest.min(step);
 
-            wave_freq *= 1.18;
-            wave_weight *= 0.82;
+        // We've just moved by `prev_step`, so - if the scene is a proper SDF -
+        // the distance couldn't have changed by more than that (modulo some
+        // floating-point slack)
+        if (step - prev_step).abs()
+            > prev_step * 1.01 + 0.001
+        {
+            lipschitz_violated = true;
+        }
 
-            noise += 1234.4321;
+        if step < threshold {
+            return Hit {
+                point,
+                distance,
+                lipschitz_violated,
+                exhausted: false,
+                steps,
+                closest,
+            };
         }
 
-        point.y - (h_sum / h_weight)
+        distance = q(distance + step);
+        prev_step = step;
+    }
+
+    Hit {
+        point: Vec3::INFINITY,
+        distance: f32::INFINITY,
+        lipschitz_violated,
+        exhausted: true,
+        steps: max_steps,
+        closest,
+    }
+}
+
+/// Result of [`march()`].
+pub struct Hit {
+    /// Point where the ray hit the surface or [`Vec3::INFINITY`] if the ray
+    /// didn't hit anything.
+    pub point: Vec3,
+
+    /// Distance from the ray's origin to [`Self::point`].
+    pub distance: f32,
+
+    /// Whether, at any step, the scene's distance changed faster than the ray
+    /// moved - i.e. whether the scene is not 1-Lipschitz along this ray.
+    ///
+    /// When that happens, `march()` can overshoot and miss surfaces (or walk
+    /// right through them), which shows up as holes and noise.
+    pub lipschitz_violated: bool,
+
+    /// Whether we've run out of steps before hitting anything or escaping the
+    /// scene - in that case [`Self::point`] is [`Vec3::INFINITY`], even though
+    /// there might be a surface right in front of us.
+    pub exhausted: bool,
+
+    /// How many steps it took to hit the surface or escape the scene.
+    pub steps: u32,
+
+    /// Smallest distance to the scene encountered along the ray (not counting
+    /// the object the ray has started in) - for rays that miss, it says how
+    /// close they got to hitting something (see [`Params::glow`]).
+    pub closest: f32,
+}
+
+/// Returns the normal of surface at given point.
+///
+/// Intuitively, normal describes the orientation ("rotation") of surface at
+/// given point:
+///
+/// ```text
+///     /-----------/
+///    /    ^      /
+///   /     |     /   (so this boi is "looking up")
+///  /           /
+/// /-----------/
+/// ```
+///
+/// Later we calculate the angle between the surface's normal and the sun, which
+/// allows us to compute how bright that surface should be:
+///
+/// ```text
+///         *  (the sun)
+///
+///
+///     /-----------/
+///    /           /|
+///   /     A     / |
+///  /           /  |
+/// /-----------/ C |
+/// |           |   /
+/// |     B     |  /
+/// |           | /
+/// |           |/
+/// \-----------/
+///
+/// (in this case we'd imagine that `A` is bright, while `B` and `C` are black,
+///  since their normals point totally outside "of" the sun)
+/// ```
+///
+/// `pixel_size` is the same as in [`scene()`].
+pub fn normal(
+    scene_id: u32,
+    time: f32,
+    hot: &Hot,
+    point: Vec3,
+    pixel_size: f32,
+) -> Vec3 {
+    // If the scene supports it, we can get the exact normal using automatic
+    // differentiation (see `dual.rs`) - that's both faster and more precise
+    // than the approach below
+    let grad = scene_dual(
+        scene_id,
+        time,
+        hot,
+        DualVec3::point(point),
+    )
+    .grad;
+
+    if grad.length_squared() > 0.0 && grad.is_finite() {
+        return grad.normalize();
     }
+
+    // Calculating normal is as simple taking the derivative of `scene`, but
+    // since (for our purposes here) that function is closed-form, we do the
+    // next best thing:
+    //
+    // Calculate the gradient and use it to estimate the derivative.
+    //
+    // Instead of probing the scene twice along each axis, we probe it at four
+    // corners of a tetrahedron, which gives the same result in fewer steps¹.
+    //
+    // Also, the farther we are from the camera, the larger area a single pixel
+    // covers - so we scale the probing distance, which avoids shimmering of
+    // tiny details that wouldn't be visible anyway.
+    //
+    // ¹ https://iquilezles.org/articles/normalsSDF/
+    let d = (0.1 * pixel_size).max(0.0001);
+    let k = vec2(1.0, -1.0);
+
+    let g = k.xyy()
+        * scene(
+            scene_id,
+            time,
+            hot,
+            point + k.xyy() * d,
+            pixel_size,
+        )
+        + k.yyx()
+            * scene(
+                scene_id,
+                time,
+                hot,
+                point + k.yyx() * d,
+                pixel_size,
+            )
+        + k.yxy()
+            * scene(
+                scene_id,
+                time,
+                hot,
+                point + k.yxy() * d,
+                pixel_size,
+            )
+        + k.xxx()
+            * scene(
+                scene_id,
+                time,
+                hot,
+                point + k.xxx() * d,
+                pixel_size,
+            );
+
+    g.normalize()
 }
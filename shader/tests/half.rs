@@ -0,0 +1,47 @@
+//! Emulation of half-precision floats, used by `reference_half()`.
+//!
+//! Run with `cargo test -p sdf-playground-shader`.
+
+use sdf_playground_shader::half;
+
+#[test]
+fn representable_values_stay_the_same() {
+    for value in [
+        0.0,
+        1.0,
+        -2.5,
+        0.333_251_95,
+        65_504.0,
+        6.103_515_6e-5,
+    ] {
+        assert_eq!(half(value), value);
+    }
+}
+
+#[test]
+fn values_get_rounded() {
+    // f16 has 10 bits of mantissa, so around 1.0 values are 2^-10 apart
+    assert_eq!(half(1.0 + 0.4 / 1024.0), 1.0);
+    assert_eq!(
+        half(1.0 + 0.6 / 1024.0),
+        1.0 + 1.0 / 1024.0
+    );
+
+    // ... and around 1000.0 they are 0.5 apart, so that small steps along a
+    // long ray get lost
+    assert_eq!(half(1000.0 + 0.2), 1000.0);
+
+    // Ties go to even
+    assert_eq!(half(1.0 + 0.5 / 1024.0), 1.0);
+    assert_eq!(
+        half(1.0 + 1.5 / 1024.0),
+        1.0 + 2.0 / 1024.0
+    );
+}
+
+#[test]
+fn out_of_range_values() {
+    assert_eq!(half(70_000.0), f32::INFINITY);
+    assert_eq!(half(-70_000.0), f32::NEG_INFINITY);
+    assert_eq!(half(1e-9), 0.0);
+}
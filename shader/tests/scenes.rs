@@ -0,0 +1,254 @@
+//! Distances returned by the scenes at a couple of hand-picked points - the
+//! center, the surface and somewhere far away - so that refactoring the
+//! primitives or operators doesn't silently change how the scenes look.
+//!
+//! Run with `cargo test -p sdf-playground-shader`.
+
+use sdf_playground_common::glam::*;
+use sdf_playground_common::{Hot, RELIEF, SCENES};
+use sdf_playground_shader::{
+    bounds, normal, scene, Bounds,
+};
+
+#[track_caller]
+fn assert_distance(
+    scene_id: u32,
+    time: f32,
+    point: Vec3,
+    expected: f32,
+) {
+    let actual =
+        scene(scene_id, time, &Hot::default(), point, 0.0);
+
+    assert!(
+        (actual - expected).abs() < 0.001,
+        "scene {scene_id} at {point} (time = {time}): expected {expected}, got {actual}"
+    );
+}
+
+#[test]
+fn sphere() {
+    assert_distance(1, 0.0, Vec3::ZERO, -5.0);
+    assert_distance(1, 0.0, vec3(5.0, 0.0, 0.0), 0.0);
+    assert_distance(1, 0.0, vec3(0.0, -3.0, 4.0), 0.0);
+    assert_distance(1, 0.0, vec3(0.0, 0.0, 20.0), 15.0);
+}
+
+#[test]
+fn rect() {
+    assert_distance(2, 0.0, Vec3::ZERO, -3.0);
+    assert_distance(2, 0.0, vec3(3.0, 1.0, -2.0), 0.0);
+    assert_distance(2, 0.0, vec3(0.0, 10.0, 0.0), 7.0);
+
+    // Past the corner, the closest point is the corner itself
+    assert_distance(2, 0.0, vec3(7.0, 6.0, 3.0), 5.0);
+}
+
+#[test]
+fn sphere_and_rect() {
+    // At `time = 0.0` the sphere's radius is 4.0, so it's the rect that
+    // bounds the scene along the axes...
+    assert_distance(3, 0.0, Vec3::ZERO, -3.0);
+    assert_distance(3, 0.0, vec3(3.0, 0.0, 0.0), 0.0);
+    assert_distance(3, 0.0, vec3(0.0, 0.0, -10.0), 7.0);
+
+    // ... and the sphere that cuts off the corners
+    assert_distance(
+        3,
+        0.0,
+        vec3(3.0, 3.0, 3.0),
+        27f32.sqrt() - 4.0,
+    );
+
+    // Later the sphere grows to 5.0, still cutting off the corners
+    let time = std::f32::consts::PI / 6.0;
+
+    assert_distance(3, time, Vec3::ZERO, -3.0);
+    assert_distance(
+        3,
+        time,
+        vec3(3.0, 3.0, 3.0),
+        27f32.sqrt() - 5.0,
+    );
+}
+
+#[test]
+fn heart() {
+    // At `time = 0.0` the heart doesn't beat, so it's just a sphere
+    assert_distance(4, 0.0, Vec3::ZERO, -3.0);
+    assert_distance(4, 0.0, vec3(0.0, 3.0, 0.0), 0.0);
+    assert_distance(4, 0.0, vec3(10.0, 0.0, 0.0), 7.0);
+
+    // Points on the axes are unaffected by the beating, the rest moves
+    assert_distance(4, 1.0, vec3(10.0, 0.0, 0.0), 7.0);
+    assert_distance(
+        4,
+        1.0,
+        vec3(1.0, 2.0, 0.5),
+        -0.708_712,
+    );
+}
+
+#[test]
+fn ocean() {
+    // The waves are 0.522 above the origin at `time = 0.0`
+    assert_distance(5, 0.0, Vec3::ZERO, -0.522_064);
+    assert_distance(5, 0.0, vec3(0.0, 0.522_064, 0.0), 0.0);
+    assert_distance(5, 0.0, vec3(0.0, 5.0, 0.0), 4.477_936);
+
+    // ... and the water is bounded by a sphere
+    assert_distance(5, 0.0, vec3(7.0, 0.0, 0.0), 0.0);
+    assert_distance(5, 0.0, vec3(0.0, -7.0, 0.0), 0.0);
+
+    // Far enough from the sphere, the ocean is not computed at all
+    assert_distance(5, 0.0, vec3(0.0, 0.0, 20.0), f32::MAX);
+}
+
+#[test]
+fn mandelbulb() {
+    assert_distance(
+        6,
+        0.0,
+        vec3(0.0, 0.0, 10.0),
+        4.581_454,
+    );
+
+    // Fractal's distance estimate is not a proper SDF inside of it, so let's
+    // just check we're inside
+    for point in [vec3(1.0, 0.0, 0.0), vec3(3.0, 0.0, 0.0)]
+    {
+        assert!(
+            scene(6, 0.0, &Hot::default(), point, 0.0)
+                < 0.0
+        );
+    }
+
+    // Time doesn't matter here
+    assert_distance(
+        6,
+        1.0,
+        vec3(0.0, 0.0, 10.0),
+        4.581_454,
+    );
+}
+
+#[test]
+fn bounds_enclose_scenes() {
+    for scene_id in 1..=SCENES {
+        let bounds =
+            bounds(scene_id).unwrap_or_else(|| {
+                panic!("scene {scene_id} has no bounds")
+            });
+
+        // Points evenly spread over a sphere (a Fibonacci lattice), pushed
+        // onto the bounds and a bit past them
+        for idx in 0..500 {
+            let z = 1.0 - 2.0 * (idx as f32 + 0.5) / 500.0;
+            let angle = idx as f32 * 2.399_963;
+            let r = (1.0 - z * z).sqrt();
+            let dir =
+                vec3(r * angle.cos(), z, r * angle.sin());
+
+            let point = match bounds {
+                Bounds::Sphere { radius } => dir * radius,
+                Bounds::Cuboid { half_size } => {
+                    dir / (dir.abs() / half_size)
+                        .max_element()
+                }
+            } * 1.01;
+
+            for time in [0.0, 0.3, 1.0, 2.5] {
+                let distance = scene(
+                    scene_id,
+                    time,
+                    &Hot::default(),
+                    point,
+                    0.0,
+                );
+
+                assert!(
+                    distance > 0.0,
+                    "scene {scene_id} sticks out of its bounds at {point} \
+                     (time = {time}): {distance}"
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn hot_variables() {
+    let point = vec3(2.5, 2.5, 2.5);
+    let mut hot = Hot::default();
+
+    // Untweaked variables keep their defaults...
+    assert_eq!(
+        scene(3, 0.5, &hot, point, 0.0),
+        scene(3, 0.5, &Hot::default(), point, 0.0),
+    );
+
+    // ... while tweaked ones replace them (scene 3 pulses at `pulse_speed`,
+    // so zero stops it at the sphere's base radius of 4.0)
+    assert!(hot.set(Hot::key("pulse_speed"), 0.0));
+
+    let actual = scene(3, 0.5, &hot, point, 0.0);
+    let expected = point.length() - 4.0;
+
+    assert!(
+        (actual - expected).abs() < 0.001,
+        "expected {expected}, got {actual}"
+    );
+}
+
+#[test]
+fn relief() {
+    // Relief mode swaps the inside with the outside...
+    assert_distance(1 | RELIEF, 0.0, Vec3::ZERO, 5.0);
+    assert_distance(
+        1 | RELIEF,
+        0.0,
+        vec3(0.0, 0.0, 20.0),
+        -15.0,
+    );
+    assert_distance(
+        3 | RELIEF,
+        0.0,
+        vec3(3.0, 0.0, 0.0),
+        0.0,
+    );
+
+    // ... and flips the normals (both the exact ones and the ones estimated
+    // from finite differences, as in the mandelbulb)
+    for (scene_id, point) in [
+        (1, vec3(0.0, 5.0, 0.0)),
+        (4, vec3(0.0, 3.0, 0.0)),
+        (6, vec3(0.0, 0.0, 10.0)),
+    ] {
+        let regular = normal(
+            scene_id,
+            0.0,
+            &Hot::default(),
+            point,
+            0.0,
+        );
+        let inverted = normal(
+            scene_id | RELIEF,
+            0.0,
+            &Hot::default(),
+            point,
+            0.0,
+        );
+
+        assert!(
+            (regular + inverted).length() < 0.001,
+            "scene {scene_id}: {regular} vs {inverted}"
+        );
+    }
+}
+
+#[test]
+fn unknown_scene() {
+    assert_distance(0, 0.0, Vec3::ZERO, f32::MAX);
+    assert_distance(SCENES + 1, 0.0, Vec3::ZERO, f32::MAX);
+    assert_eq!(bounds(SCENES + 1), None);
+}